@@ -2,3 +2,15 @@ use anchor_lang::prelude::*;
 
 #[constant]
 pub const SEED: &str = "anchor";
+
+// Royalties a skin can ask on secondary sales, 25%
+#[constant]
+pub const MAX_SELLER_FEE_BASIS_POINTS: u16 = 2500;
+
+// Metaplex takes 5 creators, the authority is always one
+#[constant]
+pub const MAX_CREATORS: usize = 4;
+
+// Skins a recipe takes, each one burned in its own transaction
+#[constant]
+pub const MAX_RECIPE_INGREDIENTS: usize = 4;
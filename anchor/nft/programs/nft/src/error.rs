@@ -2,6 +2,44 @@ use anchor_lang::prelude::*;
 
 #[error_code]
 pub enum ErrorCode {
-    #[msg("Custom error message")]
-    CustomError,
+    #[msg("The listing is paid in an spl token, its mint and token accounts are missing")]
+    PaymentAccountsMissing,
+    #[msg("The payment accounts don't match the listing")]
+    WrongPaymentAccounts,
+    #[msg("Not a collection made by create_collection")]
+    WrongCollection,
+    #[msg("The creator is not registered or is suspended")]
+    UnauthorizedCreator,
+    #[msg("Royalties are capped at MAX_SELLER_FEE_BASIS_POINTS")]
+    InvalidRoyalty,
+    #[msg("Creator shares must sum to 100, include the signer, have no duplicate and at most MAX_CREATORS wallets")]
+    InvalidCreatorSplit,
+    #[msg("The sale has to end after it starts")]
+    InvalidSaleWindow,
+    #[msg("The sale is paused")]
+    SalePaused,
+    #[msg("The sale has not started or is over")]
+    SaleNotActive,
+    #[msg("This wallet bought all the prints it can")]
+    WalletLimitReached,
+    #[msg("Early access, the wallet is not on the allowlist")]
+    NotOnAllowlist,
+    #[msg("Every edition of this skin is printed")]
+    SoldOut,
+    #[msg("A skin needs a supply of at least 1")]
+    InvalidSupply,
+    #[msg("The edition marker is not the one of the next edition")]
+    EditionMarkerMismatch,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("A recipe takes 1 to MAX_RECIPE_INGREDIENTS other skins, each once with a count")]
+    InvalidRecipe,
+    #[msg("Not an ingredient of the recipe, or not as many burned yet")]
+    RecipeMismatch,
+    #[msg("Only the skins of the program can be traded")]
+    NotASkin,
+    #[msg("The price of the listing is not the one expected")]
+    PriceMismatch,
+    #[msg("The creator accounts don't match the creators of the skin")]
+    WrongCreatorAccounts,
 }
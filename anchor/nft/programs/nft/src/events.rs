@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+// Logged as "Program data: <base64>", the game replays them to follow the shop without scanning accounts
+
+#[event]
+pub struct CollectionCreated {
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+    pub name: String,
+    pub symbol: String,
+}
+
+#[event]
+pub struct SkinCreated {
+    pub master_mint: Pubkey,
+    pub creator: Pubkey,
+    pub collection_mint: Pubkey,
+    pub name: String,
+    pub uri: String,
+    pub max_supply: u64,
+    pub seller_fee_basis_points: u16,
+}
+
+#[event]
+pub struct SkinPurchased {
+    pub master_mint: Pubkey,
+    pub edition_mint: Pubkey,
+    pub edition: u64,
+    pub buyer: Pubkey,
+    // Lamports, or base units of payment_mint
+    pub price: u64,
+    pub payment_mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct SkinFused {
+    pub upgrade_mint: Pubkey,
+    pub edition_mint: Pubkey,
+    pub edition: u64,
+    pub player: Pubkey,
+}
+
+#[event]
+pub struct SkinListed {
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct SkinDelisted {
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct SkinTraded {
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    // Part of the price paid to the creators
+    pub royalties: u64,
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::{program::Nft, Config};
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    // Only the upgrade authority of the program can
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Nft>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAdmin<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.admin = admin;
+    config.bump = ctx.bumps.config;
+    msg!("Admin is {}", admin);
+
+    Ok(())
+}
+
+pub fn set_admin(ctx: Context<SetAdmin>, new_admin: Pubkey) -> Result<()> {
+    ctx.accounts.config.admin = new_admin;
+    msg!("Admin is {}", new_admin);
+
+    Ok(())
+}
@@ -12,6 +12,8 @@ use anchor_spl::{
     token::{mint_to, Mint, MintTo, Token, TokenAccount},
 };
 
+use crate::events::CollectionCreated;
+
 #[derive(Accounts)]
 pub struct CreateCollection<'info> {
     #[account(mut)]
@@ -93,7 +95,7 @@ pub fn create_collection(ctx: &Context<CreateCollection>, name: String, symbol:
     msg!("Collection NFT minted!");
 
     let creator = vec![Creator {
-        address: ctx.accounts.mint_authority.key().clone(),
+        address: ctx.accounts.mint_authority.key(),
         verified: true,
         share: 100,
     }];
@@ -111,8 +113,8 @@ pub fn create_collection(ctx: &Context<CreateCollection>, name: String, symbol:
         },
         CreateMetadataAccountV3InstructionArgs {
             data: DataV2 {
-                name: name,
-                symbol: symbol,
+                name: name.clone(),
+                symbol: symbol.clone(),
                 uri: "".to_owned(),
                 seller_fee_basis_points: 0,
                 creators: Some(creator),
@@ -146,5 +148,12 @@ pub fn create_collection(ctx: &Context<CreateCollection>, name: String, symbol:
     master_edition_account.invoke_signed(signer_seeds)?;
     msg!("Master Edition Account created");
 
+    emit!(CollectionCreated {
+        mint: ctx.accounts.mint.key(),
+        creator: ctx.accounts.creator.key(),
+        name,
+        symbol,
+    });
+
     Ok(())
 }
@@ -4,18 +4,38 @@ use anchor_spl::metadata::{mpl_token_metadata::{
         CreateMasterEditionV3Cpi, CreateMasterEditionV3CpiAccounts,
         CreateMasterEditionV3InstructionArgs, CreateMetadataAccountV3Cpi,
         CreateMetadataAccountV3CpiAccounts, CreateMetadataAccountV3InstructionArgs,
+        SignMetadataCpi, SignMetadataCpiAccounts,
+        VerifySizedCollectionItemCpi, VerifySizedCollectionItemCpiAccounts,
     }, types::{Collection, Creator, DataV2}
 }, MasterEditionAccount, MetadataAccount};
+
+use crate::{error::ErrorCode, events::SkinCreated, CreatorRecord, SkinListing, MAX_CREATORS, MAX_SELLER_FEE_BASIS_POINTS};
 use anchor_spl::{
     associated_token::AssociatedToken,
     metadata::Metadata,
     token::{mint_to, Mint, MintTo, Token, TokenAccount},
 };
 
+// A wallet getting part of the royalties, shares sum to 100
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CreatorShare {
+    pub address: Pubkey,
+    pub share: u8,
+}
+
 #[derive(Accounts)]
 pub struct CreateNFT<'info> {
     #[account(mut)]
     pub creator: Signer<'info>,
+
+    // Only the wallets the admin registered, and not suspended
+    #[account(
+        seeds = [b"creator", creator.key().as_ref()],
+        bump,
+        constraint = CreatorRecord::is_active(&creator_record) @ ErrorCode::UnauthorizedCreator,
+    )]
+    /// CHECK: owner and data, in is_active
+    pub creator_record: UncheckedAccount<'info>,
     #[account(
         init,
         payer = creator,
@@ -66,8 +86,47 @@ pub struct CreateNFT<'info> {
     )]
     pub master_edition: UncheckedAccount<'info>,
 
+    // Made by create_collection, told apart by its metadata and its sized master edition
+    // Its mint authority moved to the master edition when create_collection made it
     #[account(mut)]
-    pub collection_mint: Account<'info, Mint>,
+    pub collection_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            collection_mint.key().as_ref()
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump,
+        constraint = collection_metadata.update_authority == mint_authority.key() @ ErrorCode::WrongCollection,
+        constraint = collection_metadata.collection_details.is_some() @ ErrorCode::WrongCollection,
+    )]
+    pub collection_metadata: Box<Account<'info, MetadataAccount>>,
+
+    #[account(
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            collection_mint.key().as_ref(),
+            b"edition",
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump,
+        constraint = collection_master_edition.max_supply == Some(0) @ ErrorCode::WrongCollection,
+    )]
+    pub collection_master_edition: Box<Account<'info, MasterEditionAccount>>,
+
+    // At the price given, paid to the creator until set_price
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + SkinListing::INIT_SPACE,
+        seeds = [b"listing", mint.key().as_ref()],
+        bump,
+    )]
+    pub listing: Account<'info, SkinListing>,
 
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
@@ -75,7 +134,36 @@ pub struct CreateNFT<'info> {
     pub token_metadata_program: Program<'info, Metadata>,
 }
 
-pub fn create_nft(ctx: &Context<CreateNFT>, name: String, symbol: String, metadata_uri: String, supply: u64) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub fn create_nft(
+    ctx: &mut Context<CreateNFT>,
+    name: String,
+    symbol: String,
+    metadata_uri: String,
+    supply: u64,
+    seller_fee_basis_points: u16,
+    creators: Vec<CreatorShare>,
+    price: u64,
+    payment_mint: Option<Pubkey>,
+) -> Result<()> {
+    // A master with a max supply of 0 can't print, that is a collection
+    require!(supply > 0, ErrorCode::InvalidSupply);
+    require!(seller_fee_basis_points <= MAX_SELLER_FEE_BASIS_POINTS, ErrorCode::InvalidRoyalty);
+    // Empty gives everything to the signer
+    let creators = if creators.is_empty() {
+        vec![CreatorShare { address: ctx.accounts.creator.key(), share: 100 }]
+    } else {
+        creators
+    };
+    require!(creators.len() <= MAX_CREATORS, ErrorCode::InvalidCreatorSplit);
+    require!(creators.iter().map(|c| c.share as u32).sum::<u32>() == 100, ErrorCode::InvalidCreatorSplit);
+    // The signer is verified below, it has to be in the split
+    require!(creators.iter().any(|c| c.address == ctx.accounts.creator.key()), ErrorCode::InvalidCreatorSplit);
+    require!(
+        creators.iter().enumerate().all(|(i, c)| creators[..i].iter().all(|other| other.address != c.address)),
+        ErrorCode::InvalidCreatorSplit
+    );
+
     let metadata = &ctx.accounts.metadata.to_account_info();
     let master_edition = &ctx.accounts.master_edition.to_account_info();
     let mint = &ctx.accounts.mint.to_account_info();
@@ -98,11 +186,17 @@ pub fn create_nft(ctx: &Context<CreateNFT>, name: String, symbol: String, metada
     mint_to(cpi_ctx, 1)?;
     msg!("NFT minted!");
 
-    let creator = vec![Creator {
+    // The authority stays first, the shop finds the skins by it, the split gets the royalties
+    let mut creator = vec![Creator {
         address: ctx.accounts.mint_authority.key(),
         verified: true,
-        share: 100,
+        share: 0,
     }];
+    creator.extend(creators.iter().map(|c| Creator {
+        address: c.address,
+        verified: false,
+        share: c.share,
+    }));
 
     let metadata_account = CreateMetadataAccountV3Cpi::new(
         spl_metadata_program,
@@ -117,10 +211,10 @@ pub fn create_nft(ctx: &Context<CreateNFT>, name: String, symbol: String, metada
         },
         CreateMetadataAccountV3InstructionArgs {
             data: DataV2 {
-                name: name,
-                symbol: symbol,
-                uri: metadata_uri,
-                seller_fee_basis_points: 0,
+                name: name.clone(),
+                symbol,
+                uri: metadata_uri.clone(),
+                seller_fee_basis_points,
                 creators: Some(creator),
                 collection: Some(Collection {
                     verified: false,
@@ -134,6 +228,16 @@ pub fn create_nft(ctx: &Context<CreateNFT>, name: String, symbol: String, metada
     );
     metadata_account.invoke_signed(signer_seeds)?;
 
+    // The creator signed the transaction, this verifies it in the metadata
+    SignMetadataCpi::new(
+        spl_metadata_program,
+        SignMetadataCpiAccounts {
+            metadata,
+            creator: payer,
+        },
+    )
+    .invoke()?;
+
     let master_edition_account = CreateMasterEditionV3Cpi::new(
         spl_metadata_program,
         CreateMasterEditionV3CpiAccounts {
@@ -153,5 +257,50 @@ pub fn create_nft(ctx: &Context<CreateNFT>, name: String, symbol: String, metada
     );
     master_edition_account.invoke_signed(signer_seeds)?;
 
+    // Our authority is the update authority of the collection, it verifies the skin into it
+    let collection_mint = &ctx.accounts.collection_mint.to_account_info();
+    let collection_metadata = &ctx.accounts.collection_metadata.to_account_info();
+    let collection_master_edition = &ctx.accounts.collection_master_edition.to_account_info();
+    let verify = VerifySizedCollectionItemCpi::new(
+        spl_metadata_program,
+        VerifySizedCollectionItemCpiAccounts {
+            metadata,
+            collection_authority: authority,
+            payer,
+            collection_mint,
+            collection: collection_metadata,
+            collection_master_edition_account: collection_master_edition,
+            collection_authority_record: None,
+        },
+    );
+    verify.invoke_signed(signer_seeds)?;
+    msg!("Verified in collection {}", ctx.accounts.collection_mint.key());
+
+    let listing = &mut ctx.accounts.listing;
+    listing.master_mint = ctx.accounts.mint.key();
+    listing.creator = ctx.accounts.creator.key();
+    listing.treasury = ctx.accounts.creator.key();
+    listing.price = price;
+    listing.payment_mint = payment_mint;
+    listing.bump = ctx.bumps.listing;
+    listing.revision = 0;
+    listing.sale_start = 0;
+    listing.sale_end = 0;
+    listing.paused = false;
+    listing.max_per_wallet = 0;
+    listing.allowlist_root = None;
+    listing.public_start = 0;
+    listing.editions_printed = 0;
+
+    emit!(SkinCreated {
+        master_mint: ctx.accounts.mint.key(),
+        creator: ctx.accounts.creator.key(),
+        collection_mint: ctx.accounts.collection_mint.key(),
+        name,
+        uri: metadata_uri,
+        max_supply: supply,
+        seller_fee_basis_points,
+    });
+
     Ok(())
 }
@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, CreatorRecord, CreatorStatus};
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddCreator<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + CreatorRecord::INIT_SPACE,
+        seeds = [b"creator", wallet.as_ref()],
+        bump,
+    )]
+    pub creator_record: Account<'info, CreatorRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCreatorStatus<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"creator", creator_record.wallet.as_ref()],
+        bump = creator_record.bump,
+    )]
+    pub creator_record: Account<'info, CreatorRecord>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveCreator<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin,
+    )]
+    pub config: Account<'info, Config>,
+
+    // Rent goes back to the admin
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"creator", creator_record.wallet.as_ref()],
+        bump = creator_record.bump,
+    )]
+    pub creator_record: Account<'info, CreatorRecord>,
+}
+
+pub fn add_creator(ctx: Context<AddCreator>, wallet: Pubkey) -> Result<()> {
+    let record = &mut ctx.accounts.creator_record;
+    record.wallet = wallet;
+    record.status = CreatorStatus::Active;
+    record.bump = ctx.bumps.creator_record;
+    msg!("Creator {} added", wallet);
+
+    Ok(())
+}
+
+pub fn set_creator_status(ctx: Context<SetCreatorStatus>, status: CreatorStatus) -> Result<()> {
+    ctx.accounts.creator_record.status = status;
+    msg!("Creator {} is {:?}", ctx.accounts.creator_record.wallet, status);
+
+    Ok(())
+}
+
+pub fn remove_creator(ctx: Context<RemoveCreator>) -> Result<()> {
+    msg!("Creator {} removed", ctx.accounts.creator_record.wallet);
+
+    Ok(())
+}
@@ -0,0 +1,360 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{
+        mpl_token_metadata::{
+            self,
+            instructions::{BurnEditionNftCpi, BurnEditionNftCpiAccounts},
+        },
+        MasterEditionAccount, Metadata, MetadataAccount,
+    },
+    token::{Mint, Token, TokenAccount},
+};
+
+use crate::{
+    error::ErrorCode,
+    events::SkinFused,
+    instructions::print_nft::{next_edition, print_edition, PrintAccounts},
+    FuseProgress, Ingredient, Recipe, SkinListing, MAX_RECIPE_INGREDIENTS,
+};
+
+#[derive(Accounts)]
+pub struct SetRecipe<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    // Listing of the upgrade, only its creator writes the recipe
+    #[account(
+        seeds = [b"listing", listing.master_mint.as_ref()],
+        bump = listing.bump,
+        has_one = creator @ ErrorCode::UnauthorizedCreator,
+    )]
+    pub listing: Box<Account<'info, SkinListing>>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + Recipe::INIT_SPACE,
+        seeds = [b"recipe", listing.master_mint.as_ref()],
+        bump,
+    )]
+    pub recipe: Box<Account<'info, Recipe>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn set_recipe(ctx: Context<SetRecipe>, ingredients: Vec<Ingredient>) -> Result<()> {
+    let upgrade_mint = ctx.accounts.listing.master_mint;
+
+    require!(
+        !ingredients.is_empty() && ingredients.len() <= MAX_RECIPE_INGREDIENTS,
+        ErrorCode::InvalidRecipe
+    );
+    require!(ingredients.iter().all(|ingredient| ingredient.count > 0), ErrorCode::InvalidRecipe);
+    require!(
+        ingredients.iter().all(|ingredient| ingredient.master_mint != upgrade_mint),
+        ErrorCode::InvalidRecipe
+    );
+    require!(
+        ingredients
+            .iter()
+            .enumerate()
+            .all(|(i, ingredient)| ingredients[..i].iter().all(|other| other.master_mint != ingredient.master_mint)),
+        ErrorCode::InvalidRecipe
+    );
+
+    let recipe = &mut ctx.accounts.recipe;
+    recipe.upgrade_mint = upgrade_mint;
+    recipe.creator = ctx.accounts.creator.key();
+    recipe.ingredients = ingredients;
+    recipe.revision = recipe.revision.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    recipe.bump = ctx.bumps.recipe;
+    msg!("Recipe of {} set, {} skins, revision {}", upgrade_mint, recipe.ingredients.len(), recipe.revision);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct BurnIngredient<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        seeds = [b"recipe", recipe.upgrade_mint.as_ref()],
+        bump = recipe.bump,
+    )]
+    pub recipe: Box<Account<'info, Recipe>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + FuseProgress::INIT_SPACE,
+        seeds = [b"fuse", recipe.upgrade_mint.as_ref(), player.key().as_ref()],
+        bump,
+    )]
+    pub progress: Box<Account<'info, FuseProgress>>,
+
+    // The print and its master, Metaplex checks they go together and the print is the player's
+    /// CHECK: checked by the burn
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: checked by the burn
+    #[account(mut)]
+    pub print_mint: UncheckedAccount<'info>,
+
+    /// CHECK: checked by the burn
+    #[account(mut)]
+    pub print_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: checked by the burn
+    #[account(mut)]
+    pub print_edition: UncheckedAccount<'info>,
+
+    /// CHECK: checked by the burn
+    #[account(mut)]
+    pub edition_marker: UncheckedAccount<'info>,
+
+    pub master_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: checked by the burn
+    pub master_vault: UncheckedAccount<'info>,
+
+    /// CHECK: checked by the burn
+    #[account(mut)]
+    pub master_edition: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+// One print per transaction, a burn takes too many accounts to fit several with the print of the upgrade
+pub fn burn_ingredient(ctx: Context<BurnIngredient>) -> Result<()> {
+    let master_mint = ctx.accounts.master_mint.key();
+    let recipe = &ctx.accounts.recipe;
+    let progress = &mut ctx.accounts.progress;
+
+    // The first burn takes the recipe as it is, the next ones count toward that same recipe
+    // so a change of the creator never voids prints already burned
+    if progress.burned.is_empty() {
+        progress.player = ctx.accounts.player.key();
+        progress.upgrade_mint = recipe.upgrade_mint;
+        progress.recipe_revision = recipe.revision;
+        progress.ingredients = recipe.ingredients.clone();
+        progress.bump = ctx.bumps.progress;
+    } else if progress.recipe_revision != recipe.revision {
+        msg!("The recipe changed since the first burn, still fusing revision {}", progress.recipe_revision);
+    }
+
+    let needed = progress
+        .ingredients
+        .iter()
+        .find(|ingredient| ingredient.master_mint == master_mint)
+        .map(|ingredient| ingredient.count)
+        .ok_or(ErrorCode::RecipeMismatch)?;
+
+    let burned = match progress.burned.iter().position(|burned| burned.master_mint == master_mint) {
+        Some(i) => &mut progress.burned[i],
+        None => {
+            progress.burned.push(Ingredient { master_mint, count: 0 });
+            progress.burned.last_mut().unwrap()
+        }
+    };
+    require!(burned.count < needed, ErrorCode::RecipeMismatch);
+    burned.count += 1;
+    msg!("Burned {} of {} prints of {}", burned.count, needed, master_mint);
+
+    BurnEditionNftCpi::new(
+        &ctx.accounts.token_metadata_program.to_account_info(),
+        BurnEditionNftCpiAccounts {
+            metadata: &ctx.accounts.metadata.to_account_info(),
+            owner: &ctx.accounts.player.to_account_info(),
+            print_edition_mint: &ctx.accounts.print_mint.to_account_info(),
+            master_edition_mint: &ctx.accounts.master_mint.to_account_info(),
+            print_edition_token_account: &ctx.accounts.print_token_account.to_account_info(),
+            master_edition_token_account: &ctx.accounts.master_vault.to_account_info(),
+            master_edition_account: &ctx.accounts.master_edition.to_account_info(),
+            print_edition_account: &ctx.accounts.print_edition.to_account_info(),
+            edition_marker_account: &ctx.accounts.edition_marker.to_account_info(),
+            spl_token_program: &ctx.accounts.token_program.to_account_info(),
+        },
+    )
+    .invoke()?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Fuse<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        seeds = [b"recipe", upgrade_mint.key().as_ref()],
+        bump = recipe.bump,
+        has_one = upgrade_mint,
+    )]
+    pub recipe: Box<Account<'info, Recipe>>,
+
+    // Closed by the fuse, the next burn starts over on the recipe as it is then
+    #[account(
+        mut,
+        close = player,
+        seeds = [b"fuse", upgrade_mint.key().as_ref(), player.key().as_ref()],
+        bump = progress.bump,
+    )]
+    pub progress: Box<Account<'info, FuseProgress>>,
+
+    // Master of the upgrade, printed like buy does
+    pub upgrade_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: address
+    #[account(
+        seeds = [b"authority"],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = upgrade_mint,
+        associated_token::authority = mint_authority
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            upgrade_mint.key().as_ref()
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+
+    #[account(mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            upgrade_mint.key().as_ref(),
+            b"edition",
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump,
+    )]
+    pub master_edition: Box<Account<'info, MasterEditionAccount>>,
+
+    #[account(
+        init,
+        payer = player,
+        mint::decimals = 0,
+        mint::authority = player,
+        mint::freeze_authority = player,
+    )]
+    pub new_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        associated_token::mint = new_mint,
+        associated_token::authority = player
+    )]
+    pub edition_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: address
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            new_mint.key().as_ref()
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump
+    )]
+    pub new_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: address
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            new_mint.key().as_ref(),
+            b"edition"
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump
+    )]
+    pub new_edition: UncheckedAccount<'info>,
+
+    /// CHECK: checked in fuse, it is the marker of the next edition
+    #[account(mut)]
+    pub edition_marker: UncheckedAccount<'info>,
+
+    // Of the upgrade, counts its editions like buy does
+    #[account(
+        mut,
+        seeds = [b"listing", upgrade_mint.key().as_ref()],
+        bump = listing.bump,
+    )]
+    pub listing: Box<Account<'info, SkinListing>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+// Takes the burns of the recipe the progress started on and prints the upgrade
+pub fn fuse(ctx: Context<Fuse>) -> Result<()> {
+    let next_edition = next_edition(
+        &ctx.accounts.listing,
+        &ctx.accounts.master_edition,
+        &ctx.accounts.edition_marker.key(),
+    )?;
+
+    // burn_ingredient stops at the count of the recipe, the burns are exactly it and nothing is left over
+    let progress = &ctx.accounts.progress;
+    require!(
+        progress.burned.len() == progress.ingredients.len()
+            && progress.ingredients.iter().all(|ingredient| progress
+                .burned
+                .iter()
+                .any(|burned| burned.master_mint == ingredient.master_mint && burned.count == ingredient.count)),
+        ErrorCode::RecipeMismatch
+    );
+
+    print_edition(
+        PrintAccounts {
+            owner: &ctx.accounts.player.to_account_info(),
+            metadata: &ctx.accounts.metadata.to_account_info(),
+            master_edition: &ctx.accounts.master_edition.to_account_info(),
+            vault: &ctx.accounts.vault.to_account_info(),
+            mint_authority: &ctx.accounts.mint_authority.to_account_info(),
+            new_mint: &ctx.accounts.new_mint.to_account_info(),
+            edition_token_account: &ctx.accounts.edition_token_account.to_account_info(),
+            new_metadata: &ctx.accounts.new_metadata.to_account_info(),
+            new_edition: &ctx.accounts.new_edition.to_account_info(),
+            edition_marker: &ctx.accounts.edition_marker.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+            token_program: &ctx.accounts.token_program.to_account_info(),
+            token_metadata_program: &ctx.accounts.token_metadata_program.to_account_info(),
+        },
+        next_edition,
+        ctx.bumps.mint_authority,
+    )?;
+    ctx.accounts.listing.editions_printed = next_edition;
+
+    emit!(SkinFused {
+        upgrade_mint: ctx.accounts.upgrade_mint.key(),
+        edition_mint: ctx.accounts.new_mint.key(),
+        edition: next_edition,
+        player: ctx.accounts.player.key(),
+    });
+
+    Ok(())
+}
@@ -6,3 +6,24 @@ pub use create_nft::*;
 
 pub mod print_nft;
 pub use print_nft::*;
+
+pub mod set_price;
+pub use set_price::*;
+
+pub mod admin;
+pub use admin::*;
+
+pub mod creator_registry;
+pub use creator_registry::*;
+
+pub mod update_skin;
+pub use update_skin::*;
+
+pub mod sale;
+pub use sale::*;
+
+pub mod fuse;
+pub use fuse::*;
+
+pub mod trade;
+pub use trade::*;
@@ -1,4 +1,4 @@
-use anchor_lang::{prelude::*, solana_program};
+use anchor_lang::{prelude::*, system_program};
 use anchor_spl::{metadata::{
     mpl_token_metadata::{
         self,
@@ -11,12 +11,14 @@ use anchor_spl::{metadata::{
         EDITION_MARKER_BIT_SIZE,
     },
     MasterEditionAccount, Metadata, MetadataAccount,
-}, token::{mint_to, MintTo}};
+}, token::{self, mint_to, MintTo}};
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{Mint, Token, TokenAccount},
 };
 
+use crate::{error::ErrorCode, events::SkinPurchased, merkle, SkinListing, WalletPurchases};
+
 #[derive(Accounts)]
 pub struct Buy<'info> {
     #[account(mut)]
@@ -110,121 +112,249 @@ pub struct Buy<'info> {
     )]
     pub new_edition: UncheckedAccount<'info>,
 
-    /// CHECK: address
+    /// CHECK: checked in buy, it is the marker of the next edition
+    #[account(mut)]
+    pub edition_marker: UncheckedAccount<'info>,
+
     #[account(
         mut,
-        seeds = [
-            b"metadata",
-            mpl_token_metadata::ID.as_ref(),
-            master_mint.key().as_ref(),
-            b"edition",
-            (master_edition.supply / EDITION_MARKER_BIT_SIZE).to_string().as_bytes()
-        ],
-        seeds::program = mpl_token_metadata::ID,
-        bump
+        seeds = [b"listing", master_mint.key().as_ref()],
+        bump = listing.bump,
+        has_one = treasury,
     )]
-    pub edition_marker: UncheckedAccount<'info>,
-    // #[account(mut)]
+    pub listing: Box<Account<'info, SkinListing>>,
+
+    /// CHECK: has_one on the listing
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Counts the prints of this skin the buyer has, for max_per_wallet
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + WalletPurchases::INIT_SPACE,
+        seeds = [b"purchases", master_mint.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub wallet_purchases: Box<Account<'info, WalletPurchases>>,
+
+    // Only for listings paid in an spl token
+    pub payment_mint: Option<Box<Account<'info, Mint>>>,
+    #[account(mut)]
+    pub buyer_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub treasury_payment_account: Option<Box<Account<'info, TokenAccount>>>,
 
-    // /// CHECK: address
-    // pub edition_marker: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_metadata_program: Program<'info, Metadata>,
 }
 
-pub fn buy(ctx: &Context<Buy>) -> Result<()> {
-    let current_supply = ctx.accounts.master_edition.supply;
-    let next_edition = current_supply.checked_add(1).unwrap();
-    let marker_index = (next_edition - 1) / EDITION_MARKER_BIT_SIZE;
-    let marker_bytes = marker_index.to_le_bytes();
-
-    // let (marker_derived, marker_bump) =
-    // Pubkey::find_program_address(
-    //     &[
-    //         b"metadata",
-    //         mpl_token_metadata::ID.as_ref(),
-    //         ctx.accounts.master_mint.key().as_ref(),
-    //         b"edition",
-    //         &marker_bytes,
-    //     ],
-    //     &mpl_token_metadata::ID,
-    // );
-    // solana_program::pubkey::Pubkey::find_program_address(
-    //     &[
-    //         "metadata".as_bytes(),
-    //         mpl_token_metadata::ID.as_ref(),
-    //         ctx.accounts.master_mint.key().as_ref(),
-    //         "edition".as_bytes(),
-    //         marker_index.to_string().as_ref(),
-    //     ],
-    //     &mpl_token_metadata::ID,
-    // );
-    // msg!("Derived edition marker PDA: {}", marker_derived);
-    // msg!(
-    //     "Provided edition marker key: {}",
-    //     ctx.accounts.edition_marker.key()
-    // );
-    // require_keys_eq!(
-    //     marker_derived,
-    //     ctx.accounts.edition_marker.key(),
-    //     ErrorCode::AccountNotProgramData
-    // );
-
-    let buyer = &ctx.accounts.buyer.to_account_info();
-    let metadata = &ctx.accounts.metadata.to_account_info();
-    let master_edition = &ctx.accounts.master_edition.to_account_info();
-    let token_account = &ctx.accounts.vault.to_account_info();
-    let new_mint = &ctx.accounts.new_mint.to_account_info();
-    let new_metadata = &ctx.accounts.new_metadata.to_account_info();
-    let new_edition = &ctx.accounts.new_edition.to_account_info();
-    let edition_mark_pda = &ctx.accounts.edition_marker.to_account_info();
-    let mint_authority = &ctx.accounts.mint_authority.to_account_info();
-    let payer = &ctx.accounts.buyer.to_account_info();
-    let system_program = &ctx.accounts.system_program.to_account_info();
-    let token_program = &ctx.accounts.token_program.to_account_info();
-    let spl_metadata_program = &ctx.accounts.token_metadata_program.to_account_info();
-
-    let seeds = &[&b"authority"[..], &[ctx.bumps.mint_authority]];
+// Buyer to treasury, in SOL or in the spl token of the listing
+fn pay(ctx: &Context<Buy>) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    if listing.price == 0 {
+        return Ok(());
+    }
+
+    let Some(payment_mint) = listing.payment_mint else {
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.buyer.to_account_info(),
+            to: ctx.accounts.treasury.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        return system_program::transfer(cpi_ctx, listing.price);
+    };
+
+    let (Some(mint), Some(from), Some(to)) = (
+        &ctx.accounts.payment_mint,
+        &ctx.accounts.buyer_payment_account,
+        &ctx.accounts.treasury_payment_account,
+    ) else {
+        return err!(ErrorCode::PaymentAccountsMissing);
+    };
+    require_keys_eq!(mint.key(), payment_mint, ErrorCode::WrongPaymentAccounts);
+    require_keys_eq!(from.mint, payment_mint, ErrorCode::WrongPaymentAccounts);
+    require_keys_eq!(to.mint, payment_mint, ErrorCode::WrongPaymentAccounts);
+    require_keys_eq!(to.owner, listing.treasury, ErrorCode::WrongPaymentAccounts);
+
+    let cpi_accounts = token::TransferChecked {
+        from: from.to_account_info(),
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: ctx.accounts.buyer.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::transfer_checked(cpi_ctx, listing.price, mint.decimals)
+}
+
+// Paused, window, allowlist and cap
+fn check_sale(ctx: &Context<Buy>, allowlist_proof: &[[u8; 32]]) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(!listing.paused, ErrorCode::SalePaused);
+    require!(now >= listing.sale_start, ErrorCode::SaleNotActive);
+    require!(listing.sale_end == 0 || now < listing.sale_end, ErrorCode::SaleNotActive);
+
+    if let Some(root) = listing.allowlist_root {
+        if now < listing.public_start {
+            let leaf = merkle::leaf(ctx.accounts.buyer.key().as_ref());
+            require!(merkle::verify(allowlist_proof, &root, leaf), ErrorCode::NotOnAllowlist);
+        }
+    }
+
+    require!(
+        listing.max_per_wallet == 0 || ctx.accounts.wallet_purchases.count < listing.max_per_wallet,
+        ErrorCode::WalletLimitReached
+    );
+
+    Ok(())
+}
+
+// Sold out and marker checks, returns the number of the edition about to be printed
+// Counted from the listing and not the supply, a burn lowers the supply and the edition it frees is still marked
+pub(crate) fn next_edition(
+    listing: &SkinListing,
+    master_edition: &MasterEditionAccount,
+    edition_marker: &Pubkey,
+) -> Result<u64> {
+    // Metaplex would fail on it too, but without saying why
+    if master_edition.max_supply.is_some_and(|max_supply| listing.editions_printed >= max_supply) {
+        return err!(ErrorCode::SoldOut);
+    }
+    let next_edition = listing.editions_printed.checked_add(1).ok_or(ErrorCode::Overflow)?;
+
+    // One marker holds the bits of 248 editions, the next edition picks it
+    let (marker, _) = Pubkey::find_program_address(
+        &[
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            listing.master_mint.as_ref(),
+            b"edition",
+            (next_edition / EDITION_MARKER_BIT_SIZE).to_string().as_bytes(),
+        ],
+        &mpl_token_metadata::ID,
+    );
+    require_keys_eq!(marker, *edition_marker, ErrorCode::EditionMarkerMismatch);
+
+    Ok(next_edition)
+}
+
+// What a print takes, buy and fuse both print from the master in the vault
+pub(crate) struct PrintAccounts<'a, 'info> {
+    // Gets the new edition and pays for its accounts
+    pub owner: &'a AccountInfo<'info>,
+    pub metadata: &'a AccountInfo<'info>,
+    pub master_edition: &'a AccountInfo<'info>,
+    pub vault: &'a AccountInfo<'info>,
+    pub mint_authority: &'a AccountInfo<'info>,
+    pub new_mint: &'a AccountInfo<'info>,
+    pub edition_token_account: &'a AccountInfo<'info>,
+    pub new_metadata: &'a AccountInfo<'info>,
+    pub new_edition: &'a AccountInfo<'info>,
+    pub edition_marker: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+    pub token_metadata_program: &'a AccountInfo<'info>,
+}
+
+// Mints the token of the new edition to its owner then prints the edition on it
+pub(crate) fn print_edition(accounts: PrintAccounts, edition: u64, authority_bump: u8) -> Result<()> {
+    let seeds = &[&b"authority"[..], &[authority_bump]];
     let signer_seeds = &[&seeds[..]];
 
-    let cpi_program = ctx.accounts.token_program.to_account_info();
     let cpi_accounts = MintTo {
-        mint: ctx.accounts.new_mint.to_account_info(),
-        to: ctx.accounts.edition_token_account.to_account_info(),
-        authority: ctx.accounts.buyer.to_account_info(),
+        mint: accounts.new_mint.clone(),
+        to: accounts.edition_token_account.clone(),
+        authority: accounts.owner.clone(),
     };
-    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    let cpi_ctx = CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, signer_seeds);
     mint_to(cpi_ctx, 1)?;
     msg!("NFT minted!");
 
     let print = MintNewEditionFromMasterEditionViaTokenCpi::new(
-        spl_metadata_program,
+        accounts.token_metadata_program,
         MintNewEditionFromMasterEditionViaTokenCpiAccounts {
-            metadata,
-            master_edition,
-            new_metadata,
-            new_edition,
-            new_metadata_update_authority: mint_authority,
-            new_mint_authority: buyer,
-            new_mint,
-            token_account_owner: mint_authority,
-            token_account,
-            payer,
-            token_program,
-            system_program,
-            edition_mark_pda,
+            metadata: accounts.metadata,
+            master_edition: accounts.master_edition,
+            new_metadata: accounts.new_metadata,
+            new_edition: accounts.new_edition,
+            new_metadata_update_authority: accounts.mint_authority,
+            new_mint_authority: accounts.owner,
+            new_mint: accounts.new_mint,
+            token_account_owner: accounts.mint_authority,
+            token_account: accounts.vault,
+            payer: accounts.owner,
+            token_program: accounts.token_program,
+            system_program: accounts.system_program,
+            edition_mark_pda: accounts.edition_marker,
             rent: None,
         },
         MintNewEditionFromMasterEditionViaTokenInstructionArgs {
             mint_new_edition_from_master_edition_via_token_args:
-                MintNewEditionFromMasterEditionViaTokenArgs {
-                    edition: ctx.accounts.master_edition.supply + 1,
-                },
+                MintNewEditionFromMasterEditionViaTokenArgs { edition },
         },
     );
     print.invoke_signed(signer_seeds)?;
 
     Ok(())
 }
+
+// expected_price and expected_payment_mint are what the buyer saw, a creator repricing in between makes it fail
+pub fn buy(
+    ctx: &mut Context<Buy>,
+    allowlist_proof: Vec<[u8; 32]>,
+    expected_price: u64,
+    expected_payment_mint: Option<Pubkey>,
+) -> Result<()> {
+    require!(ctx.accounts.listing.price == expected_price, ErrorCode::PriceMismatch);
+    require!(ctx.accounts.listing.payment_mint == expected_payment_mint, ErrorCode::PriceMismatch);
+    check_sale(ctx, &allowlist_proof)?;
+
+    let next_edition = next_edition(
+        &ctx.accounts.listing,
+        &ctx.accounts.master_edition,
+        &ctx.accounts.edition_marker.key(),
+    )?;
+
+    pay(ctx)?;
+    msg!("Paid {}", ctx.accounts.listing.price);
+
+    print_edition(
+        PrintAccounts {
+            owner: &ctx.accounts.buyer.to_account_info(),
+            metadata: &ctx.accounts.metadata.to_account_info(),
+            master_edition: &ctx.accounts.master_edition.to_account_info(),
+            vault: &ctx.accounts.vault.to_account_info(),
+            mint_authority: &ctx.accounts.mint_authority.to_account_info(),
+            new_mint: &ctx.accounts.new_mint.to_account_info(),
+            edition_token_account: &ctx.accounts.edition_token_account.to_account_info(),
+            new_metadata: &ctx.accounts.new_metadata.to_account_info(),
+            new_edition: &ctx.accounts.new_edition.to_account_info(),
+            edition_marker: &ctx.accounts.edition_marker.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+            token_program: &ctx.accounts.token_program.to_account_info(),
+            token_metadata_program: &ctx.accounts.token_metadata_program.to_account_info(),
+        },
+        next_edition,
+        ctx.bumps.mint_authority,
+    )?;
+
+    ctx.accounts.listing.editions_printed = next_edition;
+    let purchases = &mut ctx.accounts.wallet_purchases;
+    purchases.count = purchases.count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    purchases.bump = ctx.bumps.wallet_purchases;
+
+    emit!(SkinPurchased {
+        master_mint: ctx.accounts.master_mint.key(),
+        edition_mint: ctx.accounts.new_mint.key(),
+        edition: next_edition,
+        buyer: ctx.accounts.buyer.key(),
+        price: ctx.accounts.listing.price,
+        payment_mint: ctx.accounts.listing.payment_mint,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, Config, SkinListing};
+
+#[derive(Accounts)]
+pub struct SetSale<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"listing", listing.master_mint.as_ref()],
+        bump = listing.bump,
+        has_one = creator @ ErrorCode::UnauthorizedCreator,
+    )]
+    pub listing: Account<'info, SkinListing>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    // The creator of the skin or the admin
+    #[account(
+        constraint = authority.key() == listing.creator || authority.key() == config.admin @ ErrorCode::UnauthorizedCreator,
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"listing", listing.master_mint.as_ref()],
+        bump = listing.bump,
+    )]
+    pub listing: Account<'info, SkinListing>,
+}
+
+// Timestamps are unix seconds, 0 is no limit. public_start only matters with an allowlist
+pub fn set_sale(
+    ctx: Context<SetSale>,
+    sale_start: i64,
+    sale_end: i64,
+    max_per_wallet: u32,
+    allowlist_root: Option<[u8; 32]>,
+    public_start: i64,
+) -> Result<()> {
+    require!(sale_end == 0 || sale_end > sale_start, ErrorCode::InvalidSaleWindow);
+
+    let listing = &mut ctx.accounts.listing;
+    listing.sale_start = sale_start;
+    listing.sale_end = sale_end;
+    listing.max_per_wallet = max_per_wallet;
+    listing.allowlist_root = allowlist_root;
+    listing.public_start = public_start;
+    msg!("Sale from {} to {}", sale_start, sale_end);
+
+    Ok(())
+}
+
+pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+    ctx.accounts.listing.paused = paused;
+    msg!("Paused: {}", paused);
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+use crate::SkinListing;
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"listing", listing.master_mint.as_ref()],
+        bump = listing.bump,
+        has_one = creator,
+    )]
+    pub listing: Account<'info, SkinListing>,
+}
+
+pub fn set_price(ctx: Context<SetPrice>, price: u64, payment_mint: Option<Pubkey>, treasury: Pubkey) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    listing.price = price;
+    listing.payment_mint = payment_mint;
+    listing.treasury = treasury;
+    msg!("Price set to {}", price);
+
+    Ok(())
+}
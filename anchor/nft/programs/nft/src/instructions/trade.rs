@@ -0,0 +1,307 @@
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::{mpl_token_metadata, MetadataAccount},
+    token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer},
+};
+
+use crate::{
+    error::ErrorCode,
+    events::{SkinDelisted, SkinListed, SkinTraded},
+    TradeListing,
+};
+
+#[derive(Accounts)]
+pub struct List<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = seller
+    )]
+    pub seller_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: address
+    #[account(
+        seeds = [b"authority"],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    // Prints keep our authority as update authority, collections have details, skins don't
+    #[account(
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref()
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump,
+        constraint = metadata.update_authority == mint_authority.key() @ ErrorCode::NotASkin,
+        constraint = metadata.collection_details.is_none() @ ErrorCode::NotASkin,
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + TradeListing::INIT_SPACE,
+        seeds = [b"trade", mint.key().as_ref()],
+        bump,
+    )]
+    pub trade_listing: Box<Account<'info, TradeListing>>,
+
+    // Holds the print until it is sold or the listing cancelled
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = trade_listing
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn list(ctx: Context<List>, price: u64) -> Result<()> {
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.seller_token_account.to_account_info(),
+        to: ctx.accounts.escrow.to_account_info(),
+        authority: ctx.accounts.seller.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+    token::transfer(cpi_ctx, 1)?;
+
+    let trade_listing = &mut ctx.accounts.trade_listing;
+    trade_listing.seller = ctx.accounts.seller.key();
+    trade_listing.mint = ctx.accounts.mint.key();
+    trade_listing.price = price;
+    trade_listing.bump = ctx.bumps.trade_listing;
+    msg!("Listed {} for {}", trade_listing.mint, price);
+
+    emit!(SkinListed {
+        mint: trade_listing.mint,
+        seller: trade_listing.seller,
+        price,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Cancel<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"trade", mint.key().as_ref()],
+        bump = trade_listing.bump,
+        has_one = seller,
+        has_one = mint,
+        close = seller,
+    )]
+    pub trade_listing: Box<Account<'info, TradeListing>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = trade_listing
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    // Left empty by list, the seller may have closed it since
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = seller
+    )]
+    pub seller_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn cancel(ctx: Context<Cancel>) -> Result<()> {
+    release_escrow(
+        EscrowAccounts {
+            token_program: ctx.accounts.token_program.to_account_info(),
+            escrow: ctx.accounts.escrow.to_account_info(),
+            trade_listing: ctx.accounts.trade_listing.to_account_info(),
+            to: ctx.accounts.seller_token_account.to_account_info(),
+            rent_to: ctx.accounts.seller.to_account_info(),
+        },
+        &ctx.accounts.mint.key(),
+        ctx.accounts.trade_listing.bump,
+    )?;
+    msg!("Listing of {} cancelled", ctx.accounts.mint.key());
+
+    emit!(SkinDelisted {
+        mint: ctx.accounts.mint.key(),
+        seller: ctx.accounts.seller.key(),
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PurchaseListing<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// CHECK: the seller of the listing, has_one checks it
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"trade", mint.key().as_ref()],
+        bump = trade_listing.bump,
+        has_one = seller,
+        has_one = mint,
+        close = seller,
+    )]
+    pub trade_listing: Box<Account<'info, TradeListing>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = trade_listing
+    )]
+    pub escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    // Royalties and who gets them
+    #[account(
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref()
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+// remaining_accounts are the wallets of the metadata creators with a share, in their order
+// price is what the buyer saw, a seller relisting higher in between makes it fail
+pub fn purchase_listing<'info>(ctx: Context<'_, '_, '_, 'info, PurchaseListing<'info>>, price: u64) -> Result<()> {
+    require!(ctx.accounts.trade_listing.price == price, ErrorCode::PriceMismatch);
+
+    let metadata = &ctx.accounts.metadata;
+    let royalties = (price as u128 * metadata.seller_fee_basis_points as u128 / 10_000) as u64;
+    // Our authority is a creator with no share
+    let creators: Vec<_> = metadata.creators.iter().flatten().filter(|creator| creator.share > 0).collect();
+    require!(creators.len() == ctx.remaining_accounts.len(), ErrorCode::WrongCreatorAccounts);
+
+    let buyer = ctx.accounts.buyer.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+    let mut paid: u64 = 0;
+    for (creator, wallet) in creators.iter().zip(ctx.remaining_accounts) {
+        require_keys_eq!(creator.address, wallet.key(), ErrorCode::WrongCreatorAccounts);
+
+        let amount = (royalties as u128 * creator.share as u128 / 100) as u64;
+        pay(&system_program, &buyer, wallet, amount)?;
+        paid = paid.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    }
+    // What the shares round down stays with the seller
+    let proceeds = price.checked_sub(paid).ok_or(ErrorCode::Overflow)?;
+    pay(&system_program, &buyer, &ctx.accounts.seller.to_account_info(), proceeds)?;
+    msg!("Paid {} to the seller, {} in royalties", proceeds, paid);
+
+    release_escrow(
+        EscrowAccounts {
+            token_program: ctx.accounts.token_program.to_account_info(),
+            escrow: ctx.accounts.escrow.to_account_info(),
+            trade_listing: ctx.accounts.trade_listing.to_account_info(),
+            to: ctx.accounts.buyer_token_account.to_account_info(),
+            rent_to: ctx.accounts.seller.to_account_info(),
+        },
+        &ctx.accounts.mint.key(),
+        ctx.accounts.trade_listing.bump,
+    )?;
+
+    emit!(SkinTraded {
+        mint: ctx.accounts.mint.key(),
+        seller: ctx.accounts.seller.key(),
+        buyer: ctx.accounts.buyer.key(),
+        price,
+        royalties: paid,
+    });
+
+    Ok(())
+}
+
+fn pay<'info>(
+    system_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let cpi_accounts = system_program::Transfer {
+        from: from.clone(),
+        to: to.clone(),
+    };
+    let cpi_ctx = CpiContext::new(system_program.clone(), cpi_accounts);
+    system_program::transfer(cpi_ctx, amount)
+}
+
+struct EscrowAccounts<'info> {
+    token_program: AccountInfo<'info>,
+    escrow: AccountInfo<'info>,
+    trade_listing: AccountInfo<'info>,
+    // Gets the print
+    to: AccountInfo<'info>,
+    // Gets the rent of the escrow, the seller paid it
+    rent_to: AccountInfo<'info>,
+}
+
+// Moves the print out of the escrow and closes it, the listing signs as its owner
+fn release_escrow(accounts: EscrowAccounts, mint: &Pubkey, bump: u8) -> Result<()> {
+    let seeds = &[&b"trade"[..], mint.as_ref(), &[bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: accounts.escrow.clone(),
+        to: accounts.to,
+        authority: accounts.trade_listing.clone(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(accounts.token_program.clone(), cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, 1)?;
+
+    let cpi_accounts = CloseAccount {
+        account: accounts.escrow,
+        destination: accounts.rent_to,
+        authority: accounts.trade_listing,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(accounts.token_program, cpi_accounts, signer_seeds);
+    token::close_account(cpi_ctx)
+}
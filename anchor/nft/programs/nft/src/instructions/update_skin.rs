@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::{
+    mpl_token_metadata::{
+        self,
+        instructions::{
+            UpdateMetadataAccountV2Cpi, UpdateMetadataAccountV2CpiAccounts,
+            UpdateMetadataAccountV2InstructionArgs,
+        },
+        types::DataV2,
+    },
+    Metadata, MetadataAccount,
+};
+
+use crate::{error::ErrorCode, CreatorRecord, SkinListing};
+
+#[derive(Accounts)]
+pub struct UpdateSkin<'info> {
+    pub creator: Signer<'info>,
+
+    // Registered and not suspended, like for create_nft
+    #[account(
+        seeds = [b"creator", creator.key().as_ref()],
+        bump,
+        constraint = CreatorRecord::is_active(&creator_record) @ ErrorCode::UnauthorizedCreator,
+    )]
+    /// CHECK: owner and data, in is_active
+    pub creator_record: UncheckedAccount<'info>,
+
+    // Only the creator of the skin
+    #[account(
+        mut,
+        seeds = [b"listing", listing.master_mint.as_ref()],
+        bump = listing.bump,
+        has_one = creator @ ErrorCode::UnauthorizedCreator,
+    )]
+    pub listing: Box<Account<'info, SkinListing>>,
+
+    /// CHECK: address
+    #[account(
+        seeds = [b"authority"],
+        bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            listing.master_mint.as_ref()
+        ],
+        seeds::program = mpl_token_metadata::ID,
+        bump
+    )]
+    pub metadata: Box<Account<'info, MetadataAccount>>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+// None keeps the current value, every call bumps the revision so the clients drop their cache
+pub fn update_skin(ctx: Context<UpdateSkin>, name: Option<String>, metadata_uri: Option<String>) -> Result<()> {
+    let current = &ctx.accounts.metadata;
+    let data = DataV2 {
+        name: name.unwrap_or_else(|| current.name.trim_matches('\0').to_string()),
+        symbol: current.symbol.trim_matches('\0').to_string(),
+        uri: metadata_uri.unwrap_or_else(|| current.uri.trim_matches('\0').to_string()),
+        seller_fee_basis_points: current.seller_fee_basis_points,
+        creators: current.creators.clone(),
+        collection: current.collection.clone(),
+        uses: current.uses.clone(),
+    };
+
+    let seeds = &[&b"authority"[..], &[ctx.bumps.mint_authority]];
+    let signer_seeds = &[&seeds[..]];
+
+    let token_metadata_program = ctx.accounts.token_metadata_program.to_account_info();
+    let metadata = &ctx.accounts.metadata.to_account_info();
+    let authority = &ctx.accounts.mint_authority.to_account_info();
+    let update = UpdateMetadataAccountV2Cpi::new(
+        &token_metadata_program,
+        UpdateMetadataAccountV2CpiAccounts {
+            metadata,
+            update_authority: authority,
+        },
+        UpdateMetadataAccountV2InstructionArgs {
+            data: Some(data),
+            new_update_authority: None,
+            primary_sale_happened: None,
+            is_mutable: None,
+        },
+    );
+    update.invoke_signed(signer_seeds)?;
+
+    let listing = &mut ctx.accounts.listing;
+    listing.revision = listing.revision.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    msg!("Skin {} is at revision {}", listing.master_mint, listing.revision);
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod merkle;
 pub mod state;
 
 use anchor_lang::prelude::*;
@@ -23,19 +25,116 @@ pub mod nft {
         create_collection::create_collection(&ctx, name, symbol)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_nft(
-        ctx: Context<CreateNFT>,
+        mut ctx: Context<CreateNFT>,
         name: String,
         symbol: String,
         metadata_uri: String,
         supply: u64,
+        seller_fee_basis_points: u16,
+        creators: Vec<CreatorShare>,
+        price: u64,
+        payment_mint: Option<Pubkey>,
     ) -> Result<()> {
-        create_nft::create_nft(&ctx, name, symbol, metadata_uri, supply)
+        create_nft::create_nft(
+            &mut ctx,
+            name,
+            symbol,
+            metadata_uri,
+            supply,
+            seller_fee_basis_points,
+            creators,
+            price,
+            payment_mint,
+        )
     }
 
     pub fn buy_nft(
-        ctx: Context<Buy>
+        mut ctx: Context<Buy>,
+        allowlist_proof: Vec<[u8; 32]>,
+        expected_price: u64,
+        expected_payment_mint: Option<Pubkey>,
     ) -> Result<()> {
-        print_nft::buy(&ctx)
+        print_nft::buy(&mut ctx, allowlist_proof, expected_price, expected_payment_mint)
+    }
+
+    pub fn set_price(
+        ctx: Context<SetPrice>,
+        price: u64,
+        payment_mint: Option<Pubkey>,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        set_price::set_price(ctx, price, payment_mint, treasury)
+    }
+
+    pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+        admin::initialize_config(ctx, admin)
+    }
+
+    pub fn set_admin(ctx: Context<SetAdmin>, new_admin: Pubkey) -> Result<()> {
+        admin::set_admin(ctx, new_admin)
+    }
+
+    pub fn add_creator(ctx: Context<AddCreator>, wallet: Pubkey) -> Result<()> {
+        creator_registry::add_creator(ctx, wallet)
+    }
+
+    pub fn set_creator_status(ctx: Context<SetCreatorStatus>, status: CreatorStatus) -> Result<()> {
+        creator_registry::set_creator_status(ctx, status)
+    }
+
+    pub fn remove_creator(ctx: Context<RemoveCreator>) -> Result<()> {
+        creator_registry::remove_creator(ctx)
+    }
+
+    pub fn update_skin(
+        ctx: Context<UpdateSkin>,
+        name: Option<String>,
+        metadata_uri: Option<String>,
+    ) -> Result<()> {
+        update_skin::update_skin(ctx, name, metadata_uri)
+    }
+
+    pub fn set_sale(
+        ctx: Context<SetSale>,
+        sale_start: i64,
+        sale_end: i64,
+        max_per_wallet: u32,
+        allowlist_root: Option<[u8; 32]>,
+        public_start: i64,
+    ) -> Result<()> {
+        sale::set_sale(ctx, sale_start, sale_end, max_per_wallet, allowlist_root, public_start)
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        sale::set_paused(ctx, paused)
+    }
+
+    pub fn set_recipe(ctx: Context<SetRecipe>, ingredients: Vec<Ingredient>) -> Result<()> {
+        fuse::set_recipe(ctx, ingredients)
+    }
+
+    pub fn burn_ingredient(ctx: Context<BurnIngredient>) -> Result<()> {
+        fuse::burn_ingredient(ctx)
+    }
+
+    pub fn fuse(ctx: Context<Fuse>) -> Result<()> {
+        fuse::fuse(ctx)
+    }
+
+    pub fn list(ctx: Context<List>, price: u64) -> Result<()> {
+        trade::list(ctx, price)
+    }
+
+    pub fn cancel(ctx: Context<Cancel>) -> Result<()> {
+        trade::cancel(ctx)
+    }
+
+    pub fn purchase_listing<'info>(
+        ctx: Context<'_, '_, '_, 'info, PurchaseListing<'info>>,
+        price: u64,
+    ) -> Result<()> {
+        trade::purchase_listing(ctx, price)
     }
 }
@@ -0,0 +1,18 @@
+use anchor_lang::solana_program::hash::hashv;
+
+// Leaves are sha256(wallet), pairs are hashed sorted so the proof needs no directions
+pub fn leaf(wallet: &[u8]) -> [u8; 32] {
+    hashv(&[wallet]).to_bytes()
+}
+
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+
+    computed == *root
+}
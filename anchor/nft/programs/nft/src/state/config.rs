@@ -0,0 +1,10 @@
+use anchor_lang::prelude::*;
+
+// Program wide settings at [b"config"], made once by the upgrade authority
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    // Manages the creator registry
+    pub admin: Pubkey,
+    pub bump: u8,
+}
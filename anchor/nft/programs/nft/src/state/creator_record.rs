@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum CreatorStatus {
+    Active,
+    // Keeps the skins already made, can't make new ones or update them
+    Suspended,
+}
+
+// A wallet allowed to create skins, at [b"creator", wallet]
+#[account]
+#[derive(InitSpace)]
+pub struct CreatorRecord {
+    pub wallet: Pubkey,
+    pub status: CreatorStatus,
+    pub bump: u8,
+}
+
+impl CreatorRecord {
+    // Checked by hand, a wallet never registered has no account at its seeds and Anchor would fail with AccountNotInitialized
+    pub fn is_active(account: &AccountInfo) -> bool {
+        account.owner == &crate::ID
+            && CreatorRecord::try_deserialize(&mut &account.data.borrow()[..])
+                .is_ok_and(|record| record.status == CreatorStatus::Active)
+    }
+}
@@ -0,0 +1,17 @@
+pub mod skin_listing;
+pub use skin_listing::*;
+
+pub mod config;
+pub use config::*;
+
+pub mod creator_record;
+pub use creator_record::*;
+
+pub mod wallet_purchases;
+pub use wallet_purchases::*;
+
+pub mod recipe;
+pub use recipe::*;
+
+pub mod trade_listing;
+pub use trade_listing::*;
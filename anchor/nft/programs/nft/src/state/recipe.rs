@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::MAX_RECIPE_INGREDIENTS;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct Ingredient {
+    // Master mint of the skin whose prints get burned
+    pub master_mint: Pubkey,
+    pub count: u8,
+}
+
+// What fuse takes to print the upgrade, set by the upgrade's creator at [b"recipe", upgrade_mint]
+#[account]
+#[derive(InitSpace)]
+pub struct Recipe {
+    pub upgrade_mint: Pubkey,
+    pub creator: Pubkey,
+    #[max_len(MAX_RECIPE_INGREDIENTS)]
+    pub ingredients: Vec<Ingredient>,
+    // Bumped by every set_recipe, progress started on an older one keeps its own ingredients
+    pub revision: u32,
+    pub bump: u8,
+}
+
+// Prints a player burned toward an upgrade and not fused yet, at [b"fuse", upgrade_mint, player]
+#[account]
+#[derive(InitSpace)]
+pub struct FuseProgress {
+    pub player: Pubkey,
+    pub upgrade_mint: Pubkey,
+    // The recipe as it was at the first burn, what fuse takes even if the creator changed it since
+    pub recipe_revision: u32,
+    #[max_len(MAX_RECIPE_INGREDIENTS)]
+    pub ingredients: Vec<Ingredient>,
+    #[max_len(MAX_RECIPE_INGREDIENTS)]
+    pub burned: Vec<Ingredient>,
+    pub bump: u8,
+}
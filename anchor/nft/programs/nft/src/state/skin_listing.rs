@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+// What a print of a master edition costs, when it sells and its revision, created with the skin at [b"listing", master_mint]
+#[account]
+#[derive(InitSpace)]
+pub struct SkinListing {
+    pub master_mint: Pubkey,
+    // Only one allowed to change the price
+    pub creator: Pubkey,
+    // Wallet the payments go to, owner of the token account when paid in spl
+    pub treasury: Pubkey,
+    // Lamports, or base units of payment_mint
+    pub price: u64,
+    // None is paid in SOL
+    pub payment_mint: Option<Pubkey>,
+    pub bump: u8,
+    // Bumped by update_skin, the game drops its cached textures when it changes
+    pub revision: u32,
+    // The sale, set by the creator with set_sale. Unix seconds, 0 is no limit
+    pub sale_start: i64,
+    pub sale_end: i64,
+    // Creator or admin, stops the sales until unpaused
+    pub paused: bool,
+    // Prints a wallet can buy, 0 is no cap
+    pub max_per_wallet: u32,
+    // Early access, until public_start only the wallets in this merkle tree can buy
+    pub allowlist_root: Option<[u8; 32]>,
+    pub public_start: i64,
+    // Editions buy and fuse printed, only goes up. A burned print lowers the master edition supply
+    // but keeps its bit in the edition marker, so the next edition number comes from here
+    pub editions_printed: u64,
+}
@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+// A print a player sells to another, its token waits in the listing's associated token account at [b"trade", mint]
+#[account]
+#[derive(InitSpace)]
+pub struct TradeListing {
+    pub seller: Pubkey,
+    // Mint of the print
+    pub mint: Pubkey,
+    // Lamports, royalties included
+    pub price: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,9 @@
+use anchor_lang::prelude::*;
+
+// Prints of a skin bought by a wallet, at [b"purchases", master_mint, wallet], for max_per_wallet
+#[account]
+#[derive(InitSpace)]
+pub struct WalletPurchases {
+    pub count: u32,
+    pub bump: u8,
+}
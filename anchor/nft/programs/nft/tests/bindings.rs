@@ -0,0 +1,656 @@
+// The builders of skin_common against the program's own Accounts structs and instruction data
+// A field moved in an Accounts struct or a renamed instruction fails here, no nft.so needed
+
+use anchor_lang::{system_program, AccountSerialize, Event, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::metadata::mpl_token_metadata::{
+    self,
+    accounts::{EditionMarker, MasterEdition, Metadata},
+};
+use anchor_spl::token;
+use nft::{CreatorShare, CreatorStatus, Ingredient};
+use skin_common::nft_program::{self, ProgramEvent};
+use solana_sdk::{
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+fn authority() -> Pubkey {
+    Pubkey::find_program_address(&[b"authority"], &nft::ID).0
+}
+
+fn listing(master_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"listing", master_mint.as_ref()], &nft::ID).0
+}
+
+fn recipe(upgrade_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"recipe", upgrade_mint.as_ref()], &nft::ID).0
+}
+
+fn fuse_progress(upgrade_mint: &Pubkey, player: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"fuse", upgrade_mint.as_ref(), player.as_ref()], &nft::ID).0
+}
+
+fn trade_listing(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"trade", mint.as_ref()], &nft::ID).0
+}
+
+fn wallet_purchases(master_mint: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"purchases", master_mint.as_ref(), wallet.as_ref()], &nft::ID).0
+}
+
+fn edition_marker(master_mint: &Pubkey, edition: u64) -> Pubkey {
+    EditionMarker::find_pda(master_mint, &(edition / mpl_token_metadata::EDITION_MARKER_BIT_SIZE).to_string()).0
+}
+
+fn config() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &nft::ID).0
+}
+
+fn creator_record(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"creator", wallet.as_ref()], &nft::ID).0
+}
+
+fn program_data() -> Pubkey {
+    Pubkey::find_program_address(&[nft::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+// Same accounts in the same order with the same signer and writable flags, then the same data
+fn assert_builds(built: Instruction, accounts: impl ToAccountMetas, data: impl InstructionData) {
+    let expected = Instruction { program_id: nft::ID, accounts: accounts.to_account_metas(None), data: data.data() };
+
+    assert_eq!(built.program_id, expected.program_id);
+    for (i, (built, expected)) in built.accounts.iter().zip(&expected.accounts).enumerate() {
+        assert_eq!(built, expected, "account {}", i);
+    }
+    assert_eq!(built.accounts.len(), expected.accounts.len());
+    assert_eq!(built.data, expected.data);
+}
+
+#[test]
+fn create_collection() {
+    let (creator, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::CreateCollection { creator, mint, name: "Skins".to_string(), symbol: "SKIN".to_string() }
+            .instruction(),
+        nft::accounts::CreateCollection {
+            creator,
+            mint,
+            mint_authority: authority(),
+            metadata: Metadata::find_pda(&mint).0,
+            master_edition: MasterEdition::find_pda(&mint).0,
+            destination: get_associated_token_address(&creator, &mint),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+        },
+        nft::instruction::CreateCollection { name: "Skins".to_string(), symbol: "SKIN".to_string() },
+    );
+}
+
+#[test]
+fn create_nft() {
+    let (creator, mint, collection_mint, other) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::CreateNft {
+            creator,
+            mint,
+            collection_mint,
+            name: "CAT_Ak".to_string(),
+            symbol: "C_AK".to_string(),
+            metadata_uri: "https://files.test/cat_ak.json".to_string(),
+            supply: 10,
+            seller_fee_basis_points: 500,
+            creators: vec![
+                nft_program::CreatorShare { address: creator, share: 70 },
+                nft_program::CreatorShare { address: other, share: 30 },
+            ],
+            price: 5,
+            payment_mint: Some(other),
+        }
+        .instruction(),
+        nft::accounts::CreateNFT {
+            creator,
+            creator_record: creator_record(&creator),
+            mint,
+            mint_authority: authority(),
+            vault: get_associated_token_address(&authority(), &mint),
+            metadata: Metadata::find_pda(&mint).0,
+            master_edition: MasterEdition::find_pda(&mint).0,
+            collection_mint,
+            collection_metadata: Metadata::find_pda(&collection_mint).0,
+            collection_master_edition: MasterEdition::find_pda(&collection_mint).0,
+            listing: listing(&mint),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+        },
+        nft::instruction::CreateNft {
+            name: "CAT_Ak".to_string(),
+            symbol: "C_AK".to_string(),
+            metadata_uri: "https://files.test/cat_ak.json".to_string(),
+            supply: 10,
+            seller_fee_basis_points: 500,
+            creators: vec![CreatorShare { address: creator, share: 70 }, CreatorShare { address: other, share: 30 }],
+            price: 5,
+            payment_mint: Some(other),
+        },
+    );
+}
+
+// Paid in SOL and in an spl token, whose accounts fill the optional ones
+#[test]
+fn buy_nft() {
+    let (buyer, master_mint, new_mint, treasury, payment_mint) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let proof = vec![[7; 32], [9; 32]];
+
+    for payment_mint in [None, Some(payment_mint)] {
+        assert_builds(
+            nft_program::BuyNft {
+                buyer,
+                master_mint,
+                new_mint,
+                editions_printed: 247,
+                treasury,
+                price: 5,
+                payment_mint,
+                allowlist_proof: proof.clone(),
+            }
+            .instruction(),
+            nft::accounts::Buy {
+                buyer,
+                master_mint,
+                mint_authority: authority(),
+                vault: get_associated_token_address(&authority(), &master_mint),
+                metadata: Metadata::find_pda(&master_mint).0,
+                master_edition: MasterEdition::find_pda(&master_mint).0,
+                new_mint,
+                edition_token_account: get_associated_token_address(&buyer, &new_mint),
+                new_metadata: Metadata::find_pda(&new_mint).0,
+                new_edition: MasterEdition::find_pda(&new_mint).0,
+                edition_marker: edition_marker(&master_mint, 248),
+                listing: listing(&master_mint),
+                treasury,
+                wallet_purchases: wallet_purchases(&master_mint, &buyer),
+                payment_mint,
+                buyer_payment_account: payment_mint.map(|mint| get_associated_token_address(&buyer, &mint)),
+                treasury_payment_account: payment_mint.map(|mint| get_associated_token_address(&treasury, &mint)),
+                system_program: system_program::ID,
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                token_metadata_program: mpl_token_metadata::ID,
+            },
+            nft::instruction::BuyNft {
+                allowlist_proof: proof.clone(),
+                expected_price: 5,
+                expected_payment_mint: payment_mint,
+            },
+        );
+    }
+}
+
+#[test]
+fn set_price() {
+    let (creator, master_mint, payment_mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::SetPrice { creator, master_mint, price: 5, payment_mint: Some(payment_mint), treasury: creator }
+            .instruction(),
+        nft::accounts::SetPrice { creator, listing: listing(&master_mint) },
+        nft::instruction::SetPrice { price: 5, payment_mint: Some(payment_mint), treasury: creator },
+    );
+}
+
+#[test]
+fn update_skin() {
+    let (creator, master_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::UpdateSkin {
+            creator,
+            master_mint,
+            name: None,
+            metadata_uri: Some("https://files.test/cat_ak_2.json".to_string()),
+        }
+        .instruction(),
+        nft::accounts::UpdateSkin {
+            creator,
+            creator_record: creator_record(&creator),
+            listing: listing(&master_mint),
+            mint_authority: authority(),
+            metadata: Metadata::find_pda(&master_mint).0,
+            token_metadata_program: mpl_token_metadata::ID,
+        },
+        nft::instruction::UpdateSkin { name: None, metadata_uri: Some("https://files.test/cat_ak_2.json".to_string()) },
+    );
+}
+
+#[test]
+fn set_sale() {
+    let (creator, master_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::SetSale {
+            creator,
+            master_mint,
+            sale_start: 10,
+            sale_end: 20,
+            max_per_wallet: 3,
+            allowlist_root: Some([1; 32]),
+            public_start: 15,
+        }
+        .instruction(),
+        nft::accounts::SetSale { creator, listing: listing(&master_mint) },
+        nft::instruction::SetSale {
+            sale_start: 10,
+            sale_end: 20,
+            max_per_wallet: 3,
+            allowlist_root: Some([1; 32]),
+            public_start: 15,
+        },
+    );
+}
+
+#[test]
+fn set_paused() {
+    let (authority, master_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::SetPaused { authority, master_mint, paused: true }.instruction(),
+        nft::accounts::SetPaused { authority, config: config(), listing: listing(&master_mint) },
+        nft::instruction::SetPaused { paused: true },
+    );
+}
+
+#[test]
+fn set_recipe() {
+    let (creator, upgrade_mint, ingredient) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::SetRecipe {
+            creator,
+            upgrade_mint,
+            ingredients: vec![nft_program::Ingredient { master_mint: ingredient, count: 3 }],
+        }
+        .instruction(),
+        nft::accounts::SetRecipe {
+            creator,
+            listing: listing(&upgrade_mint),
+            recipe: recipe(&upgrade_mint),
+            system_program: system_program::ID,
+        },
+        nft::instruction::SetRecipe { ingredients: vec![Ingredient { master_mint: ingredient, count: 3 }] },
+    );
+}
+
+#[test]
+fn burn_ingredient() {
+    let (player, upgrade_mint, print_mint, master_mint) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::BurnIngredient { player, upgrade_mint, print_mint, master_mint, edition: 248 }.instruction(),
+        nft::accounts::BurnIngredient {
+            player,
+            recipe: recipe(&upgrade_mint),
+            progress: fuse_progress(&upgrade_mint, &player),
+            metadata: Metadata::find_pda(&print_mint).0,
+            print_mint,
+            print_token_account: get_associated_token_address(&player, &print_mint),
+            print_edition: MasterEdition::find_pda(&print_mint).0,
+            edition_marker: edition_marker(&master_mint, 248),
+            master_mint,
+            master_vault: get_associated_token_address(&authority(), &master_mint),
+            master_edition: MasterEdition::find_pda(&master_mint).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+        },
+        nft::instruction::BurnIngredient {},
+    );
+}
+
+#[test]
+fn fuse() {
+    let (player, upgrade_mint, new_mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::Fuse { player, upgrade_mint, new_mint, editions_printed: 3 }.instruction(),
+        nft::accounts::Fuse {
+            player,
+            recipe: recipe(&upgrade_mint),
+            progress: fuse_progress(&upgrade_mint, &player),
+            upgrade_mint,
+            mint_authority: authority(),
+            vault: get_associated_token_address(&authority(), &upgrade_mint),
+            metadata: Metadata::find_pda(&upgrade_mint).0,
+            master_edition: MasterEdition::find_pda(&upgrade_mint).0,
+            new_mint,
+            edition_token_account: get_associated_token_address(&player, &new_mint),
+            new_metadata: Metadata::find_pda(&new_mint).0,
+            new_edition: MasterEdition::find_pda(&new_mint).0,
+            edition_marker: edition_marker(&upgrade_mint, 4),
+            listing: listing(&upgrade_mint),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+        },
+        nft::instruction::Fuse {},
+    );
+}
+
+#[test]
+fn list() {
+    let (seller, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::List { seller, mint, price: 5 }.instruction(),
+        nft::accounts::List {
+            seller,
+            mint,
+            seller_token_account: get_associated_token_address(&seller, &mint),
+            mint_authority: authority(),
+            metadata: Metadata::find_pda(&mint).0,
+            trade_listing: trade_listing(&mint),
+            escrow: get_associated_token_address(&trade_listing(&mint), &mint),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        nft::instruction::List { price: 5 },
+    );
+}
+
+#[test]
+fn cancel() {
+    let (seller, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::Cancel { seller, mint }.instruction(),
+        nft::accounts::Cancel {
+            seller,
+            mint,
+            trade_listing: trade_listing(&mint),
+            escrow: get_associated_token_address(&trade_listing(&mint), &mint),
+            seller_token_account: get_associated_token_address(&seller, &mint),
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        },
+        nft::instruction::Cancel {},
+    );
+}
+
+// The royalty wallets follow the Accounts struct as remaining accounts
+#[test]
+fn purchase_listing() {
+    let (buyer, seller, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let creators = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let mut accounts = nft::accounts::PurchaseListing {
+        buyer,
+        seller,
+        mint,
+        trade_listing: trade_listing(&mint),
+        escrow: get_associated_token_address(&trade_listing(&mint), &mint),
+        buyer_token_account: get_associated_token_address(&buyer, &mint),
+        metadata: Metadata::find_pda(&mint).0,
+        system_program: system_program::ID,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(creators.iter().map(|creator| AccountMeta::new(*creator, false)));
+
+    assert_builds(
+        nft_program::PurchaseListing { buyer, seller, mint, price: 5, creators }.instruction(),
+        accounts,
+        nft::instruction::PurchaseListing { price: 5 },
+    );
+}
+
+#[test]
+fn initialize_config() {
+    let (authority, admin) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::InitializeConfig { authority, admin }.instruction(),
+        nft::accounts::InitializeConfig {
+            authority,
+            config: config(),
+            program: nft::ID,
+            program_data: program_data(),
+            system_program: system_program::ID,
+        },
+        nft::instruction::InitializeConfig { admin },
+    );
+}
+
+#[test]
+fn set_admin() {
+    let (admin, new_admin) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::SetAdmin { admin, new_admin }.instruction(),
+        nft::accounts::SetAdmin { admin, config: config() },
+        nft::instruction::SetAdmin { new_admin },
+    );
+}
+
+#[test]
+fn add_creator() {
+    let (admin, wallet) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::AddCreator { admin, wallet }.instruction(),
+        nft::accounts::AddCreator {
+            admin,
+            config: config(),
+            creator_record: creator_record(&wallet),
+            system_program: system_program::ID,
+        },
+        nft::instruction::AddCreator { wallet },
+    );
+}
+
+#[test]
+fn set_creator_status() {
+    let (admin, wallet) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::SetCreatorStatus { admin, wallet, status: nft_program::CreatorStatus::Suspended }.instruction(),
+        nft::accounts::SetCreatorStatus { admin, config: config(), creator_record: creator_record(&wallet) },
+        nft::instruction::SetCreatorStatus { status: CreatorStatus::Suspended },
+    );
+}
+
+#[test]
+fn remove_creator() {
+    let (admin, wallet) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    assert_builds(
+        nft_program::RemoveCreator { admin, wallet }.instruction(),
+        nft::accounts::RemoveCreator { admin, config: config(), creator_record: creator_record(&wallet) },
+        nft::instruction::RemoveCreator {},
+    );
+}
+
+// What emit! logs is what the indexer of the game parses
+#[test]
+fn events() {
+    let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let cases = [
+        (
+            nft::events::CollectionCreated {
+                mint: a,
+                creator: b,
+                name: "Skins".to_string(),
+                symbol: "SKIN".to_string(),
+            }
+            .data(),
+            ProgramEvent::CollectionCreated(nft_program::CollectionCreated {
+                mint: a,
+                creator: b,
+                name: "Skins".to_string(),
+                symbol: "SKIN".to_string(),
+            }),
+        ),
+        (
+            nft::events::SkinCreated {
+                master_mint: a,
+                creator: b,
+                collection_mint: c,
+                name: "CAT_Ak".to_string(),
+                uri: "https://files.test/cat_ak.json".to_string(),
+                max_supply: 10,
+                seller_fee_basis_points: 500,
+            }
+            .data(),
+            ProgramEvent::SkinCreated(nft_program::SkinCreated {
+                master_mint: a,
+                creator: b,
+                collection_mint: c,
+                name: "CAT_Ak".to_string(),
+                uri: "https://files.test/cat_ak.json".to_string(),
+                max_supply: 10,
+                seller_fee_basis_points: 500,
+            }),
+        ),
+        (
+            nft::events::SkinPurchased {
+                master_mint: a,
+                edition_mint: b,
+                edition: 3,
+                buyer: c,
+                price: 5,
+                payment_mint: Some(a),
+            }
+            .data(),
+            ProgramEvent::SkinPurchased(nft_program::SkinPurchased {
+                master_mint: a,
+                edition_mint: b,
+                edition: 3,
+                buyer: c,
+                price: 5,
+                payment_mint: Some(a),
+            }),
+        ),
+        (
+            nft::events::SkinFused { upgrade_mint: a, edition_mint: b, edition: 3, player: c }.data(),
+            ProgramEvent::SkinFused(nft_program::SkinFused { upgrade_mint: a, edition_mint: b, edition: 3, player: c }),
+        ),
+        (
+            nft::events::SkinListed { mint: a, seller: b, price: 5 }.data(),
+            ProgramEvent::SkinListed(nft_program::SkinListed { mint: a, seller: b, price: 5 }),
+        ),
+        (
+            nft::events::SkinDelisted { mint: a, seller: b }.data(),
+            ProgramEvent::SkinDelisted(nft_program::SkinDelisted { mint: a, seller: b }),
+        ),
+        (
+            nft::events::SkinTraded { mint: a, seller: b, buyer: c, price: 5, royalties: 1 }.data(),
+            ProgramEvent::SkinTraded(nft_program::SkinTraded { mint: a, seller: b, buyer: c, price: 5, royalties: 1 }),
+        ),
+    ];
+
+    for (data, event) in cases {
+        assert_eq!(ProgramEvent::parse(&data), Some(event.clone()));
+        assert_eq!(event.to_log_data(), data);
+    }
+}
+
+fn account_data(account: impl AccountSerialize) -> Vec<u8> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data
+}
+
+// The accounts the game and skin_tool read
+#[test]
+fn accounts() {
+    let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let listing = nft_program::SkinListing {
+        master_mint: a,
+        creator: b,
+        treasury: c,
+        price: 5,
+        payment_mint: Some(a),
+        bump: 254,
+        revision: 2,
+        sale_start: 10,
+        sale_end: 20,
+        paused: true,
+        max_per_wallet: 3,
+        allowlist_root: Some([1; 32]),
+        public_start: 15,
+        editions_printed: 7,
+    };
+    let data = account_data(nft::SkinListing {
+        master_mint: a,
+        creator: b,
+        treasury: c,
+        price: 5,
+        payment_mint: Some(a),
+        bump: 254,
+        revision: 2,
+        sale_start: 10,
+        sale_end: 20,
+        paused: true,
+        max_per_wallet: 3,
+        allowlist_root: Some([1; 32]),
+        public_start: 15,
+        editions_printed: 7,
+    });
+    assert_eq!(nft_program::SkinListing::parse(&data), Ok(listing.clone()));
+    assert_eq!(listing.to_account_data(), data);
+
+    let ingredients = vec![nft_program::Ingredient { master_mint: c, count: 2 }];
+    let recipe =
+        nft_program::Recipe { upgrade_mint: a, creator: b, ingredients: ingredients.clone(), revision: 1, bump: 253 };
+    let data = account_data(nft::Recipe {
+        upgrade_mint: a,
+        creator: b,
+        ingredients: vec![Ingredient { master_mint: c, count: 2 }],
+        revision: 1,
+        bump: 253,
+    });
+    assert_eq!(nft_program::Recipe::parse(&data), Ok(recipe.clone()));
+    assert_eq!(recipe.to_account_data(), data);
+
+    let progress = nft_program::FuseProgress {
+        player: a,
+        upgrade_mint: b,
+        recipe_revision: 1,
+        ingredients,
+        burned: vec![nft_program::Ingredient { master_mint: c, count: 1 }],
+        bump: 252,
+    };
+    let data = account_data(nft::FuseProgress {
+        player: a,
+        upgrade_mint: b,
+        recipe_revision: 1,
+        ingredients: vec![Ingredient { master_mint: c, count: 2 }],
+        burned: vec![Ingredient { master_mint: c, count: 1 }],
+        bump: 252,
+    });
+    assert_eq!(nft_program::FuseProgress::parse(&data), Ok(progress.clone()));
+    assert_eq!(progress.to_account_data(), data);
+
+    let trade = nft_program::TradeListing { seller: a, mint: b, price: 5, bump: 251 };
+    let data = account_data(nft::TradeListing { seller: a, mint: b, price: 5, bump: 251 });
+    assert_eq!(nft_program::TradeListing::parse(&data), Ok(trade.clone()));
+    assert_eq!(trade.to_account_data(), data);
+
+    let data = account_data(nft::WalletPurchases { count: 4, bump: 250 });
+    assert_eq!(nft_program::parse_wallet_purchases(&data), Ok(4));
+}
@@ -0,0 +1,1038 @@
+// Runs the nft program and Metaplex token metadata in an in-process bank, no validator or Node needed
+// nft is deployed through the upgradeable loader, its upgrade authority is the admin that creates the config
+// Needs target/deploy/nft.so (anchor build) and the Metaplex program next to it, so a plain cargo test skips it:
+//   solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s target/deploy/metaplex_token_metadata.so
+//   SBF_OUT_DIR=$(pwd)/target/deploy cargo test -p nft --features test-sbf
+#![cfg(feature = "test-sbf")]
+
+use anchor_lang::{system_program, AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::metadata::mpl_token_metadata::{
+    self,
+    accounts::{EditionMarker, MasterEdition, Metadata},
+    types::CollectionDetails,
+};
+use anchor_spl::token;
+use nft::{error::ErrorCode, CreatorShare, CreatorStatus, Ingredient};
+use solana_program_test::{find_file, read_file, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    compute_budget::ComputeBudgetInstruction,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const LAMPORTS: u64 = 10_000_000_000;
+
+struct Test {
+    context: ProgramTestContext,
+    // Upgrade authority of the program, creates the config and runs the registry
+    admin: Keypair,
+    // Registered, makes the collection and the skins
+    creator: Keypair,
+    buyer: Keypair,
+    // Buys from the buyer on the market
+    trader: Keypair,
+}
+
+fn authority() -> Pubkey {
+    Pubkey::find_program_address(&[b"authority"], &nft::ID).0
+}
+
+fn listing(master_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"listing", master_mint.as_ref()], &nft::ID).0
+}
+
+fn recipe(upgrade_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"recipe", upgrade_mint.as_ref()], &nft::ID).0
+}
+
+fn fuse_progress(upgrade_mint: &Pubkey, player: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"fuse", upgrade_mint.as_ref(), player.as_ref()], &nft::ID).0
+}
+
+fn trade_listing(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"trade", mint.as_ref()], &nft::ID).0
+}
+
+fn wallet_purchases(master_mint: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"purchases", master_mint.as_ref(), wallet.as_ref()], &nft::ID).0
+}
+
+// Metaplex keeps the bits of 248 editions per marker
+fn edition_marker(master_mint: &Pubkey, edition: u64) -> Pubkey {
+    EditionMarker::find_pda(master_mint, &(edition / mpl_token_metadata::EDITION_MARKER_BIT_SIZE).to_string()).0
+}
+
+fn config() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &nft::ID).0
+}
+
+fn creator_record(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"creator", wallet.as_ref()], &nft::ID).0
+}
+
+fn program_data() -> Pubkey {
+    Pubkey::find_program_address(&[nft::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+// The program and program data accounts a deploy leaves, ProgramTest::add_program would use the old loader
+fn add_upgradeable_program(program: &mut ProgramTest, upgrade_authority: &Pubkey) {
+    let elf = find_file("nft.so").expect("No nft.so in SBF_OUT_DIR, run anchor build, see the top of tests/program.rs");
+    let elf = read_file(elf);
+    let state = UpgradeableLoaderState::ProgramData { slot: 0, upgrade_authority_address: Some(*upgrade_authority) };
+    let space = UpgradeableLoaderState::size_of_programdata(elf.len());
+    let mut data = Account::new_data_with_space(Rent::default().minimum_balance(space), &state, space, &bpf_loader_upgradeable::ID).unwrap();
+    data.data[UpgradeableLoaderState::size_of_programdata_metadata()..].copy_from_slice(&elf);
+    program.add_account(program_data(), data);
+
+    let state = UpgradeableLoaderState::Program { programdata_address: program_data() };
+    let mut account = Account::new_data(LAMPORTS, &state, &bpf_loader_upgradeable::ID).unwrap();
+    account.executable = true;
+    program.add_account(nft::ID, account);
+}
+
+fn error_code(error: BanksClientError) -> u32 {
+    match error.unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => code,
+        other => panic!("Not a program error: {:?}", other),
+    }
+}
+
+impl Test {
+    async fn start() -> Self {
+        let admin = Keypair::new();
+        let creator = Keypair::new();
+        let buyer = Keypair::new();
+        let trader = Keypair::new();
+
+        let mut program = ProgramTest::default();
+        add_upgradeable_program(&mut program, &admin.pubkey());
+        program.add_program("metaplex_token_metadata", mpl_token_metadata::ID, None);
+        for wallet in [&admin, &creator, &buyer, &trader] {
+            program.add_account(wallet.pubkey(), Account::new(LAMPORTS, 0, &system_program::ID));
+        }
+
+        let context = program.start_with_context().await;
+        let mut test = Self { context, admin, creator, buyer, trader };
+        test.initialize_config().await;
+        let creator = test.creator.pubkey();
+        test.add_creator(&creator).await;
+
+        test
+    }
+
+    async fn send(&mut self, instruction: Instruction, signers: &[&Keypair]) -> Result<(), BanksClientError> {
+        // The Metaplex cpis take more than the default compute budget
+        let instructions = [ComputeBudgetInstruction::set_compute_unit_limit(600_000), instruction];
+        let blockhash = self.context.banks_client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(&instructions, Some(&signers[0].pubkey()), signers, blockhash);
+
+        self.context.banks_client.process_transaction(transaction).await
+    }
+
+    async fn initialize_config(&mut self) {
+        let admin = self.admin.insecure_clone();
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::InitializeConfig {
+                authority: admin.pubkey(),
+                config: config(),
+                program: nft::ID,
+                program_data: program_data(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: nft::instruction::InitializeConfig { admin: admin.pubkey() }.data(),
+        };
+
+        self.send(instruction, &[&admin]).await.unwrap();
+    }
+
+    async fn add_creator(&mut self, wallet: &Pubkey) {
+        let admin = self.admin.insecure_clone();
+        self.add_creator_as(&admin, wallet).await.unwrap();
+    }
+
+    async fn add_creator_as(&mut self, signer: &Keypair, wallet: &Pubkey) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::AddCreator {
+                admin: signer.pubkey(),
+                config: config(),
+                creator_record: creator_record(wallet),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: nft::instruction::AddCreator { wallet: *wallet }.data(),
+        };
+
+        self.send(instruction, &[signer]).await
+    }
+
+    async fn set_creator_status(&mut self, wallet: &Pubkey, status: CreatorStatus) {
+        let admin = self.admin.insecure_clone();
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::SetCreatorStatus {
+                admin: admin.pubkey(),
+                config: config(),
+                creator_record: creator_record(wallet),
+            }
+            .to_account_metas(None),
+            data: nft::instruction::SetCreatorStatus { status }.data(),
+        };
+
+        self.send(instruction, &[&admin]).await.unwrap();
+    }
+
+    async fn remove_creator(&mut self, wallet: &Pubkey) {
+        let admin = self.admin.insecure_clone();
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::RemoveCreator {
+                admin: admin.pubkey(),
+                config: config(),
+                creator_record: creator_record(wallet),
+            }
+            .to_account_metas(None),
+            data: nft::instruction::RemoveCreator {}.data(),
+        };
+
+        self.send(instruction, &[&admin]).await.unwrap();
+    }
+
+    async fn create_collection(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let creator = self.creator.insecure_clone();
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::CreateCollection {
+                creator: creator.pubkey(),
+                mint: mint.pubkey(),
+                mint_authority: authority(),
+                metadata: Metadata::find_pda(&mint.pubkey()).0,
+                master_edition: MasterEdition::find_pda(&mint.pubkey()).0,
+                destination: get_associated_token_address(&creator.pubkey(), &mint.pubkey()),
+                system_program: system_program::ID,
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                token_metadata_program: mpl_token_metadata::ID,
+            }
+            .to_account_metas(None),
+            data: nft::instruction::CreateCollection { name: "Skins".to_string(), symbol: "SKIN".to_string() }.data(),
+        };
+
+        self.send(instruction, &[&creator, &mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    // Listed at price lamports, 5% royalties all to the creator
+    fn create_nft_instruction(
+        &self,
+        creator: &Pubkey,
+        mint: &Pubkey,
+        collection_mint: &Pubkey,
+        supply: u64,
+        price: u64,
+    ) -> Instruction {
+        let creators = vec![CreatorShare { address: *creator, share: 100 }];
+        self.create_nft_instruction_with(creator, mint, collection_mint, supply, price, (500, creators))
+    }
+
+    fn create_nft_instruction_with(
+        &self,
+        creator: &Pubkey,
+        mint: &Pubkey,
+        collection_mint: &Pubkey,
+        supply: u64,
+        price: u64,
+        royalties: (u16, Vec<CreatorShare>),
+    ) -> Instruction {
+        Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::CreateNFT {
+                creator: *creator,
+                creator_record: creator_record(creator),
+                mint: *mint,
+                mint_authority: authority(),
+                vault: get_associated_token_address(&authority(), mint),
+                metadata: Metadata::find_pda(mint).0,
+                master_edition: MasterEdition::find_pda(mint).0,
+                collection_mint: *collection_mint,
+                collection_metadata: Metadata::find_pda(collection_mint).0,
+                collection_master_edition: MasterEdition::find_pda(collection_mint).0,
+                listing: listing(mint),
+                system_program: system_program::ID,
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                token_metadata_program: mpl_token_metadata::ID,
+            }
+            .to_account_metas(None),
+            data: nft::instruction::CreateNft {
+                name: "CAT_Ak".to_string(),
+                symbol: "C_AK".to_string(),
+                metadata_uri: "https://files.test/cat_ak.json".to_string(),
+                supply,
+                seller_fee_basis_points: royalties.0,
+                creators: royalties.1,
+                price,
+                payment_mint: None,
+            }
+            .data(),
+        }
+    }
+
+    // Free, most tests don't care about the price
+    async fn create_nft(&mut self, collection_mint: &Pubkey, supply: u64) -> Pubkey {
+        self.create_nft_at(collection_mint, supply, 0).await
+    }
+
+    async fn create_nft_at(&mut self, collection_mint: &Pubkey, supply: u64, price: u64) -> Pubkey {
+        let mint = Keypair::new();
+        let creator = self.creator.insecure_clone();
+        let instruction = self.create_nft_instruction(&creator.pubkey(), &mint.pubkey(), collection_mint, supply, price);
+
+        self.send(instruction, &[&creator, &mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    // Accounts of a buy of the next edition, tests swap some of them
+    fn buy_accounts(&self, master_mint: &Pubkey, new_mint: &Pubkey, next_edition: u64) -> nft::accounts::Buy {
+        let buyer = self.buyer.pubkey();
+        nft::accounts::Buy {
+            buyer,
+            master_mint: *master_mint,
+            mint_authority: authority(),
+            vault: get_associated_token_address(&authority(), master_mint),
+            metadata: Metadata::find_pda(master_mint).0,
+            master_edition: MasterEdition::find_pda(master_mint).0,
+            new_mint: *new_mint,
+            edition_token_account: get_associated_token_address(&buyer, new_mint),
+            new_metadata: Metadata::find_pda(new_mint).0,
+            new_edition: MasterEdition::find_pda(new_mint).0,
+            edition_marker: edition_marker(master_mint, next_edition),
+            listing: listing(master_mint),
+            treasury: self.creator.pubkey(),
+            wallet_purchases: wallet_purchases(master_mint, &buyer),
+            payment_mint: None,
+            buyer_payment_account: None,
+            treasury_payment_account: None,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+        }
+    }
+
+    // The skins of the tests are free unless set_price changed it
+    async fn buy_with(&mut self, accounts: nft::accounts::Buy, new_mint: &Keypair) -> Result<(), BanksClientError> {
+        self.buy_at(accounts, new_mint, 0, None).await
+    }
+
+    // expected_price and expected_payment_mint are what the buyer saw in the shop
+    async fn buy_at(
+        &mut self,
+        accounts: nft::accounts::Buy,
+        new_mint: &Keypair,
+        expected_price: u64,
+        expected_payment_mint: Option<Pubkey>,
+    ) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: accounts.to_account_metas(None),
+            data: nft::instruction::BuyNft { allowlist_proof: Vec::new(), expected_price, expected_payment_mint }.data(),
+        };
+        let buyer = self.buyer.insecure_clone();
+
+        self.send(instruction, &[&buyer, new_mint]).await
+    }
+
+    // Returns the mint of the print
+    async fn buy(&mut self, master_mint: &Pubkey) -> Result<Pubkey, BanksClientError> {
+        let new_mint = Keypair::new();
+        let next_edition = self.editions_printed(master_mint).await + 1;
+        let accounts = self.buy_accounts(master_mint, &new_mint.pubkey(), next_edition);
+
+        self.buy_with(accounts, &new_mint).await?;
+        Ok(new_mint.pubkey())
+    }
+
+    // Paid in SOL to the creator
+    async fn set_price(&mut self, master_mint: &Pubkey, price: u64) {
+        let creator = self.creator.insecure_clone();
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::SetPrice { creator: creator.pubkey(), listing: listing(master_mint) }
+                .to_account_metas(None),
+            data: nft::instruction::SetPrice { price, payment_mint: None, treasury: creator.pubkey() }.data(),
+        };
+
+        self.send(instruction, &[&creator]).await.unwrap();
+    }
+
+    async fn set_recipe(&mut self, upgrade_mint: &Pubkey, ingredients: Vec<Ingredient>) {
+        let creator = self.creator.insecure_clone();
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::SetRecipe {
+                creator: creator.pubkey(),
+                listing: listing(upgrade_mint),
+                recipe: recipe(upgrade_mint),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: nft::instruction::SetRecipe { ingredients }.data(),
+        };
+
+        self.send(instruction, &[&creator]).await.unwrap();
+    }
+
+    // The buyer burns edition print_mint of master_mint toward upgrade_mint
+    async fn burn_ingredient(
+        &mut self,
+        upgrade_mint: &Pubkey,
+        master_mint: &Pubkey,
+        print_mint: &Pubkey,
+        edition: u64,
+    ) -> Result<(), BanksClientError> {
+        let player = self.buyer.insecure_clone();
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::BurnIngredient {
+                player: player.pubkey(),
+                recipe: recipe(upgrade_mint),
+                progress: fuse_progress(upgrade_mint, &player.pubkey()),
+                metadata: Metadata::find_pda(print_mint).0,
+                print_mint: *print_mint,
+                print_token_account: get_associated_token_address(&player.pubkey(), print_mint),
+                print_edition: MasterEdition::find_pda(print_mint).0,
+                edition_marker: edition_marker(master_mint, edition),
+                master_mint: *master_mint,
+                master_vault: get_associated_token_address(&authority(), master_mint),
+                master_edition: MasterEdition::find_pda(master_mint).0,
+                system_program: system_program::ID,
+                token_program: token::ID,
+                token_metadata_program: mpl_token_metadata::ID,
+            }
+            .to_account_metas(None),
+            data: nft::instruction::BurnIngredient {}.data(),
+        };
+
+        self.send(instruction, &[&player]).await
+    }
+
+    async fn fuse(&mut self, upgrade_mint: &Pubkey) -> Result<(), BanksClientError> {
+        let player = self.buyer.insecure_clone();
+        let new_mint = Keypair::new();
+        let next_edition = self.editions_printed(upgrade_mint).await + 1;
+        let buy = self.buy_accounts(upgrade_mint, &new_mint.pubkey(), next_edition);
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::Fuse {
+                player: player.pubkey(),
+                recipe: recipe(upgrade_mint),
+                progress: fuse_progress(upgrade_mint, &player.pubkey()),
+                upgrade_mint: *upgrade_mint,
+                mint_authority: buy.mint_authority,
+                vault: buy.vault,
+                metadata: buy.metadata,
+                master_edition: buy.master_edition,
+                new_mint: buy.new_mint,
+                edition_token_account: buy.edition_token_account,
+                new_metadata: buy.new_metadata,
+                new_edition: buy.new_edition,
+                edition_marker: buy.edition_marker,
+                listing: buy.listing,
+                system_program: system_program::ID,
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                token_metadata_program: mpl_token_metadata::ID,
+            }
+            .to_account_metas(None),
+            data: nft::instruction::Fuse {}.data(),
+        };
+
+        self.send(instruction, &[&player, &new_mint]).await
+    }
+
+    async fn list(&mut self, seller: &Keypair, mint: &Pubkey, price: u64) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::List {
+                seller: seller.pubkey(),
+                mint: *mint,
+                seller_token_account: get_associated_token_address(&seller.pubkey(), mint),
+                mint_authority: authority(),
+                metadata: Metadata::find_pda(mint).0,
+                trade_listing: trade_listing(mint),
+                escrow: get_associated_token_address(&trade_listing(mint), mint),
+                system_program: system_program::ID,
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+            }
+            .to_account_metas(None),
+            data: nft::instruction::List { price }.data(),
+        };
+
+        self.send(instruction, &[seller]).await
+    }
+
+    async fn cancel(&mut self, seller: &Keypair, mint: &Pubkey) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::Cancel {
+                seller: seller.pubkey(),
+                mint: *mint,
+                trade_listing: trade_listing(mint),
+                escrow: get_associated_token_address(&trade_listing(mint), mint),
+                seller_token_account: get_associated_token_address(&seller.pubkey(), mint),
+                system_program: system_program::ID,
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+            }
+            .to_account_metas(None),
+            data: nft::instruction::Cancel {}.data(),
+        };
+
+        self.send(instruction, &[seller]).await
+    }
+
+    // The trader buys the print the buyer listed, creators are the royalty wallets passed along
+    async fn purchase_listing(&mut self, mint: &Pubkey, price: u64, creators: &[Pubkey]) -> Result<(), BanksClientError> {
+        let trader = self.trader.insecure_clone();
+        let mut accounts = nft::accounts::PurchaseListing {
+            buyer: trader.pubkey(),
+            seller: self.buyer.pubkey(),
+            mint: *mint,
+            trade_listing: trade_listing(mint),
+            escrow: get_associated_token_address(&trade_listing(mint), mint),
+            buyer_token_account: get_associated_token_address(&trader.pubkey(), mint),
+            metadata: Metadata::find_pda(mint).0,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(creators.iter().map(|creator| AccountMeta::new(*creator, false)));
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts,
+            data: nft::instruction::PurchaseListing { price }.data(),
+        };
+
+        self.send(instruction, &[&trader]).await
+    }
+
+    async fn update_skin(&mut self, master_mint: &Pubkey, metadata_uri: &str) -> Result<(), BanksClientError> {
+        let creator = self.creator.insecure_clone();
+        let instruction = Instruction {
+            program_id: nft::ID,
+            accounts: nft::accounts::UpdateSkin {
+                creator: creator.pubkey(),
+                creator_record: creator_record(&creator.pubkey()),
+                listing: listing(master_mint),
+                mint_authority: authority(),
+                metadata: Metadata::find_pda(master_mint).0,
+                token_metadata_program: mpl_token_metadata::ID,
+            }
+            .to_account_metas(None),
+            data: nft::instruction::UpdateSkin { name: None, metadata_uri: Some(metadata_uri.to_string()) }.data(),
+        };
+
+        self.send(instruction, &[&creator]).await
+    }
+
+    async fn metadata_uri(&mut self, master_mint: &Pubkey) -> String {
+        let account = self
+            .context
+            .banks_client
+            .get_account(Metadata::find_pda(master_mint).0)
+            .await
+            .unwrap()
+            .unwrap();
+        Metadata::from_bytes(&account.data).unwrap().uri.trim_matches('\0').to_string()
+    }
+
+    // Token account: mint(32) owner(32) amount(8), None once closed
+    async fn token_amount(&mut self, address: Pubkey) -> Option<u64> {
+        let account = self.context.banks_client.get_account(address).await.unwrap()?;
+        Some(u64::from_le_bytes(account.data[64..72].try_into().unwrap()))
+    }
+
+    async fn balance(&mut self, wallet: Pubkey) -> u64 {
+        self.context.banks_client.get_balance(wallet).await.unwrap()
+    }
+
+    async fn exists(&mut self, address: Pubkey) -> bool {
+        self.context.banks_client.get_account(address).await.unwrap().is_some()
+    }
+
+    // MasterEditionV2: key(1) supply(8) max_supply(1 + 8)
+    async fn supply(&mut self, master_mint: &Pubkey) -> u64 {
+        let account = self
+            .context
+            .banks_client
+            .get_account(MasterEdition::find_pda(master_mint).0)
+            .await
+            .unwrap()
+            .unwrap();
+        u64::from_le_bytes(account.data[1..9].try_into().unwrap())
+    }
+
+    // What the next edition is numbered from, burns don't lower it like the supply
+    async fn editions_printed(&mut self, master_mint: &Pubkey) -> u64 {
+        let account = self.context.banks_client.get_account(listing(master_mint)).await.unwrap().unwrap();
+        nft::SkinListing::try_deserialize(&mut account.data.as_slice()).unwrap().editions_printed
+    }
+
+    // Prints until the master edition is at supply, to get to the editions of a later marker
+    async fn buy_until(&mut self, master_mint: &Pubkey, supply: u64) {
+        while self.supply(master_mint).await < supply {
+            self.buy(master_mint).await.unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn create_collection_and_skin() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 5).await;
+
+    assert_eq!(test.supply(&skin).await, 0);
+    let listing = test.context.banks_client.get_account(listing(&skin)).await.unwrap();
+    assert!(listing.is_some());
+
+    // Verified into the collection, which counts it
+    let account = test.context.banks_client.get_account(Metadata::find_pda(&skin).0).await.unwrap().unwrap();
+    let collection_of_skin = Metadata::from_bytes(&account.data).unwrap().collection.unwrap();
+    assert_eq!((collection_of_skin.key, collection_of_skin.verified), (collection, true));
+    let account = test.context.banks_client.get_account(Metadata::find_pda(&collection).0).await.unwrap().unwrap();
+    let details = Metadata::from_bytes(&account.data).unwrap().collection_details;
+    assert_eq!(details, Some(CollectionDetails::V1 { size: 1 }));
+}
+
+#[tokio::test]
+async fn buy_prints_the_next_edition() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 5).await;
+
+    test.buy(&skin).await.unwrap();
+    test.buy(&skin).await.unwrap();
+    assert_eq!(test.supply(&skin).await, 2);
+}
+
+#[tokio::test]
+async fn buy_across_the_edition_marker_boundary() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 300).await;
+    test.buy_until(&skin, 246).await;
+
+    // 247 is the last edition of marker 0, 248 and 249 are on marker 1
+    for edition in [247, 248, 249] {
+        test.buy(&skin).await.unwrap();
+        assert_eq!(test.supply(&skin).await, edition);
+    }
+    assert_ne!(edition_marker(&skin, 247), edition_marker(&skin, 248));
+    assert_eq!(edition_marker(&skin, 248), edition_marker(&skin, 249));
+}
+
+#[tokio::test]
+async fn buy_rejects_the_marker_of_another_edition() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 300).await;
+    test.buy_until(&skin, 247).await;
+
+    // supply / 248 was the old seed, it is still marker 0 for edition 248
+    let new_mint = Keypair::new();
+    let mut accounts = test.buy_accounts(&skin, &new_mint.pubkey(), 248);
+    accounts.edition_marker = edition_marker(&skin, 247);
+    let error = test.buy_with(accounts, &new_mint).await.unwrap_err();
+
+    assert_eq!(error_code(error), u32::from(ErrorCode::EditionMarkerMismatch));
+    assert_eq!(test.supply(&skin).await, 247);
+}
+
+#[tokio::test]
+async fn buy_fails_once_sold_out() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 2).await;
+
+    test.buy(&skin).await.unwrap();
+    test.buy(&skin).await.unwrap();
+    let error = test.buy(&skin).await.unwrap_err();
+
+    assert_eq!(error_code(error), u32::from(ErrorCode::SoldOut));
+    assert_eq!(test.supply(&skin).await, 2);
+}
+
+#[tokio::test]
+async fn buy_rejects_substituted_accounts() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 5).await;
+    let other = test.create_nft(&collection, 5).await;
+
+    // The listing of another skin, to pay its price instead
+    let new_mint = Keypair::new();
+    let mut accounts = test.buy_accounts(&skin, &new_mint.pubkey(), 1);
+    accounts.listing = listing(&other);
+    let error = test.buy_with(accounts, &new_mint).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(anchor_lang::error::ErrorCode::ConstraintSeeds));
+
+    // Paying someone else than the listing's treasury
+    let mut accounts = test.buy_accounts(&skin, &new_mint.pubkey(), 1);
+    accounts.treasury = test.buyer.pubkey();
+    let error = test.buy_with(accounts, &new_mint).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(anchor_lang::error::ErrorCode::ConstraintHasOne));
+
+    // The vault of another skin
+    let mut accounts = test.buy_accounts(&skin, &new_mint.pubkey(), 1);
+    accounts.vault = get_associated_token_address(&authority(), &other);
+    let error = test.buy_with(accounts, &new_mint).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(anchor_lang::error::ErrorCode::ConstraintAssociated));
+
+    assert_eq!(test.supply(&skin).await, 0);
+}
+
+#[tokio::test]
+async fn buy_rejects_a_price_changed_since_the_shop_showed_it() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft_at(&collection, 5, 1_000_000).await;
+
+    // Priced from the start, it is never free
+    let new_mint = Keypair::new();
+    let accounts = test.buy_accounts(&skin, &new_mint.pubkey(), 1);
+    let error = test.buy_at(accounts, &new_mint, 0, None).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::PriceMismatch));
+
+    // The shop still showed the old price, or paid in a token
+    test.set_price(&skin, 2_000_000).await;
+    let accounts = test.buy_accounts(&skin, &new_mint.pubkey(), 1);
+    let error = test.buy_at(accounts, &new_mint, 1_000_000, None).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::PriceMismatch));
+    let accounts = test.buy_accounts(&skin, &new_mint.pubkey(), 1);
+    let error = test.buy_at(accounts, &new_mint, 2_000_000, Some(Pubkey::new_unique())).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::PriceMismatch));
+    assert_eq!(test.supply(&skin).await, 0);
+
+    let creator = test.creator.pubkey();
+    let creator_before = test.balance(creator).await;
+    let accounts = test.buy_accounts(&skin, &new_mint.pubkey(), 1);
+    test.buy_at(accounts, &new_mint, 2_000_000, None).await.unwrap();
+    assert_eq!(test.balance(creator).await - creator_before, 2_000_000);
+    assert_eq!(test.supply(&skin).await, 1);
+}
+
+#[tokio::test]
+async fn create_nft_rejects_what_is_not_a_collection() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 5).await;
+
+    // Minted by our authority too, but a skin
+    let mint = Keypair::new();
+    let creator = test.creator.insecure_clone();
+    let instruction = test.create_nft_instruction(&creator.pubkey(), &mint.pubkey(), &skin, 5, 0);
+    let error = test.send(instruction, &[&creator, &mint]).await.unwrap_err();
+
+    assert_eq!(error_code(error), u32::from(ErrorCode::WrongCollection));
+}
+
+#[tokio::test]
+async fn create_nft_rejects_unregistered_and_suspended_creators() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+
+    // Zero supply is a collection, not a skin
+    let mint = Keypair::new();
+    let creator = test.creator.insecure_clone();
+    let instruction = test.create_nft_instruction(&creator.pubkey(), &mint.pubkey(), &collection, 0, 0);
+    let error = test.send(instruction, &[&creator, &mint]).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::InvalidSupply));
+
+    test.set_creator_status(&creator.pubkey(), CreatorStatus::Suspended).await;
+    let instruction = test.create_nft_instruction(&creator.pubkey(), &mint.pubkey(), &collection, 5, 0);
+    let error = test.send(instruction, &[&creator, &mint]).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::UnauthorizedCreator));
+
+    // Not in the registry at all
+    let buyer = test.buyer.insecure_clone();
+    let instruction = test.create_nft_instruction(&buyer.pubkey(), &mint.pubkey(), &collection, 5, 0);
+    let error = test.send(instruction, &[&buyer, &mint]).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::UnauthorizedCreator));
+}
+
+#[tokio::test]
+async fn only_the_admin_adds_creators() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let (creator, buyer) = (test.creator.insecure_clone(), test.buyer.insecure_clone());
+
+    // A registered creator can't bring in another one
+    let error = test.add_creator_as(&creator, &buyer.pubkey()).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(anchor_lang::error::ErrorCode::ConstraintHasOne));
+    assert!(!test.exists(creator_record(&buyer.pubkey())).await);
+
+    test.add_creator(&buyer.pubkey()).await;
+    let mint = Keypair::new();
+    let instruction = test.create_nft_instruction(&buyer.pubkey(), &mint.pubkey(), &collection, 5, 0);
+    test.send(instruction, &[&buyer, &mint]).await.unwrap();
+}
+
+#[tokio::test]
+async fn create_nft_checks_the_royalties() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let creator = test.creator.insecure_clone();
+    let (me, other) = (creator.pubkey(), Pubkey::new_unique());
+    let share = |address: Pubkey, share: u8| CreatorShare { address, share };
+    let five = [me].into_iter().chain((0..4).map(|_| Pubkey::new_unique())).map(|address| share(address, 20)).collect();
+
+    let rejected = [
+        ((2501, vec![share(me, 100)]), ErrorCode::InvalidRoyalty),
+        ((500, vec![share(me, 70), share(other, 20)]), ErrorCode::InvalidCreatorSplit),
+        // The signer is the one verified, it has to be in the split
+        ((500, vec![share(other, 100)]), ErrorCode::InvalidCreatorSplit),
+        ((500, vec![share(me, 50), share(me, 50)]), ErrorCode::InvalidCreatorSplit),
+        ((500, five), ErrorCode::InvalidCreatorSplit),
+    ];
+    for (royalties, code) in rejected {
+        let mint = Keypair::new();
+        let instruction = test.create_nft_instruction_with(&me, &mint.pubkey(), &collection, 5, 0, royalties);
+        let error = test.send(instruction, &[&creator, &mint]).await.unwrap_err();
+        assert_eq!(error_code(error), u32::from(code));
+    }
+
+    // The authority first with no share, then the split with only the signer verified
+    let mint = Keypair::new();
+    let royalties = (2500, vec![share(me, 70), share(other, 30)]);
+    let instruction = test.create_nft_instruction_with(&me, &mint.pubkey(), &collection, 5, 0, royalties);
+    test.send(instruction, &[&creator, &mint]).await.unwrap();
+
+    let account = test.context.banks_client.get_account(Metadata::find_pda(&mint.pubkey()).0).await.unwrap().unwrap();
+    let metadata = Metadata::from_bytes(&account.data).unwrap();
+    assert_eq!(metadata.seller_fee_basis_points, 2500);
+    let creators: Vec<(Pubkey, u8, bool)> =
+        metadata.creators.unwrap().iter().map(|creator| (creator.address, creator.share, creator.verified)).collect();
+    assert_eq!(creators, [(authority(), 0, true), (me, 70, true), (other, 30, false)]);
+}
+
+#[tokio::test]
+async fn update_skin_rejects_suspended_creators() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 5).await;
+    let uri = test.metadata_uri(&skin).await;
+
+    let creator = test.creator.pubkey();
+    test.set_creator_status(&creator, CreatorStatus::Suspended).await;
+    let error = test.update_skin(&skin, "https://files.test/cat_ak_fixed.json").await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::UnauthorizedCreator));
+    assert_eq!(test.metadata_uri(&skin).await, uri);
+
+    // Back once the admin resumes them
+    test.set_creator_status(&creator, CreatorStatus::Active).await;
+    test.update_skin(&skin, "https://files.test/cat_ak_fixed.json").await.unwrap();
+    assert_eq!(test.metadata_uri(&skin).await, "https://files.test/cat_ak_fixed.json");
+
+    // Removed from the registry, the record is closed
+    test.remove_creator(&creator).await;
+    let error = test.update_skin(&skin, "https://files.test/cat_ak_again.json").await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::UnauthorizedCreator));
+}
+
+#[tokio::test]
+async fn fuse_burns_the_recipe_and_prints_the_upgrade() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let common = test.create_nft(&collection, 5).await;
+    let upgrade = test.create_nft(&collection, 5).await;
+    test.set_recipe(&upgrade, vec![Ingredient { master_mint: common, count: 2 }]).await;
+
+    let first = test.buy(&common).await.unwrap();
+    let second = test.buy(&common).await.unwrap();
+    test.burn_ingredient(&upgrade, &common, &first, 1).await.unwrap();
+
+    // One of two burned
+    let error = test.fuse(&upgrade).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::RecipeMismatch));
+
+    test.burn_ingredient(&upgrade, &common, &second, 2).await.unwrap();
+    test.fuse(&upgrade).await.unwrap();
+
+    assert_eq!(test.supply(&upgrade).await, 1);
+    let player = test.buyer.pubkey();
+    assert!(!test.exists(get_associated_token_address(&player, &first)).await);
+    assert!(!test.exists(get_associated_token_address(&player, &second)).await);
+    // Nothing left toward the next one
+    assert!(!test.exists(fuse_progress(&upgrade, &player)).await);
+}
+
+#[tokio::test]
+async fn burn_ingredient_rejects_what_the_recipe_does_not_take() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let common = test.create_nft(&collection, 5).await;
+    let other = test.create_nft(&collection, 5).await;
+    let upgrade = test.create_nft(&collection, 5).await;
+    test.set_recipe(&upgrade, vec![Ingredient { master_mint: common, count: 1 }]).await;
+
+    let print = test.buy(&other).await.unwrap();
+    let error = test.burn_ingredient(&upgrade, &other, &print, 1).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::RecipeMismatch));
+
+    // More than the recipe takes
+    let player = test.buyer.pubkey();
+    let first = test.buy(&common).await.unwrap();
+    let second = test.buy(&common).await.unwrap();
+    test.burn_ingredient(&upgrade, &common, &first, 1).await.unwrap();
+    let error = test.burn_ingredient(&upgrade, &common, &second, 2).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::RecipeMismatch));
+    assert!(test.exists(get_associated_token_address(&player, &second)).await);
+}
+
+#[tokio::test]
+async fn recipe_change_keeps_the_burns_toward_the_old_recipe() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let common = test.create_nft(&collection, 5).await;
+    let rare = test.create_nft(&collection, 5).await;
+    let upgrade = test.create_nft(&collection, 5).await;
+    test.set_recipe(&upgrade, vec![Ingredient { master_mint: common, count: 2 }]).await;
+
+    let first = test.buy(&common).await.unwrap();
+    let second = test.buy(&common).await.unwrap();
+    let rare_print = test.buy(&rare).await.unwrap();
+    test.burn_ingredient(&upgrade, &common, &first, 1).await.unwrap();
+
+    // The creator changes the recipe, the burn made toward the old one still counts
+    test.set_recipe(&upgrade, vec![Ingredient { master_mint: rare, count: 1 }]).await;
+    let error = test.burn_ingredient(&upgrade, &rare, &rare_print, 1).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::RecipeMismatch));
+    test.burn_ingredient(&upgrade, &common, &second, 2).await.unwrap();
+    test.fuse(&upgrade).await.unwrap();
+    assert_eq!(test.supply(&upgrade).await, 1);
+
+    // Nothing is left over, the next burn starts on the new recipe
+    let player = test.buyer.pubkey();
+    assert!(!test.exists(fuse_progress(&upgrade, &player)).await);
+    let third = test.buy(&common).await.unwrap();
+    let error = test.burn_ingredient(&upgrade, &common, &third, 3).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::RecipeMismatch));
+    test.burn_ingredient(&upgrade, &rare, &rare_print, 1).await.unwrap();
+    test.fuse(&upgrade).await.unwrap();
+    assert_eq!(test.supply(&upgrade).await, 2);
+}
+
+#[tokio::test]
+async fn buy_after_a_burn_prints_the_next_unused_edition() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let common = test.create_nft(&collection, 3).await;
+    let upgrade = test.create_nft(&collection, 5).await;
+    test.set_recipe(&upgrade, vec![Ingredient { master_mint: common, count: 1 }]).await;
+
+    let first = test.buy(&common).await.unwrap();
+    test.buy(&common).await.unwrap();
+    test.burn_ingredient(&upgrade, &common, &first, 1).await.unwrap();
+    test.fuse(&upgrade).await.unwrap();
+
+    // The supply + 1 is edition 2 again, already printed, the buy prints edition 3
+    assert_eq!(test.supply(&common).await, 1);
+    test.buy(&common).await.unwrap();
+    assert_eq!(test.supply(&common).await, 2);
+    assert_eq!(test.editions_printed(&common).await, 3);
+    assert_eq!(test.editions_printed(&upgrade).await, 1);
+
+    // 3 printed of 3, the burn doesn't free one
+    let error = test.buy(&common).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::SoldOut));
+}
+
+#[tokio::test]
+async fn purchase_listing_pays_the_seller_and_the_royalties() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 5).await;
+    let print = test.buy(&skin).await.unwrap();
+    let (seller, trader, creator) = (test.buyer.insecure_clone(), test.trader.pubkey(), test.creator.pubkey());
+
+    test.list(&seller, &print, 1_000_000_000).await.unwrap();
+    assert_eq!(test.token_amount(get_associated_token_address(&seller.pubkey(), &print)).await, Some(0));
+    assert_eq!(test.token_amount(get_associated_token_address(&trade_listing(&print), &print)).await, Some(1));
+
+    let (seller_before, creator_before) = (test.balance(seller.pubkey()).await, test.balance(creator).await);
+    test.purchase_listing(&print, 1_000_000_000, &[creator]).await.unwrap();
+
+    // 5% to the only creator, the rest and the rents of the listing and escrow to the seller
+    assert_eq!(test.balance(creator).await - creator_before, 50_000_000);
+    assert!(test.balance(seller.pubkey()).await - seller_before > 950_000_000);
+    assert_eq!(test.token_amount(get_associated_token_address(&trader, &print)).await, Some(1));
+    assert!(!test.exists(trade_listing(&print)).await);
+    assert!(!test.exists(get_associated_token_address(&trade_listing(&print), &print)).await);
+}
+
+#[tokio::test]
+async fn purchase_listing_rejects_another_price_or_creator() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 5).await;
+    let print = test.buy(&skin).await.unwrap();
+    let (seller, creator) = (test.buyer.insecure_clone(), test.creator.pubkey());
+    test.list(&seller, &print, 1_000_000_000).await.unwrap();
+
+    let error = test.purchase_listing(&print, 1, &[creator]).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::PriceMismatch));
+
+    // Royalties to the seller instead of the creator
+    let error = test.purchase_listing(&print, 1_000_000_000, &[seller.pubkey()]).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::WrongCreatorAccounts));
+    let error = test.purchase_listing(&print, 1_000_000_000, &[]).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::WrongCreatorAccounts));
+
+    assert_eq!(test.token_amount(get_associated_token_address(&trade_listing(&print), &print)).await, Some(1));
+}
+
+#[tokio::test]
+async fn cancel_gives_the_print_back() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+    let skin = test.create_nft(&collection, 5).await;
+    let print = test.buy(&skin).await.unwrap();
+    let seller = test.buyer.insecure_clone();
+    test.list(&seller, &print, 1_000_000_000).await.unwrap();
+
+    // Only the seller
+    let trader = test.trader.insecure_clone();
+    let error = test.cancel(&trader, &print).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(anchor_lang::error::ErrorCode::ConstraintHasOne));
+
+    test.cancel(&seller, &print).await.unwrap();
+    assert_eq!(test.token_amount(get_associated_token_address(&seller.pubkey(), &print)).await, Some(1));
+    assert!(!test.exists(trade_listing(&print)).await);
+
+    // Listed again after
+    test.list(&seller, &print, 5).await.unwrap();
+}
+
+#[tokio::test]
+async fn list_rejects_what_is_not_a_skin() {
+    let mut test = Test::start().await;
+    let collection = test.create_collection().await;
+
+    // The creator holds the collection nft
+    let creator = test.creator.insecure_clone();
+    let error = test.list(&creator, &collection, 1).await.unwrap_err();
+    assert_eq!(error_code(error), u32::from(ErrorCode::NotASkin));
+}
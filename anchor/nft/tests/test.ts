@@ -5,7 +5,6 @@ import NodeWallet from "@coral-xyz/anchor/dist/cjs/nodewallet";
 import { getAssociatedTokenAddressSync } from "@solana/spl-token";
 import { Connection, Keypair, PublicKey } from "@solana/web3.js";
 import {
-	fetchMasterEditionFromSeeds,
 	findMasterEditionPda,
 	safeFetchMasterEditionFromSeeds,
 } from "@metaplex-foundation/mpl-token-metadata";
@@ -56,6 +55,28 @@ describe("mint-nft", () => {
 		console.log("\nCollection NFT minted: TxID - ", tx);
 	})
 
+	it("Register the wallet as a creator", async () => {
+		// The local validator deploys with the wallet as upgrade authority
+		const [programData] = PublicKey.findProgramAddressSync(
+			[program.programId.toBuffer()],
+			new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+		);
+
+		await program.methods.initializeConfig(wallet.publicKey)
+			.accounts({
+				authority: wallet.publicKey,
+				programData,
+			})
+			.rpc();
+
+		const tx = await program.methods.addCreator(wallet.publicKey)
+			.accounts({
+				admin: wallet.publicKey,
+			})
+			.rpc();
+		console.log("\nCreator added: TxID - ", tx);
+	});
+
 	it("Create NFT", async () => {
 		console.log("\nMint", mint.toBase58());
 
@@ -63,7 +84,13 @@ describe("mint-nft", () => {
 			"My NFT",
 			"MNFT",
 			"https://example.com/metadata.json",
-			new anchor.BN(1)
+			new anchor.BN(1),
+			// 5% royalties, all to the wallet
+			500,
+			[{ address: wallet.publicKey, share: 100 }],
+			// Free, the buys below pay nothing
+			new anchor.BN(0),
+			null,
 		)
 			.accounts({
 				creator: wallet.publicKey,
@@ -88,14 +115,12 @@ describe("mint-nft", () => {
 			console.log("\nEdition Mint Key: ", editionMint.toBase58());
 			console.log("\nEdition Mint Key: ", mint.toBase58());
 
-			// Fetch the Master Edition account
-			const masterEdition = await fetchMasterEditionFromSeeds(
-				umi,
-				{ mint: fromWeb3JsPublicKey(mint) },
-			);
+			// The listing counts the editions printed, burns lower the supply but not it
+			const [listing] = PublicKey.findProgramAddressSync([Buffer.from("listing"), mint.toBuffer()], program.programId);
+			const { editionsPrinted } = await program.account.skinListing.fetch(listing);
 
 			//fetchEditionMarkerFromSeeds
-			const editionMarkerIx = Math.floor(Number(masterEdition.supply) / 248);
+			const editionMarkerIx = Math.floor((Number(editionsPrinted) + 1) / 248);
 			const [editionMarker] = PublicKey.findProgramAddressSync(
 				[
 					Buffer.from("metadata"),
@@ -108,12 +133,17 @@ describe("mint-nft", () => {
 			);
 			console.log("\nEdition Mint Key: ", editionMarker.toBase58());
 
-			const tx = await program.methods.buyNft()
+			const tx = await program.methods.buyNft([], new anchor.BN(0), null)
 				.accounts({
 					buyer: wallet.publicKey,
 					masterMint: mint,
 					newMint: editionMint,
 					editionMarker,
+					// Creator of the skin, the listing pays it
+					treasury: wallet.publicKey,
+					paymentMint: null,
+					buyerPaymentAccount: null,
+					treasuryPaymentAccount: null,
 				})
 				.signers([editionMintKeypair, wallet.payer]).transaction();
 			tx.feePayer = wallet.publicKey;
@@ -121,12 +151,16 @@ describe("mint-nft", () => {
 			console.log('Simulation logs:', simulationResult.value.logs);
 
 			try {
-				const tx = await program.methods.buyNft()
+				const tx = await program.methods.buyNft([], new anchor.BN(0), null)
 					.accounts({
 						buyer: wallet.publicKey,
 						masterMint: mint,
 						newMint: editionMint,
 						editionMarker,
+						treasury: wallet.publicKey,
+						paymentMint: null,
+						buyerPaymentAccount: null,
+						treasuryPaymentAccount: null,
 					})
 					.signers([editionMintKeypair, wallet.payer])
 					.rpc({ skipPreflight: true });
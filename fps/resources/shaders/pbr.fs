@@ -44,6 +44,15 @@ uniform float metallicValue;
 uniform float roughnessValue = 0.5;
 uniform float aoValue = 1.0;
 uniform float emissivePower;
+uniform int emissiveRgb = 0; // skins: the emissive map is the glow color
+
+// Per texture repeat, set per skin on the gun shader
+uniform vec2 uvScaleA = vec2(1.0);
+uniform vec2 uvScaleR = vec2(1.0);
+uniform vec2 uvScaleN = vec2(1.0);
+uniform vec2 uvScaleM = vec2(1.0);
+uniform vec2 uvScaleAo = vec2(1.0);
+uniform vec2 uvScaleE = vec2(1.0);
 
 // Input lighting values
 uniform vec3 viewPos;
@@ -104,7 +113,7 @@ vec3 ComputePBR() {
   // lights[3].color = vec4(0.0, 0.0, 1.0, 1.0);
   // lights[3].intensity = 12.0;
 
-  vec3 albedo = texture(albedoMap, fragTexCoord).rgb;
+  vec3 albedo = texture(albedoMap, fragTexCoord * uvScaleA).rgb;
   albedo = vec3(albedoColor.x * albedo.x, albedoColor.y * albedo.y,
                 albedoColor.z * albedo.z);
 
@@ -113,9 +122,9 @@ vec3 ComputePBR() {
   float ao = clamp(aoValue, 0.0, 1.0);
 
   if (useTexMRA == 1) {
-    vec4 m = texture(mMap, fragTexCoord);
-    vec4 r = texture(rMap, fragTexCoord);
-    vec4 ao_m = texture(aoMap, fragTexCoord);
+    vec4 m = texture(mMap, fragTexCoord * uvScaleM);
+    vec4 r = texture(rMap, fragTexCoord * uvScaleR);
+    vec4 ao_m = texture(aoMap, fragTexCoord * uvScaleAo);
     metallic = clamp(m.r + metallicValue, 0.04, 1.0);
     roughness = clamp(r.r + roughnessValue, 0.04, 1.0);
     ao = (ao_m.r + aoValue) * 0.5;
@@ -123,7 +132,7 @@ vec3 ComputePBR() {
 
   vec3 N = normalize(fragNormal);
   if (useTexNormal == 1) {
-    N = texture(normalMap, fragTexCoord).rgb;
+    N = texture(normalMap, fragTexCoord * uvScaleN).rgb;
     N = normalize(N * 2.0 - 1.0);
     N = normalize(N * TBN);
   }
@@ -131,8 +140,11 @@ vec3 ComputePBR() {
   vec3 V = normalize(viewPos - fragPosition);
 
   vec3 emissive = vec3(0);
-  emissive = (texture(emissiveMap, fragTexCoord).rgb).g * emissiveColor.rgb *
-             emissivePower * useTexEmissive;
+  vec3 emissiveTex = texture(emissiveMap, fragTexCoord * uvScaleE).rgb;
+  if (emissiveRgb == 0) {
+    emissiveTex = vec3(emissiveTex.g);
+  }
+  emissive = emissiveTex * emissiveColor.rgb * emissivePower * useTexEmissive;
 
   // return N;//vec3(metallic,metallic,metallic);
   // If  dia-electric use base reflectivity of 0.04 otherwise ut is a metal use
@@ -0,0 +1,246 @@
+use std::io::Read;
+use std::str::FromStr;
+
+use anchor_client::solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use anchor_client::solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig};
+use anchor_client::solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use anchor_client::solana_client::rpc_request::TokenAccountsFilter;
+use anchor_client::solana_sdk::hash::Hash;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::Signature;
+use anchor_client::solana_sdk::transaction::Transaction;
+use reqwest::blocking::Client;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use solana_account_decoder::{UiAccountData, UiAccountEncoding};
+use solana_transaction_status::UiTransactionEncoding;
+
+/**
+ * Everything the SolanaClient asks the chain, so it can run against a stand-in
+ */
+pub trait ChainBackend: Send + Sync {
+    //Mints of every token account the owner holds
+    fn token_mints_by_owner(&self, owner: &Pubkey) -> Result<Vec<Pubkey>, String>;
+    fn mint_decimals(&self, mint: &Pubkey) -> Result<u8, String>;
+    fn account_data(&self, address: &Pubkey) -> Result<Vec<u8>, String>;
+    //None for the accounts that don't exist
+    fn multiple_account_data(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>, String>;
+    //Accounts of a program whose data has bytes at offset
+    fn program_accounts(&self, program: &Pubkey, offset: usize, bytes: &[u8]) -> Result<Vec<(Pubkey, Vec<u8>)>, String>;
+    fn latest_blockhash(&self) -> Result<Hash, String>;
+    //Returns once confirmed
+    fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, String>;
+    //Transactions that touched address, newest first, older than before and newer than until
+    fn signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> Result<Vec<Signature>, String>;
+    //None for the transactions that failed, their logs don't count
+    fn transaction_logs(&self, signature: &Signature) -> Result<Option<Vec<String>>, String>;
+}
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: Vec<u8>,
+}
+
+//Conditional GET, what the SkinCache needs from http
+pub trait HttpBackend: Send + Sync {
+    //Fails on a body over max_bytes instead of reading the rest of it
+    fn get(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+        max_bytes: usize,
+    ) -> Result<HttpResponse, String>;
+}
+
+pub struct RpcBackend {
+    rpc: RpcClient,
+    token_program_id: Pubkey,
+}
+
+impl RpcBackend {
+    pub fn new(rpc_url: &str) -> Self {
+        Self {
+            rpc: RpcClient::new(rpc_url.to_string()),
+            token_program_id: Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap(),
+        }
+    }
+}
+
+impl ChainBackend for RpcBackend {
+    fn token_mints_by_owner(&self, owner: &Pubkey) -> Result<Vec<Pubkey>, String> {
+        let token_accounts = self.rpc
+            .get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(self.token_program_id))
+            .map_err(|e| e.to_string())?;
+
+        //Get mints from ATAs, the empty ones left by a sale or a listing don't count
+        let mints = token_accounts
+            .iter()
+            .filter_map(|account| match &account.account.data {
+                UiAccountData::Json(parsed_account) => Some(&parsed_account.parsed["info"]),
+                _ => None,
+            })
+            .filter(|info| info["tokenAmount"]["amount"].as_str() != Some("0"))
+            .filter_map(|info| info["mint"].as_str())
+            .filter_map(|mint| Pubkey::from_str(mint).ok())
+            .collect();
+
+        Ok(mints)
+    }
+
+    fn mint_decimals(&self, mint: &Pubkey) -> Result<u8, String> {
+        self.rpc
+            .get_token_supply(mint)
+            .map(|supply| supply.decimals)
+            .map_err(|e| e.to_string())
+    }
+
+    fn account_data(&self, address: &Pubkey) -> Result<Vec<u8>, String> {
+        self.rpc.get_account_data(address).map_err(|e| e.to_string())
+    }
+
+    fn multiple_account_data(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>, String> {
+        let mut data = Vec::with_capacity(addresses.len());
+
+        //The rpc takes at most 100 per call
+        for chunk in addresses.chunks(100) {
+            let accounts = self.rpc.get_multiple_accounts(chunk).map_err(|e| e.to_string())?;
+            data.extend(accounts.into_iter().map(|account| account.map(|account| account.data)));
+        }
+
+        Ok(data)
+    }
+
+    fn program_accounts(&self, program: &Pubkey, offset: usize, bytes: &[u8]) -> Result<Vec<(Pubkey, Vec<u8>)>, String> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, bytes.to_vec()))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let accounts = self.rpc
+            .get_program_accounts_with_config(program, config)
+            .map_err(|e| e.to_string())?;
+
+        Ok(accounts.into_iter().map(|(address, account)| (address, account.data)).collect())
+    }
+
+    fn latest_blockhash(&self) -> Result<Hash, String> {
+        self.rpc.get_latest_blockhash().map_err(|e| e.to_string())
+    }
+
+    fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, String> {
+        self.rpc.send_and_confirm_transaction(transaction).map_err(|e| e.to_string())
+    }
+
+    fn signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> Result<Vec<Signature>, String> {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            ..Default::default()
+        };
+
+        self.rpc
+            .get_signatures_for_address_with_config(address, config)
+            .map_err(|e| e.to_string())?
+            .iter()
+            .map(|status| Signature::from_str(&status.signature).map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    fn transaction_logs(&self, signature: &Signature) -> Result<Option<Vec<String>>, String> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            max_supported_transaction_version: Some(0),
+            ..Default::default()
+        };
+
+        let transaction = self.rpc
+            .get_transaction_with_config(signature, config)
+            .map_err(|e| e.to_string())?;
+        let Some(meta) = transaction.transaction.meta else {
+            return Ok(None);
+        };
+        if meta.err.is_some() {
+            return Ok(None);
+        }
+
+        Ok(Option::from(meta.log_messages))
+    }
+}
+
+pub struct ReqwestBackend {
+    http: Client,
+}
+
+impl ReqwestBackend {
+    pub fn new() -> Self {
+        Self { http: Client::new() }
+    }
+}
+
+impl Default for ReqwestBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpBackend for ReqwestBackend {
+    fn get(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+        max_bytes: usize,
+    ) -> Result<HttpResponse, String> {
+        let mut request = self.http.get(url);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().map_err(|e| e.to_string())?;
+        if response.content_length().is_some_and(|length| length > max_bytes as u64) {
+            return Err(format!("{} is larger than {} bytes", url, max_bytes));
+        }
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        let status = response.status().as_u16();
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+
+        //The length can be missing or wrong, read one byte past the limit to tell
+        let mut body = Vec::new();
+        response
+            .take(max_bytes as u64 + 1)
+            .read_to_end(&mut body)
+            .map_err(|e| e.to_string())?;
+        if body.len() > max_bytes {
+            return Err(format!("{} is larger than {} bytes", url, max_bytes));
+        }
+
+        Ok(HttpResponse { status, etag, last_modified, body })
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use raylib::color::Color;
+use raylib::consts::MaterialMapIndex;
+use raylib::math::Vector2;
+use raylib::models::RaylibMaterial;
+use raylib::shaders::{RaylibShader, Shader};
+use raylib::texture::{Image, WeakTexture2D};
+use raylib::{RaylibHandle, RaylibThread};
+use skin_common::skin_schema::SkinStyle;
+
+use crate::skin_loader::LoadedSkin;
+
+//The slots the shader samples, a packed orm is already split by then
+pub const TEXTURE_SLOTS: [&str; 6] = ["a", "r", "n", "m", "ao", "e"];
+
+/**
+ * The bundled ak skin, so anyone can join without a compatible nft
+ * It also stands in for skins that are still loading and for the slots a skin doesn't have
+ * Slots missing from resources/textures/ak are flat colors
+ */
+pub struct DefaultSkin {
+    textures: HashMap<String, WeakTexture2D>,
+}
+
+impl DefaultSkin {
+    pub fn load(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
+        let mut textures: HashMap<String, WeakTexture2D> = HashMap::new();
+
+        for key in TEXTURE_SLOTS {
+            let path = format!("resources/textures/ak/ak_{}.png", key);
+
+            let texture = if Path::new(&path).exists() {
+                rl.load_texture(thread, &path)
+                    .unwrap_or_else(|_| panic!("Failed to load {}", path))
+            } else {
+                let img = Image::gen_image_color(1, 1, DefaultSkin::flat_color(key));
+                rl.load_texture_from_image(thread, &img)
+                    .expect("Failed to load image to texture")
+            };
+
+            textures.insert(key.to_string(), unsafe { texture.make_weak() });
+        }
+
+        Self { textures }
+    }
+
+    //Neutral values, the gun just looks plain
+    fn flat_color(key: &str) -> Color {
+        match key {
+            "a" => Color::GRAY,
+            "n" => Color::new(128, 128, 255, 255),
+            "m" => Color::BLACK,
+            "ao" => Color::WHITE,
+            //No glow
+            "e" => Color::BLACK,
+            _ => Color::new(128, 128, 128, 255),
+        }
+    }
+
+    //The slot of a skin, or the default one when the skin doesn't have it
+    pub fn slot<'a>(&'a self, textures: Option<&'a HashMap<String, WeakTexture2D>>, key: &str) -> &'a WeakTexture2D {
+        textures
+            .and_then(|textures| textures.get(key))
+            .unwrap_or(&self.textures[key])
+    }
+
+    //Puts a skin on the gun material, the default one for None
+    pub fn apply(&self, material: &mut impl RaylibMaterial, skin: Option<&LoadedSkin>) {
+        let textures = skin.map(|skin| &skin.textures);
+        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_ALBEDO, self.slot(textures, "a"));
+        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_METALNESS, self.slot(textures, "m"));
+        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_NORMAL, self.slot(textures, "n"));
+        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_ROUGHNESS, self.slot(textures, "r"));
+        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_OCCLUSION, self.slot(textures, "ao"));
+        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_EMISSION, self.slot(textures, "e"));
+
+        //Goes to albedoColor
+        let [r, g, b, a] = skin.map(|skin| skin.style.tint).unwrap_or([255; 4]);
+        material.maps_mut()[MaterialMapIndex::MATERIAL_MAP_ALBEDO as usize].color = Color::new(r, g, b, a).into();
+    }
+
+    //Textures must be cleared manually
+    pub fn unload(&self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.textures.values().for_each(|texture| {
+            unsafe { rl.unload_texture(thread, texture.to_owned()) };
+        });
+    }
+}
+
+/**
+ * The per skin uniforms of the gun shader
+ * Materials can't carry them, so they are set right before each gun is drawn
+ */
+pub struct SkinUniforms {
+    uv_scale: Vec<(&'static str, i32)>,
+}
+
+impl SkinUniforms {
+    pub fn new(shader: &Shader) -> Self {
+        let uv_scale = [("a", "A"), ("r", "R"), ("n", "N"), ("m", "M"), ("ao", "Ao"), ("e", "E")]
+            .into_iter()
+            .map(|(slot, name)| (slot, shader.get_shader_location(&format!("uvScale{}", name))))
+            .collect();
+
+        Self { uv_scale }
+    }
+
+    pub fn bind(&self, shader: &mut Shader, style: Option<&SkinStyle>) {
+        let default = SkinStyle::default();
+        let style = style.unwrap_or(&default);
+
+        for (slot, loc) in &self.uv_scale {
+            let [u, v] = style.uv_scale(slot);
+            shader.set_shader_value(*loc, Vector2::new(u, v));
+        }
+    }
+}
@@ -11,4 +11,10 @@ pub struct PlayerInfo {
     pub yaw: f32,
     pub pitch: f32,
     pub skin: String,
-}
\ No newline at end of file
+}
+//Sent once on the login channel, the wallet's signature of Wallet::login_message with the connection's nonce
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Login {
+    pub wallet: String,
+    pub signature: Vec<u8>,
+}
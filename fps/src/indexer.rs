@@ -0,0 +1,160 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::Signature;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use skin_common::nft_program::{CollectionCreated, ProgramEvent, SkinCreated, SkinFused, SkinPurchased, SkinTraded, PROGRAM_ID};
+
+use crate::backend::ChainBackend;
+use crate::skin_cache::SkinCache;
+
+//Purchases, fusions and trades kept on disk, the shop only shows the last few
+pub const MAX_PURCHASES: usize = 200;
+//How far back the first sync goes, the program history can be long
+const MAX_BACKFILL: usize = 1000;
+
+/**
+ * The program's events replayed from its transaction logs, kept in a json next to the skin cache
+ * Each sync only reads the transactions since the newest one it has seen
+ */
+#[derive(Serialize, Deserialize, Default)]
+pub struct EventStore {
+    #[serde(skip)]
+    path: PathBuf,
+    //Newest transaction replayed, the next sync stops there
+    last_signature: Option<String>,
+    pub collections: Vec<CollectionCreated>,
+    pub skins: Vec<SkinCreated>,
+    //Oldest first
+    pub purchases: Vec<SkinPurchased>,
+    //Oldest first, missing from the stores written before fuse
+    #[serde(default)]
+    pub fusions: Vec<SkinFused>,
+    //Oldest first, sales between players on the market
+    #[serde(default)]
+    pub trades: Vec<SkinTraded>,
+}
+
+impl EventStore {
+    //cache dir/fps/events.json
+    pub fn default_path() -> PathBuf {
+        SkinCache::default_dir().join("events.json")
+    }
+
+    //Starts over when the file is missing or broken
+    pub fn load(path: &Path) -> Self {
+        let store = fs::read(path)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .unwrap_or_default();
+
+        Self { path: path.to_path_buf(), ..store }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+        fs::write(&self.path, json).map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+
+    //Reads the new transactions of the program oldest first, returns their events
+    pub fn sync(&mut self, chain: &dyn ChainBackend) -> Result<Vec<ProgramEvent>, String> {
+        let until = self.last_signature.as_deref().and_then(|s| Signature::from_str(s).ok());
+
+        //Newest first, a page at a time
+        let mut signatures: Vec<Signature> = Vec::new();
+        loop {
+            let page = chain.signatures_for_address(&PROGRAM_ID, signatures.last().copied(), until)?;
+            if page.is_empty() {
+                break;
+            }
+            signatures.extend(page);
+            if until.is_none() && signatures.len() >= MAX_BACKFILL {
+                signatures.truncate(MAX_BACKFILL);
+                break;
+            }
+        }
+
+        let mut events = Vec::new();
+        for signature in signatures.iter().rev() {
+            if let Some(logs) = chain.transaction_logs(signature)? {
+                events.extend(parse_logs(&logs));
+            }
+        }
+
+        for event in &events {
+            match event {
+                ProgramEvent::CollectionCreated(event) => self.collections.push(event.clone()),
+                ProgramEvent::SkinCreated(event) => self.skins.push(event.clone()),
+                ProgramEvent::SkinPurchased(event) => self.purchases.push(event.clone()),
+                ProgramEvent::SkinFused(event) => self.fusions.push(event.clone()),
+                ProgramEvent::SkinTraded(event) => self.trades.push(event.clone()),
+                //The open listings are accounts, the market reads them
+                ProgramEvent::SkinListed(_) | ProgramEvent::SkinDelisted(_) => {}
+            }
+        }
+        let extra = self.purchases.len().saturating_sub(MAX_PURCHASES);
+        self.purchases.drain(..extra);
+        let extra = self.fusions.len().saturating_sub(MAX_PURCHASES);
+        self.fusions.drain(..extra);
+        let extra = self.trades.len().saturating_sub(MAX_PURCHASES);
+        self.trades.drain(..extra);
+
+        if let Some(newest) = signatures.first() {
+            self.last_signature = Some(newest.to_string());
+            self.save()?;
+        }
+
+        Ok(events)
+    }
+
+    //Newest first
+    pub fn recent_purchases(&self, count: usize) -> impl Iterator<Item = &SkinPurchased> {
+        self.purchases.iter().rev().take(count)
+    }
+
+    pub fn skin_name(&self, master_mint: &Pubkey) -> Option<&str> {
+        self.skins
+            .iter()
+            .find(|skin| skin.master_mint == *master_mint)
+            .map(|skin| skin.name.as_str())
+    }
+}
+
+/**
+ * The events logged by our program itself, "Program data: <base64>" lines
+ * The programs it calls log in between, the invoke and success lines tell whose log it is
+ */
+pub fn parse_logs(logs: &[String]) -> Vec<ProgramEvent> {
+    let program = PROGRAM_ID.to_string();
+    //Program of each invoke depth, is it ours
+    let mut stack: Vec<bool> = Vec::new();
+    let mut events = Vec::new();
+
+    for line in logs {
+        if let Some(data) = line.strip_prefix("Program data: ") {
+            if stack.last() == Some(&true) {
+                events.extend(BASE64.decode(data.trim()).ok().and_then(|data| ProgramEvent::parse(&data)));
+            }
+            continue;
+        }
+
+        //"Program log: invoke ..." is a log, not an invoke
+        let words: Vec<&str> = line.split(' ').collect();
+        match words.as_slice() {
+            ["Program", id, "invoke", ..] if Pubkey::from_str(id).is_ok() => stack.push(*id == program),
+            ["Program", id, "success"] | ["Program", id, "failed:", ..] if Pubkey::from_str(id).is_ok() => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    events
+}
@@ -1,9 +1,11 @@
-use std::{collections::HashMap, f32::consts::PI};
+use std::f32::consts::PI;
 
 use raylib::{
-    ffi::MaterialMapIndex, math::{Matrix, Quaternion, Vector3, Vector4}, models::{Model, ModelAnimation, RaylibMaterial, RaylibModel, RaylibModelAnimation}, prelude::{RaylibDraw3D, RaylibDrawHandle, RaylibMode3D}, shaders::Shader, texture::WeakTexture2D, RaylibHandle, RaylibThread
+    math::{Matrix, Quaternion, Vector3, Vector4}, models::{Model, ModelAnimation, RaylibModel, RaylibModelAnimation}, prelude::{RaylibDraw3D, RaylibDrawHandle, RaylibMode3D}, shaders::Shader, RaylibHandle, RaylibThread
 };
 
+use crate::default_skin::DefaultSkin;
+use crate::skin_loader::LoadedSkin;
 use crate::utils::c_bytesto_string;
 
 
@@ -20,6 +22,7 @@ impl MPlayer {
         rl: &mut RaylibHandle,
         thread: &RaylibThread,
         shader: &Shader,
+        gun_shader: &Shader,
     ) -> Result<Self, String> {
         let mut m_player = rl.load_model(&thread, "resources/m_player.gltf").unwrap();
         let mut m_player_animations = rl
@@ -65,10 +68,10 @@ impl MPlayer {
         let mut ak_only = rl.load_model(&thread, "resources/ak_only.glb").unwrap();
 
 
-        // Apply shader to model
+        // Apply shader to model, the gun gets its own for the skin uniforms
         for i in 0..ak_only.materials().len() {
             let material = &mut ak_only.materials_mut()[i];
-            material.shader = (*shader).clone();
+            material.shader = (*gun_shader).clone();
         }
         Ok(MPlayer {
             model: m_player,
@@ -137,13 +140,8 @@ impl MPlayer {
         );
     }
 
-    pub fn apply_gun_textures(&mut self,  gun_textures: &HashMap<String, WeakTexture2D>)
+    pub fn apply_gun_textures(&mut self, skin: Option<&LoadedSkin>, default_skin: &DefaultSkin)
     {
-        let material = &mut self.ak_only.materials_mut()[1];
-        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_ALBEDO, &gun_textures["a"]);
-        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_METALNESS, &gun_textures["m"]);
-        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_NORMAL, &gun_textures["n"]);
-        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_ROUGHNESS, &gun_textures["r"]);
-        material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_OCCLUSION,&gun_textures["ao"]);
+        default_skin.apply(&mut self.ak_only.materials_mut()[1], skin);
     }
 }
@@ -6,16 +6,46 @@ use anchor_client::solana_client::rpc_request::TokenAccountsFilter;
 use anchor_client::solana_sdk::pubkey::Pubkey;
 use mpl_token_metadata::accounts::Metadata;
 
-use sol_client::TextureField;
 use solana_account_decoder::UiAccountData;
 
 use reqwest::blocking::get;
 use serde_json::Value;
 
 mod sol_client;
-use sol_client::SkinMetadata;
 use sol_client::SolanaClient;
 
+use skin_common::skin_schema::SkinMetadata;
+
+mod skin_cache;
+use skin_cache::SkinCache;
+
+mod uri_resolver;
+use uri_resolver::UriResolver;
+
+mod default_skin;
+use default_skin::DefaultSkin;
+use default_skin::SkinUniforms;
+
+mod texture_manager;
+use texture_manager::TextureManager;
+
+mod skin_loader;
+use skin_loader::LoadedSkin;
+use skin_loader::SkinLoader;
+
+mod backend;
+
+mod shop;
+use shop::Shop;
+
+mod wallet;
+use wallet::Wallet;
+
+mod indexer;
+
+#[cfg(test)]
+mod mock_chain;
+
 use raylib::math::*;
 use raylib::prelude::*;
 use std::collections::HashMap;
@@ -23,6 +53,7 @@ use std::env;
 use std::f32::consts::FRAC_PI_2;
 use std::f32::consts::PI;
 use std::f32::consts::TAU;
+use std::fs;
 use std::io;
 use std::io::stdin;
 use std::mem::swap;
@@ -36,7 +67,7 @@ mod net_client;
 use net_client::NetworkClient;
 
 mod dto;
-use dto::PlayerInfo;
+use dto::{Login, PlayerInfo};
 
 mod m_player;
 use m_player::MPlayer;
@@ -273,7 +304,9 @@ pub fn load_hands(
     rl: &mut RaylibHandle,
     thread: &RaylibThread,
     shader: &Shader,
-    gun_textures: &HashMap<String, WeakTexture2D>,
+    gun_shader: &Shader,
+    gun_skin: Option<&LoadedSkin>,
+    default_skin: &DefaultSkin,
 ) -> (Model, Vec<ModelAnimation>) {
     let mut hands = rl
         .load_model(&thread, "resources/fps_ak.glb")
@@ -282,31 +315,6 @@ pub fn load_hands(
         .load_model_animations(&thread, "resources/fps_ak.glb")
         .expect("Could not load animations for resources/fps_ak.glb");
 
-    // let ak74_color = unsafe {
-    //     rl.load_texture(&thread, "resources/textures/ak/ak_a.png")
-    //         .expect("Failed to load ak_a.png")
-    //         .make_weak()
-    // };
-    // let ak74_normal = unsafe {
-    //     rl.load_texture(&thread, "resources/textures/ak/ak_n.png")
-    //         .expect("Failed to load ak_n.png")
-    //         .make_weak()
-    // };
-    // let ak_ao = unsafe {
-    //     rl.load_texture(&thread, "resources/textures/ak/ak_ao.png")
-    //         .expect("Failed to load ak_ao.png")
-    //         .make_weak()
-    // };
-    // let ak_metallic = unsafe {
-    //     rl.load_texture(&thread, "resources/textures/ak/ak_m.png")
-    //         .expect("Failed to load ak_m.png")
-    //         .make_weak()
-    // };
-    // let ak_roughness = unsafe {
-    //     rl.load_texture(&thread, "resources/textures/ak/ak_r.png")
-    //         .expect("Failed to load ak_r.png")
-    //         .make_weak()
-    // };
     let arm_color = unsafe {
         rl.load_texture(&thread, "resources/textures/arm/armColor.png")
             .expect("Failed to load armColor.png")
@@ -323,16 +331,10 @@ pub fn load_hands(
             .make_weak()
     };
 
+    //The ak textures are in the default skin, slots the skin doesn't have come from it
     let material = &mut hands.materials_mut()[1];
-    material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_ALBEDO, &gun_textures["a"]);
-    material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_METALNESS, &gun_textures["m"]);
-    material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_NORMAL, &gun_textures["n"]);
-    material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_ROUGHNESS, &gun_textures["r"]);
-    material.set_material_texture(
-        MaterialMapIndex::MATERIAL_MAP_OCCLUSION,
-        &gun_textures["ao"],
-    );
-    material.shader = (*shader).clone();
+    default_skin.apply(material, gun_skin);
+    material.shader = (*gun_shader).clone();
 
     let material = &mut hands.materials_mut()[2];
     material.set_material_texture(MaterialMapIndex::MATERIAL_MAP_ALBEDO, arm_color);
@@ -394,6 +396,18 @@ pub fn load_lighting_shader(rl: &mut RaylibHandle, thread: &RaylibThread) -> Sha
     shader
 }
 
+//Same pbr shader, its own instance so the per skin uniforms don't reach the map and the arms
+pub fn load_gun_shader(rl: &mut RaylibHandle, thread: &RaylibThread) -> Shader {
+    let mut shader = load_lighting_shader(rl, thread);
+
+    //Skin emissive maps are the glow color
+    shader.set_shader_value(shader.get_shader_location("emissiveRgb"), 1);
+    shader.set_shader_value(shader.get_shader_location("emissivePower"), 1.0f32);
+    shader.set_shader_value(shader.get_shader_location("emissiveColor"), Vector4::new(1.0, 1.0, 1.0, 1.0));
+
+    shader
+}
+
 pub fn unload_textures_from_model(rl: &mut RaylibHandle, thread: &RaylibThread, model: &Model) {
     //Force unload textures
     model.materials().iter().for_each(|material| {
@@ -403,8 +417,15 @@ pub fn unload_textures_from_model(rl: &mut RaylibHandle, thread: &RaylibThread,
     });
 }
 
-pub fn handle_prompt(skins: &Vec<(Pubkey, SkinMetadata)>) -> &(Pubkey, SkinMetadata) {
+pub fn handle_prompt(skins: &Vec<(Pubkey, SkinMetadata)>) -> Option<&(Pubkey, SkinMetadata)> {
+    //Nothing to choose from
+    if skins.is_empty() {
+        println!("No skins found, playing with the default skin");
+        return None;
+    }
+
     println!("Select a skin:");
+    println!("0: default");
     // Display the skin options
     for (index, option) in skins.iter().enumerate() {
         println!("{}: {}/{}", index + 1, option.1.name, option.1.symbol);
@@ -416,9 +437,12 @@ pub fn handle_prompt(skins: &Vec<(Pubkey, SkinMetadata)>) -> &(Pubkey, SkinMetad
         stdin().read_line(&mut input).expect("Failed to read line");
 
         match input.trim().parse::<usize>() {
-            Ok(choice) if choice > 0 && choice <= skins.len() => {
+            Ok(0) => {
+                return None;
+            }
+            Ok(choice) if choice <= skins.len() => {
                 let selected_option = &skins[choice - 1];
-                return selected_option;
+                return Some(selected_option);
             }
             _ => {
                 println!("Invalid input");
@@ -427,25 +451,87 @@ pub fn handle_prompt(skins: &Vec<(Pubkey, SkinMetadata)>) -> &(Pubkey, SkinMetad
     }
 }
 
+//The value following a flag
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+}
+
 fn main() {
 
     let args: Vec<String> = env::args().collect();
 
-    if args.len() < 2 {
-        println!("Usage: {} <wallet_address>", args[0]);
-        return;
-    }
-    let mut sol_client = SolanaClient::new();
-    let pubkey = Pubkey::from_str(&args[1]).unwrap();
-    let skins: Vec<(Pubkey, SkinMetadata)> = sol_client.fetch_skins(pubkey).unwrap();
+    //Signs the purchases and the login, without it the client only reads
+    let wallet = match Wallet::from_args(&args) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            println!("Could not load the wallet: {}", e);
+            return;
+        }
+    };
 
-    if skins.len() == 0 {
-        println!("No skins found");
+    //The address can be left out when a wallet is loaded
+    let address = args.get(1).filter(|arg| !arg.starts_with("--"));
+    if address.is_none() && wallet.is_none() {
+        println!(
+            "Usage: {} <wallet_address> [--offline] [--texture-budget-mb <mb>] [--allowlist <file>] [--keypair <path> | --seed-phrase [--derivation-path <path>] | --wallet [path]]",
+            args[0]
+        );
         return;
     }
+    //Start only from the disk cache, no rpc and no http
+    let offline = args.iter().any(|arg| arg == "--offline");
+    let cache = SkinCache::new(
+        SkinCache::default_dir(),
+        skin_cache::DEFAULT_MAX_BYTES,
+        offline,
+        UriResolver::from_env(),
+    );
+
+    let sol_client = SolanaClient::new(cache);
+
+    //Vram the remote players' skins may take before the unused ones are evicted
+    let texture_budget = arg_value(&args, "--texture-budget-mb")
+        .and_then(|mb| mb.parse::<usize>().ok())
+        .map(|mb| mb * 1024 * 1024)
+        .unwrap_or(texture_manager::DEFAULT_BUDGET_BYTES);
+    let mut texture_manager = TextureManager::new(texture_budget);
+    let pubkey = match (&wallet, address) {
+        (Some(wallet), Some(address)) if wallet.pubkey().to_string() != *address => {
+            println!("{} is not the address of the wallet, using {}", address, wallet.pubkey());
+            wallet.pubkey()
+        }
+        (Some(wallet), _) => wallet.pubkey(),
+        (None, Some(address)) => match Pubkey::from_str(address) {
+            Ok(pubkey) => pubkey,
+            Err(e) => {
+                println!("{} is not a wallet address: {}", address, e);
+                return;
+            }
+        },
+        (None, None) => unreachable!(),
+    };
+    let skins: Vec<(Pubkey, SkinMetadata)> = sol_client.fetch_skins(pubkey).unwrap_or_else(|e| {
+        println!("Could not fetch the skins: {}", e);
+        Vec::new()
+    });
 
     let choosen_skin = handle_prompt(&skins);
 
+    //Without a wallet the shop is browse only
+    //Wallets of an early access drop, one per line, as handed out by its creator
+    let allowlist: Vec<Pubkey> = arg_value(&args, "--allowlist")
+        .map(|path| match fs::read_to_string(path) {
+            Ok(list) => list.lines().filter_map(|line| Pubkey::from_str(line.trim()).ok()).collect(),
+            Err(e) => {
+                println!("Could not read the allowlist {}: {}", path, e);
+                Vec::new()
+            }
+        })
+        .unwrap_or_default();
+    let mut shop = Shop::new(sol_client.clone(), pubkey, wallet.as_ref().map(Wallet::signer), allowlist, &skins);
+
     // Init raylib
     let (mut rl, thread) = raylib::init()
         .size(SCREEN_WIDTH, SCREEN_HEIGHT)
@@ -457,14 +543,34 @@ fn main() {
     rl.hide_cursor();
     rl.disable_cursor();
 
-    //-! Can load some default texs if there are no skins on chain
-    //Fetch gun textures
-    let gun_texures_bytes = SolanaClient::fetch_images_bytes(&choosen_skin.1.textures).unwrap();
-    let gun_textures = SolanaClient::fetch_textures(&mut rl, &thread, gun_texures_bytes)
-        .expect("Failed to fetch textures");
+    let default_skin = DefaultSkin::load(&mut rl, &thread);
+
+    //Fetch gun textures, None plays the default skin
+    let gun_skin = choosen_skin.and_then(|skin| {
+        match sol_client
+            .fetch_images_bytes(&skin.0.to_string(), &skin.1)
+            .and_then(|bytes| skin_loader::load_textures(&mut rl, &thread, bytes, skin.1.style()))
+        {
+            Ok(loaded) => Some(loaded),
+            Err(e) => {
+                println!("Could not load {}: {}, using the default skin", skin.1.name, e);
+                None
+            }
+        }
+    });
+
+    //Remote skins are fetched and decoded by the workers from here on
+    let mut skin_loader = SkinLoader::new(
+        sol_client,
+        skin_loader::DEFAULT_WORKERS,
+        skin_loader::DEFAULT_UPLOADS_PER_FRAME,
+    );
 
     let mut shader = load_lighting_shader(&mut rl, &thread);
     let view_pos_loc = shader.get_shader_location("viewPos");
+    let mut gun_shader = load_gun_shader(&mut rl, &thread);
+    let gun_view_pos_loc = gun_shader.get_shader_location("viewPos");
+    let skin_uniforms = SkinUniforms::new(&gun_shader);
 
     let map_model = rl.load_model(&thread, "resources/map.glb").unwrap();
     let mut map = Map {
@@ -486,14 +592,14 @@ fn main() {
     let map_bounding_box = map.model.get_model_bounding_box();
     let map_center = (map_bounding_box.min + map_bounding_box.max) * 0.5;
 
-    let mut m_player = MPlayer::load(&mut rl, &thread, &shader).unwrap();
+    let mut m_player = MPlayer::load(&mut rl, &thread, &shader, &gun_shader).unwrap();
 
     //This is the T pose bounding box as raylib does not count transforms from animation
     let mut player_box = m_player.model.get_model_bounding_box();
     //-! Little bug with this model, y is switched
     swap(&mut player_box.min.y, &mut player_box.max.y);
 
-    let (mut hands, hands_animations) = load_hands(&mut rl, &thread, &shader, &gun_textures);
+    let (mut hands, hands_animations) = load_hands(&mut rl, &thread, &shader, &gun_shader, gun_skin.as_ref(), &default_skin);
 
     println!("Map center: {:?}", map_center);
     let mut player = Player {
@@ -564,10 +670,25 @@ fn main() {
     //Connect to the server
     let mut net_client = NetworkClient::new().unwrap();
     net_client.connect().unwrap();
+    //Ties the id the server gave us to the wallet
+    if let Some(wallet) = &wallet {
+        let signature = wallet.sign_login(net_client::CLIENT_ID.load(Ordering::SeqCst), &net_client.login_nonce);
+        net_client.login(Login {
+            wallet: wallet.pubkey().to_string(),
+            signature: signature.as_ref().to_vec(),
+        });
+    }
 
     // Render loop
     while !rl.window_should_close() {
-        update_player(&rl, &mut player, &map);
+        //Tab for the shop, the player stands still meanwhile
+        if rl.is_key_pressed(KeyboardKey::KEY_TAB) {
+            shop.toggle();
+        }
+        shop.update(&mut rl, &thread);
+        if !shop.open {
+            update_player(&rl, &mut player, &map);
+        }
 
         net_client.update();
         net_client.send_update(PlayerInfo {
@@ -577,7 +698,9 @@ fn main() {
             position_z: player.position.z,
             yaw: player.orientation.y,
             pitch: player.orientation.x,
-            skin: choosen_skin.0.to_string(),
+            skin: choosen_skin
+                .map(|skin| skin.0.to_string())
+                .unwrap_or_default(),
         });
 
         anim_current_frame = (anim_current_frame + 1) % player.model_animations[2].frameCount;
@@ -592,16 +715,27 @@ fn main() {
         camera.target = player.target;
 
         shader.set_shader_value(view_pos_loc, camera.position);
+        gun_shader.set_shader_value(gun_view_pos_loc, camera.position);
+
+        //F3 for the resident skins
+        if rl.is_key_pressed(KeyboardKey::KEY_F3) {
+            texture_manager.show_overlay = !texture_manager.show_overlay;
+        }
 
         //fetch textures.
-        let players: Vec<(Vector3, f32, Option<HashMap<String, WeakTexture2D>>)> = net_client
+        skin_loader.pump(&mut rl, &thread);
+        texture_manager.begin_frame(&mut rl, &thread);
+        let players: Vec<(Vector3, f32, Option<LoadedSkin>, Option<String>)> = net_client
             .remotePlayers
             .iter()
             .map(|p| {
                 //Eh.. the urge to optimize things when its too late..
                 // So check if we have the texture data for the current player
-                let textures: Option<HashMap<String, WeakTexture2D>> =
-                    sol_client.fetch_skin(&mut rl, &thread, &p.skin);
+                let skin: Option<LoadedSkin> =
+                    texture_manager.acquire(&mut rl, &thread, &mut skin_loader, p.id, &p.skin);
+                let status = skin_loader
+                    .state(&p.skin)
+                    .and_then(|state| state.describe());
                 (
                     Vector3 {
                         x: p.position_x,
@@ -609,7 +743,8 @@ fn main() {
                         z: p.position_z,
                     },
                     p.yaw,
-                    textures,
+                    skin,
+                    status,
                 )
             })
             .collect();
@@ -630,6 +765,7 @@ fn main() {
                     ))
                 .to_axis_angle();
 
+            skin_uniforms.bind(&mut gun_shader, gun_skin.as_ref().map(|skin| &skin.style));
             d3d.draw_model_ex(
                 &player.model,
                 player.position,
@@ -653,22 +789,18 @@ fn main() {
             // }
 
             //Draw remote players
-            players.iter().for_each(|(position, yaw, textures)| {
+            players.iter().for_each(|(position, yaw, skin, _)| {
                 //switch gun textures for each instance as we reuse the model.. normally one would use an atlas
 
-                //the no texture material
-                let mut material_count = 0;
-                if let Some(textures) = textures {
-                    //load textures
-                    m_player.apply_gun_textures(textures);
-                    material_count = 1;
-                }
+                //Skins still loading or rejected show the default one
+                m_player.apply_gun_textures(skin.as_ref(), &default_skin);
+                skin_uniforms.bind(&mut gun_shader, skin.as_ref().map(|skin| &skin.style));
 
                 m_player.draw(
                     &mut d3d,
                     &Matrix::translate(position.x, position.y, position.z),
                     &Matrix::rotate(Vector3::new(0.0, 1.0, 0.0), *yaw),
-                    material_count,
+                    1,
                 );
 
                 // let world_box = BoundingBox {
@@ -689,11 +821,25 @@ fn main() {
             });
 
             drop(d3d);
+
+            //Why a skin is missing, above the player
+            players.iter().for_each(|(position, _, _, status)| {
+                if let Some(status) = status {
+                    let screen = dhl.get_world_to_screen(*position + Vector3::new(0.0, 0.5, 0.0), camera);
+                    dhl.draw_text(status, screen.x as i32, screen.y as i32, 14, Color::RED);
+                }
+            });
+
+            texture_manager.draw_overlay(&mut dhl);
+            shop.draw(&mut dhl);
             drop(dhl);
         }
     }
 
     //Unload textures
     unload_textures_from_model(&mut rl, &thread, &player.model);
-    sol_client.clear(&mut rl, &thread);
+    texture_manager.clear(&mut rl, &thread);
+    skin_loader.clear(&mut rl, &thread);
+    shop.clear(&mut rl, &thread);
+    default_skin.unload(&mut rl, &thread);
 }
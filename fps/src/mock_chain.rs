@@ -0,0 +1,758 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+
+use anchor_client::anchor_lang::AnchorSerialize;
+use anchor_client::solana_sdk::hash::Hash;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::Signature;
+use anchor_client::solana_sdk::transaction::Transaction;
+use mpl_token_metadata::accounts::{Edition, MasterEdition, Metadata};
+use mpl_token_metadata::types::{Creator, Key};
+use skin_common::nft_program::SkinListing;
+
+use crate::backend::{ChainBackend, HttpBackend, HttpResponse};
+use crate::utils::sha256_hex;
+
+pub const FILES_URL: &str = "https://files.test";
+
+/**
+ * Local stand-in for the rpc and the metadata hosting, serves fixtures instead of devnet
+ * Skins are built from the samples in /skins
+ */
+#[derive(Default)]
+pub struct MockChain {
+    //Owner -> mints of its token accounts
+    token_accounts: Mutex<HashMap<Pubkey, Vec<Pubkey>>>,
+    decimals: Mutex<HashMap<Pubkey, u8>>,
+    accounts: Mutex<HashMap<Pubkey, Vec<u8>>>,
+    files: Mutex<HashMap<String, Vec<u8>>>,
+    //Gets per url, revalidations included
+    hits: Mutex<HashMap<String, usize>>,
+    //Program transactions oldest first, None logs for the failed ones
+    transactions: Mutex<Vec<(Signature, Option<Vec<String>>)>>,
+}
+
+impl MockChain {
+    pub fn add_token(&self, owner: &Pubkey, mint: &Pubkey, decimals: u8) {
+        self.token_accounts.lock().unwrap().entry(*owner).or_default().push(*mint);
+        self.decimals.lock().unwrap().insert(*mint, decimals);
+    }
+
+    pub fn add_account(&self, address: &Pubkey, data: Vec<u8>) {
+        self.accounts.lock().unwrap().insert(*address, data);
+    }
+
+    //A Metaplex metadata account pointing to uri, at the pda of the mint
+    pub fn add_metadata(&self, mint: &Pubkey, uri: &str) {
+        self.add_metadata_by(mint, uri, None);
+    }
+
+    //Same, with the given first creator like the program sets
+    pub fn add_metadata_by(&self, mint: &Pubkey, uri: &str, creator: Option<Pubkey>) {
+        let metadata = Metadata {
+            key: Key::MetadataV1,
+            update_authority: Pubkey::new_unique(),
+            mint: *mint,
+            name: "skin".to_string(),
+            symbol: "SKIN".to_string(),
+            uri: uri.to_string(),
+            seller_fee_basis_points: if creator.is_some() { 500 } else { 0 },
+            //Like create_nft, the authority then the creator with the whole share
+            creators: creator.map(|address| {
+                vec![
+                    Creator { address, verified: true, share: 0 },
+                    Creator { address: Pubkey::new_unique(), verified: true, share: 100 },
+                ]
+            }),
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: None,
+            collection: None,
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        };
+
+        //Metaplex pads the strings, the creators are at a fixed offset on chain
+        let data = match creator {
+            Some(_) => MockChain::padded(metadata),
+            None => metadata.try_to_vec().unwrap(),
+        };
+        self.add_account(&MockChain::metadata_pda(mint), data);
+    }
+
+    fn padded(mut metadata: Metadata) -> Vec<u8> {
+        let pad = |s: &mut String, len: usize| *s = format!("{:\0<len$}", s, len = len);
+        pad(&mut metadata.name, 32);
+        pad(&mut metadata.symbol, 10);
+        pad(&mut metadata.uri, 200);
+        metadata.try_to_vec().unwrap()
+    }
+
+    //Master edition account of a mint, or a printed edition when printed
+    pub fn add_edition(&self, mint: &Pubkey, supply: u64, max_supply: Option<u64>, printed: bool) {
+        let data = if printed {
+            Edition { key: Key::EditionV1, parent: Pubkey::new_unique(), edition: supply }.try_to_vec().unwrap()
+        } else {
+            MasterEdition { key: Key::MasterEditionV2, supply, max_supply }.try_to_vec().unwrap()
+        };
+        self.add_account(&skin_common::nft_program::pda::edition(mint), data);
+    }
+
+    //A printed edition of master, numbered like the program prints them
+    pub fn add_print(&self, mint: &Pubkey, master: &Pubkey, edition: u64) {
+        let parent = skin_common::nft_program::pda::edition(master);
+        let data = Edition { key: Key::EditionV1, parent, edition }.try_to_vec().unwrap();
+        self.add_account(&skin_common::nft_program::pda::edition(mint), data);
+    }
+
+    //What update_skin does to the listing
+    pub fn set_revision(&self, mint: &Pubkey, revision: u32) {
+        let address = skin_common::nft_program::pda::listing(mint);
+        let mut listing = SkinListing::parse(&self.accounts.lock().unwrap()[&address]).unwrap();
+        listing.revision = revision;
+        self.add_account(&address, listing.to_account_data());
+    }
+
+    //The program's price account of a master mint
+    pub fn add_listing(&self, mint: &Pubkey, price: u64, payment_mint: Option<Pubkey>) {
+        let listing = SkinListing {
+            master_mint: *mint,
+            creator: Pubkey::new_unique(),
+            treasury: Pubkey::new_unique(),
+            price,
+            payment_mint,
+            bump: 255,
+            revision: 0,
+            sale_start: 0,
+            sale_end: 0,
+            paused: false,
+            max_per_wallet: 0,
+            allowlist_root: None,
+            public_start: 0,
+            editions_printed: 0,
+        };
+        self.add_account(&skin_common::nft_program::pda::listing(mint), listing.to_account_data());
+    }
+
+    //What buy and fuse count in the listing
+    pub fn set_printed(&self, mint: &Pubkey, editions_printed: u64) {
+        let address = skin_common::nft_program::pda::listing(mint);
+        let mut listing = SkinListing::parse(&self.accounts.lock().unwrap()[&address]).unwrap();
+        listing.editions_printed = editions_printed;
+        self.add_account(&address, listing.to_account_data());
+    }
+
+    //A transaction of the program with these logs, failed ones are listed but have no logs
+    pub fn add_transaction(&self, logs: Option<Vec<String>>) -> Signature {
+        let signature = Signature::new_unique();
+        self.transactions.lock().unwrap().push((signature, logs));
+        signature
+    }
+
+    pub fn metadata_pda(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+            &mpl_token_metadata::ID,
+        )
+        .0
+    }
+
+    pub fn serve(&self, url: &str, bytes: Vec<u8>) {
+        self.files.lock().unwrap().insert(url.to_string(), bytes);
+    }
+
+    pub fn hits(&self, url: &str) -> usize {
+        self.hits.lock().unwrap().get(url).copied().unwrap_or(0)
+    }
+
+    //The png of a sample skin
+    pub fn sample(skin: &str, slot: &str) -> Vec<u8> {
+        fs::read(format!("{}/../skins/{}/ak_{}.png", env!("CARGO_MANIFEST_DIR"), skin, slot)).unwrap()
+    }
+
+    //Serves the textures a sample skin has and a json for them, owned by owner, returns the mint
+    pub fn add_skin(&self, owner: &Pubkey, skin: &str) -> Pubkey {
+        let mut textures = serde_json::Map::new();
+        for slot in ["a", "r", "n", "m", "ao"] {
+            let path = format!("{}/../skins/{}/ak_{}.png", env!("CARGO_MANIFEST_DIR"), skin, slot);
+            if let Ok(bytes) = fs::read(path) {
+                let url = format!("{}/{}/ak_{}.png", FILES_URL, skin, slot);
+                self.serve(&url, bytes);
+                textures.insert(slot.to_string(), url.into());
+            }
+        }
+
+        self.add_json_skin(owner, skin, serde_json::json!({
+            "name": skin,
+            "symbol": "SKIN",
+            "identifier": "fps+bq",
+            "textures": textures,
+        }))
+    }
+
+    //An nft whose metadata uri serves json
+    pub fn add_json_skin(&self, owner: &Pubkey, name: &str, json: serde_json::Value) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        let uri = format!("{}/{}.json", FILES_URL, name);
+
+        self.serve(&uri, serde_json::to_vec(&json).unwrap());
+        self.add_metadata(&mint, &uri);
+        self.add_token(owner, &mint, 0);
+
+        mint
+    }
+}
+
+impl ChainBackend for MockChain {
+    fn token_mints_by_owner(&self, owner: &Pubkey) -> Result<Vec<Pubkey>, String> {
+        Ok(self.token_accounts.lock().unwrap().get(owner).cloned().unwrap_or_default())
+    }
+
+    fn mint_decimals(&self, mint: &Pubkey) -> Result<u8, String> {
+        self.decimals.lock().unwrap().get(mint).copied().ok_or(format!("Unknown mint {}", mint))
+    }
+
+    fn account_data(&self, address: &Pubkey) -> Result<Vec<u8>, String> {
+        self.accounts.lock().unwrap().get(address).cloned().ok_or(format!("AccountNotFound: {}", address))
+    }
+
+    fn multiple_account_data(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>, String> {
+        let accounts = self.accounts.lock().unwrap();
+        Ok(addresses.iter().map(|address| accounts.get(address).cloned()).collect())
+    }
+
+    //Every account is owned by every program here
+    fn program_accounts(&self, _program: &Pubkey, offset: usize, bytes: &[u8]) -> Result<Vec<(Pubkey, Vec<u8>)>, String> {
+        Ok(self
+            .accounts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, data)| data.get(offset..offset + bytes.len()) == Some(bytes))
+            .map(|(address, data)| (*address, data.clone()))
+            .collect())
+    }
+
+    fn latest_blockhash(&self) -> Result<Hash, String> {
+        Ok(Hash::default())
+    }
+
+    fn send_transaction(&self, _transaction: &Transaction) -> Result<Signature, String> {
+        Err("The mock chain doesn't run programs".to_string())
+    }
+
+    //Every transaction touches every address here
+    fn signatures_for_address(
+        &self,
+        _address: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> Result<Vec<Signature>, String> {
+        let transactions = self.transactions.lock().unwrap();
+        let position = |signature| transactions.iter().position(|(s, _)| *s == signature);
+        let start = until.and_then(position).map_or(0, |i| i + 1);
+        let end = before.and_then(position).unwrap_or(transactions.len());
+
+        Ok(transactions[start..end.max(start)].iter().rev().map(|(signature, _)| *signature).collect())
+    }
+
+    fn transaction_logs(&self, signature: &Signature) -> Result<Option<Vec<String>>, String> {
+        self.transactions
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(s, _)| s == signature)
+            .map(|(_, logs)| logs.clone())
+            .ok_or(format!("Transaction not found: {}", signature))
+    }
+}
+
+impl HttpBackend for MockChain {
+    fn get(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+        _last_modified: Option<&str>,
+        _max_bytes: usize,
+    ) -> Result<HttpResponse, String> {
+        *self.hits.lock().unwrap().entry(url.to_string()).or_default() += 1;
+
+        let Some(body) = self.files.lock().unwrap().get(url).cloned() else {
+            return Ok(HttpResponse { status: 404, etag: None, last_modified: None, body: Vec::new() });
+        };
+
+        let current = format!("\"{}\"", sha256_hex(&body));
+        if etag == Some(current.as_str()) {
+            return Ok(HttpResponse { status: 304, etag: Some(current), last_modified: None, body: Vec::new() });
+        }
+
+        Ok(HttpResponse { status: 200, etag: Some(current), last_modified: None, body })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::skin_cache::{SkinCache, DEFAULT_MAX_BYTES};
+    use crate::sol_client::SolanaClient;
+    use crate::uri_resolver::UriResolver;
+
+    const GATEWAY: &str = "https://gateway.test";
+
+    fn client(mock: &Arc<MockChain>, cache_dir: &Path, offline: bool) -> SolanaClient {
+        let resolver = UriResolver::new(vec![GATEWAY.to_string()], vec![GATEWAY.to_string()]);
+        let cache = SkinCache::new(cache_dir.to_path_buf(), DEFAULT_MAX_BYTES, offline, resolver)
+            .with_http(mock.clone());
+
+        SolanaClient::with_backend(mock.clone(), cache)
+    }
+
+    #[test]
+    fn fetch_skins_keeps_only_compatible_skins() {
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let owner = Pubkey::new_unique();
+
+        let cat = mock.add_skin(&owner, "cat_ak");
+
+        //Fungible token
+        mock.add_token(&owner, &Pubkey::new_unique(), 6);
+        //Collection nft, no uri
+        let collection = Pubkey::new_unique();
+        mock.add_metadata(&collection, "");
+        mock.add_token(&owner, &collection, 0);
+        //Some other game's nft
+        mock.add_json_skin(&owner, "other", serde_json::json!({
+            "name": "other", "symbol": "O", "identifier": "not-us", "textures": {},
+        }));
+        //Json is gone
+        let gone = Pubkey::new_unique();
+        mock.add_metadata(&gone, &format!("{}/gone.json", FILES_URL));
+        mock.add_token(&owner, &gone, 0);
+        //Json is not a skin
+        mock.add_json_skin(&owner, "malformed", serde_json::json!({ "name": 3 }));
+        //Fails validation
+        mock.add_json_skin(&owner, "invalid", serde_json::json!({
+            "schema_version": 2, "name": "invalid", "symbol": "I", "identifier": "fps+bq",
+            "textures": { "a": "ftp://nope", "orm": "ipfs://cid", "ao": "ipfs://cid" }, "tint": "red",
+        }));
+        //For a gun this client doesn't have
+        mock.add_json_skin(&owner, "m4", serde_json::json!({
+            "schema_version": 2, "name": "m4", "symbol": "M", "identifier": "fps+bq", "weapon": "m4",
+            "textures": { "a": "ipfs://cid" },
+        }));
+        //No metadata account at all
+        mock.add_token(&owner, &Pubkey::new_unique(), 0);
+        //Metadata account that doesn't deserialize
+        let garbage = Pubkey::new_unique();
+        mock.add_account(&MockChain::metadata_pda(&garbage), vec![4, 2]);
+        mock.add_token(&owner, &garbage, 0);
+
+        let skins = client(&mock, dir.path(), false).fetch_skins(owner).unwrap();
+
+        assert_eq!(skins.len(), 1);
+        assert_eq!(skins[0].0, cat);
+        assert_eq!(skins[0].1.name, "cat_ak");
+    }
+
+    #[test]
+    fn fetch_skin_returns_every_slot() {
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let mint = mock.add_skin(&Pubkey::new_unique(), "turtle_ak");
+
+        let imgs = client(&mock, dir.path(), false).fetch_skin(&mint.to_string()).unwrap().1;
+
+        assert_eq!(imgs.len(), 5);
+        for slot in ["a", "r", "n", "m", "ao"] {
+            assert_eq!(imgs[slot], MockChain::sample("turtle_ak", slot));
+        }
+    }
+
+    #[test]
+    fn missing_slots_are_left_out() {
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        //Only has ao and m
+        let mint = mock.add_skin(&Pubkey::new_unique(), "ak_1");
+
+        let imgs = client(&mock, dir.path(), false).fetch_skin(&mint.to_string()).unwrap().1;
+
+        let mut slots: Vec<&String> = imgs.keys().collect();
+        slots.sort();
+        assert_eq!(slots, ["ao", "m"]);
+    }
+
+    #[test]
+    fn fetch_skin_rejects_tampered_textures() {
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let a = format!("{}/cat_ak/ak_a.png", FILES_URL);
+        mock.serve(&a, MockChain::sample("cat_ak", "a"));
+
+        let mint = mock.add_json_skin(&Pubkey::new_unique(), "tampered", serde_json::json!({
+            "name": "tampered",
+            "symbol": "T",
+            "identifier": "fps+bq",
+            "textures": { "a": a },
+            //hash of the turtle, the cat is served
+            "sha256": { "a": sha256_hex(&MockChain::sample("turtle_ak", "a")) },
+        }));
+
+        let err = client(&mock, dir.path(), false).fetch_skin(&mint.to_string()).unwrap_err();
+        assert!(err.contains("sha256"), "{}", err);
+    }
+
+    #[test]
+    fn fetch_skin_fails_on_bad_accounts() {
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let client = client(&mock, dir.path(), false);
+
+        assert!(client.fetch_skin("not a pubkey").is_err());
+        //No metadata account
+        assert!(client.fetch_skin(&Pubkey::new_unique().to_string()).is_err());
+
+        let garbage = Pubkey::new_unique();
+        mock.add_account(&MockChain::metadata_pda(&garbage), vec![0xFF; 3]);
+        assert!(client.fetch_skin(&garbage.to_string()).is_err());
+    }
+
+    #[test]
+    fn textures_resolve_through_gateways_and_data_uris() {
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let m = MockChain::sample("cat_ak", "m");
+        mock.serve(&format!("{}/ipfs/bafycat/ak_m.png", GATEWAY), m.clone());
+
+        //1x1 png
+        let data_png = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+        let mint = mock.add_json_skin(&Pubkey::new_unique(), "uris", serde_json::json!({
+            "name": "uris",
+            "symbol": "U",
+            "identifier": "fps+bq",
+            "textures": { "m": "ipfs://bafycat/ak_m.png", "a": data_png },
+        }));
+
+        let imgs = client(&mock, dir.path(), false).fetch_skin(&mint.to_string()).unwrap().1;
+
+        assert_eq!(imgs["m"], m);
+        assert!(imgs["a"].starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn cached_entries_are_revalidated() {
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let mint = mock.add_skin(&Pubkey::new_unique(), "cat_ak");
+        let client = client(&mock, dir.path(), false);
+        let a = format!("{}/cat_ak/ak_a.png", FILES_URL);
+
+        let first = client.fetch_skin(&mint.to_string()).unwrap().1;
+        let second = client.fetch_skin(&mint.to_string()).unwrap().1;
+
+        //The second get is a 304 with the etag of the first
+        assert_eq!(mock.hits(&a), 2);
+        assert_eq!(first["a"], second["a"]);
+    }
+
+    #[test]
+    fn offline_start_comes_from_the_cache() {
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let owner = Pubkey::new_unique();
+        let mint = mock.add_skin(&owner, "cat_ak");
+
+        let online = client(&mock, dir.path(), false);
+        online.fetch_skins(owner).unwrap();
+        online.fetch_skin(&mint.to_string()).unwrap();
+
+        //Nothing on chain, nothing served
+        let empty = Arc::new(MockChain::default());
+        let offline = client(&empty, dir.path(), true);
+
+        let skins = offline.fetch_skins(owner).unwrap();
+        assert_eq!(skins.len(), 1);
+        assert_eq!(skins[0].0, mint);
+        assert_eq!(offline.fetch_skin(&mint.to_string()).unwrap().1.len(), 5);
+
+        //Never seen wallet
+        assert!(offline.fetch_skins(Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn listings_are_the_master_editions_of_the_program() {
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let authority = skin_common::nft_program::pda::mint_authority();
+
+        let cat = mock.add_skin(&Pubkey::new_unique(), "cat_ak");
+        let uri = format!("{}/cat_ak.json", FILES_URL);
+        mock.add_metadata_by(&cat, &uri, Some(authority));
+        //3 of 5 printed, a burn lowered the supply but not what is left
+        mock.add_edition(&cat, 2, Some(5), false);
+        mock.add_listing(&cat, 1_500_000_000, None);
+        mock.set_printed(&cat, 3);
+
+        //A print of it has the same creators
+        let print = Pubkey::new_unique();
+        mock.add_metadata_by(&print, &uri, Some(authority));
+        mock.add_edition(&print, 1, None, true);
+        //The collection
+        let collection = Pubkey::new_unique();
+        mock.add_metadata_by(&collection, "", Some(authority));
+        mock.add_edition(&collection, 0, Some(0), false);
+        //Someone else's
+        let other = mock.add_skin(&Pubkey::new_unique(), "turtle_ak");
+        mock.add_edition(&other, 0, None, false);
+        //Made before prices, nothing to pay with
+        let old = mock.add_skin(&Pubkey::new_unique(), "turtle_ak");
+        mock.add_metadata_by(&old, &format!("{}/turtle_ak.json", FILES_URL), Some(authority));
+        mock.add_edition(&old, 0, None, false);
+
+        let listings = client(&mock, dir.path(), false).fetch_listings().unwrap();
+
+        assert_eq!(listings.len(), 1);
+        assert_eq!(listings[0].master_mint, cat);
+        assert_eq!(listings[0].remaining(), Some(2));
+        assert_eq!(listings[0].price_text(), "1.5 SOL");
+        assert_eq!(listings[0].seller_fee_basis_points, 500);
+        assert_eq!(listings[0].creators.len(), 1);
+        assert_eq!(listings[0].creators[0].1, 100);
+    }
+
+    #[test]
+    fn prints_follow_their_master() {
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let authority = skin_common::nft_program::pda::mint_authority();
+        let owner = Pubkey::new_unique();
+
+        //The creator moved the master to the cat skin, the print still points to the turtle one
+        let master = mock.add_skin(&Pubkey::new_unique(), "cat_ak");
+        mock.add_metadata_by(&master, &format!("{}/cat_ak.json", FILES_URL), Some(authority));
+        mock.add_edition(&master, 1, Some(5), false);
+        mock.add_listing(&master, 0, None);
+        let print = mock.add_skin(&owner, "turtle_ak");
+        mock.add_metadata_by(&print, &format!("{}/turtle_ak.json", FILES_URL), Some(authority));
+        mock.add_print(&print, &master, 1);
+
+        let client = client(&mock, dir.path(), false);
+        let skins = client.fetch_skins(owner).unwrap();
+        assert_eq!(skins.len(), 1);
+        assert_eq!(skins[0].1.name, "cat_ak");
+
+        //A new revision drops the cached skin, it is fetched again
+        let cache = SkinCache::new(dir.path().to_path_buf(), DEFAULT_MAX_BYTES, true, UriResolver::from_env());
+        assert_eq!(cache.load_revision(&print.to_string()), 0);
+        mock.set_revision(&master, 1);
+
+        let (skin, _) = client.fetch_skin(&print.to_string()).unwrap();
+        assert_eq!(skin.name, "cat_ak");
+        assert_eq!(cache.load_revision(&print.to_string()), 1);
+    }
+
+    #[test]
+    fn allowlist_proofs_verify() {
+        //Odd count, the last leaf goes up a level on its own
+        let wallets: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let root = skin_common::merkle::root(&wallets).unwrap();
+
+        for wallet in &wallets {
+            let proof = skin_common::merkle::proof(&wallets, wallet).unwrap();
+            assert!(skin_common::merkle::verify(&proof, &root, wallet));
+        }
+
+        let outsider = Pubkey::new_unique();
+        assert!(skin_common::merkle::proof(&wallets, &outsider).is_none());
+        let proof = skin_common::merkle::proof(&wallets, &wallets[0]).unwrap();
+        assert!(!skin_common::merkle::verify(&proof, &root, &outsider));
+        assert!(skin_common::merkle::root(&[]).is_none());
+    }
+
+    #[test]
+    fn fuse_burns_only_what_the_recipe_still_needs() {
+        use anchor_client::solana_sdk::signature::{Keypair, Signer};
+
+        use skin_common::nft_program::{pda, FuseProgress, Ingredient, Recipe};
+
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let player = Keypair::new();
+
+        let upgrade = Pubkey::new_unique();
+        let common = Pubkey::new_unique();
+        mock.add_edition(&upgrade, 0, Some(5), false);
+        mock.add_listing(&upgrade, 0, None);
+        let recipe = Recipe {
+            upgrade_mint: upgrade,
+            creator: Pubkey::new_unique(),
+            ingredients: vec![Ingredient { master_mint: common, count: 2 }],
+            revision: 1,
+            bump: 255,
+        };
+        mock.add_account(&pda::recipe(&upgrade), recipe.to_account_data());
+
+        //One print of the common skin, and one of something else
+        let print = Pubkey::new_unique();
+        mock.add_token(&player.pubkey(), &print, 0);
+        mock.add_print(&print, &common, 1);
+        let other = Pubkey::new_unique();
+        let rare = Pubkey::new_unique();
+        mock.add_token(&player.pubkey(), &other, 0);
+        mock.add_print(&other, &rare, 1);
+
+        let client = client(&mock, dir.path(), false);
+        let error = client.fuse(&player, &upgrade).unwrap_err();
+        assert_eq!(error, format!("Needs 1 more prints of {}..", &common.to_string()[..4]));
+
+        //One burned already, the print in the wallet is enough and the burn is sent
+        let progress = FuseProgress {
+            player: player.pubkey(),
+            upgrade_mint: upgrade,
+            recipe_revision: 1,
+            ingredients: recipe.ingredients.clone(),
+            burned: vec![Ingredient { master_mint: common, count: 1 }],
+            bump: 255,
+        };
+        mock.add_account(&pda::fuse_progress(&upgrade, &player.pubkey()), progress.to_account_data());
+        assert_eq!(client.fuse(&player, &upgrade).unwrap_err(), "The mock chain doesn't run programs");
+
+        //The creator asks for two rare prints now, the burn already made still goes toward the common ones
+        let changed = Recipe {
+            ingredients: vec![Ingredient { master_mint: rare, count: 2 }],
+            revision: 2,
+            ..recipe
+        };
+        mock.add_account(&pda::recipe(&upgrade), changed.to_account_data());
+        assert_eq!(client.fuse(&player, &upgrade).unwrap_err(), "The mock chain doesn't run programs");
+
+        //Once fused the progress is closed, the next fuse fills the new recipe
+        mock.accounts.lock().unwrap().remove(&pda::fuse_progress(&upgrade, &player.pubkey()));
+        assert_eq!(client.fuse(&player, &upgrade).unwrap_err(), format!("Needs 1 more prints of {}..", &rare.to_string()[..4]));
+
+        //Nothing is burned for a sold out upgrade, even when burns lowered its supply
+        mock.add_edition(&upgrade, 4, Some(5), false);
+        mock.set_printed(&upgrade, 5);
+        assert_eq!(client.fuse(&player, &upgrade).unwrap_err(), "Sold out");
+
+        //No recipe at all
+        assert_eq!(client.fuse(&player, &common).unwrap_err(), "This skin has no recipe");
+    }
+
+    #[test]
+    fn market_lists_the_prints_in_escrow() {
+        use anchor_client::solana_sdk::signature::Keypair;
+
+        use skin_common::nft_program::{pda, TradeListing};
+
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let authority = pda::mint_authority();
+        let seller = Pubkey::new_unique();
+
+        //A print of the cat skin put on the market, it left the seller's wallet for the escrow
+        let master = mock.add_skin(&Pubkey::new_unique(), "cat_ak");
+        mock.add_metadata_by(&master, &format!("{}/cat_ak.json", FILES_URL), Some(authority));
+        mock.add_edition(&master, 1, Some(5), false);
+        mock.add_listing(&master, 0, None);
+        let print = mock.add_skin(&Pubkey::new_unique(), "turtle_ak");
+        mock.add_metadata_by(&print, &format!("{}/turtle_ak.json", FILES_URL), Some(authority));
+        mock.add_print(&print, &master, 1);
+        let listing = TradeListing { seller, mint: print, price: 2_500_000_000, bump: 255 };
+        mock.add_account(&pda::trade_listing(&print), listing.to_account_data());
+
+        let client = client(&mock, dir.path(), false);
+        let offers = client.fetch_market().unwrap();
+
+        assert_eq!(offers.len(), 1);
+        assert_eq!(offers[0].mint, print);
+        assert_eq!(offers[0].seller, seller);
+        assert_eq!(offers[0].skin.name, "cat_ak");
+        assert_eq!(offers[0].price_text(), "2.5 SOL");
+        assert_eq!(offers[0].seller_fee_basis_points, 500);
+        assert_eq!(offers[0].creators.len(), 1);
+
+        //The seller relisted higher since the market loaded
+        let buyer = Keypair::new();
+        let error = client.purchase_listing(&buyer, &print, 1_000_000_000).unwrap_err();
+        assert_eq!(error, "The price changed to 2.5 SOL");
+        let error = client.purchase_listing(&buyer, &print, 2_500_000_000).unwrap_err();
+        assert_eq!(error, "The mock chain doesn't run programs");
+        let error = client.purchase_listing(&buyer, &master, 2_500_000_000).unwrap_err();
+        assert_eq!(error, "Not on the market anymore");
+    }
+
+    #[test]
+    fn indexer_replays_program_events() {
+        use base64::engine::general_purpose::STANDARD as BASE64;
+        use base64::Engine;
+
+        use crate::indexer::EventStore;
+        use skin_common::nft_program::{ProgramEvent, SkinCreated, SkinPurchased, PROGRAM_ID};
+
+        let mock = Arc::new(MockChain::default());
+        let dir = tempfile::tempdir().unwrap();
+        let master_mint = Pubkey::new_unique();
+
+        let created = ProgramEvent::SkinCreated(SkinCreated {
+            master_mint,
+            creator: Pubkey::new_unique(),
+            collection_mint: Pubkey::new_unique(),
+            name: "CAT_Ak".to_string(),
+            uri: format!("{}/cat_ak.json", FILES_URL),
+            max_supply: 500,
+            seller_fee_basis_points: 500,
+        });
+        let purchase = |edition| SkinPurchased {
+            master_mint,
+            edition_mint: Pubkey::new_unique(),
+            edition,
+            buyer: Pubkey::new_unique(),
+            price: 500_000_000,
+            payment_mint: None,
+        };
+        let (first, second) = (purchase(248), purchase(249));
+
+        let data = |event: &ProgramEvent| format!("Program data: {}", BASE64.encode(event.to_log_data()));
+        let invoke = |program: &Pubkey, depth: u8| format!("Program {} invoke [{}]", program, depth);
+        let success = |program: &Pubkey| format!("Program {} success", program);
+
+        mock.add_transaction(Some(vec![invoke(&PROGRAM_ID, 1), data(&created), success(&PROGRAM_ID)]));
+        //Failed, whatever it logged doesn't count
+        mock.add_transaction(None);
+        //The data logged while the metadata program runs is not ours
+        mock.add_transaction(Some(vec![
+            invoke(&PROGRAM_ID, 1),
+            "Program log: invoke the metadata program".to_string(),
+            invoke(&mpl_token_metadata::ID, 2),
+            data(&ProgramEvent::SkinPurchased(purchase(1))),
+            success(&mpl_token_metadata::ID),
+            data(&ProgramEvent::SkinPurchased(first.clone())),
+            success(&PROGRAM_ID),
+        ]));
+
+        let path = dir.path().join("events.json");
+        let mut store = EventStore::load(&path);
+        let events = store.sync(mock.as_ref()).unwrap();
+        assert_eq!(events, vec![created, ProgramEvent::SkinPurchased(first)]);
+        assert!(store.sync(mock.as_ref()).unwrap().is_empty());
+
+        //A new start reads only what came after, from the store on disk
+        mock.add_transaction(Some(vec![
+            invoke(&PROGRAM_ID, 1),
+            data(&ProgramEvent::SkinPurchased(second.clone())),
+            success(&PROGRAM_ID),
+        ]));
+        let mut store = EventStore::load(&path);
+        assert_eq!(store.sync(mock.as_ref()).unwrap(), vec![ProgramEvent::SkinPurchased(second)]);
+        assert_eq!(store.recent_purchases(5).map(|purchase| purchase.edition).collect::<Vec<_>>(), vec![249, 248]);
+        assert_eq!(store.skin_name(&master_mint), Some("CAT_Ak"));
+    }
+}
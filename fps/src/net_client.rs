@@ -4,15 +4,21 @@ use enet::*;
 use std::{fs::remove_dir, future::pending, io::Read, mem, net::Ipv4Addr, pin::Pin, sync::atomic::{AtomicI32, Ordering}};
 
 use crate::dto;
-use dto::PlayerInfo;
+use dto::{Login, PlayerInfo};
 
 const ADDRESS: Ipv4Addr = Ipv4Addr::LOCALHOST;
 const PORT: u16 = 6969;
+//Channel 0 is relayed to the other players, what goes on 1 is for the server only
+const LOGIN_CHANNEL: u8 = 1;
+//What the login signs, sent by the server after our id
+const LOGIN_NONCE_LEN: usize = 32;
 
 pub struct NetworkClient {
     host: Pin<Box<Host<()>>>,
     peer: Option<Peer<'static, ()>>,
     pub remotePlayers: Vec<PlayerInfo>,
+    //Given with the id, a new one each connection
+    pub login_nonce: [u8; LOGIN_NONCE_LEN],
 }
 
 // static mut CLIENT_ID: i32 = -1;
@@ -36,7 +42,8 @@ impl NetworkClient {
         Ok(Self {
             host: Box::pin(host),
             peer: None,
-            remotePlayers: Vec::new()
+            remotePlayers: Vec::new(),
+            login_nonce: [0; LOGIN_NONCE_LEN],
         })
     }
 
@@ -44,7 +51,7 @@ impl NetworkClient {
         let host_ref: &'static mut Host<()> = unsafe { std::mem::transmute(&mut *self.host) };
 
         let peer = host_ref
-            .connect(&Address::new(ADDRESS, PORT), 1, 0)
+            .connect(&Address::new(ADDRESS, PORT), 2, 0)
             .map_err(|_| "connect failed")?;
 
         self.peer = Some(peer);
@@ -53,8 +60,9 @@ impl NetworkClient {
         loop{
             match self.host.service(0).map_err(|_| "connect failed")? {
                 Some(Event::Receive { ref packet, .. }) => {
-                    if packet.data().len() == 4 {
+                    if packet.data().len() == 4 + LOGIN_NONCE_LEN {
                         CLIENT_ID.store(i32::from_le_bytes(packet.data()[0..4].try_into().expect("slice with incorrect length")), Ordering::SeqCst);
+                        self.login_nonce.copy_from_slice(&packet.data()[4..]);
                         // unsafe { CLIENT_ID = i32::from_le_bytes(packet.data()[0..4].try_into().expect("slice with incorrect length")) };
                     } else {
                         return Err("Invalid packet length".to_string());
@@ -103,6 +111,16 @@ impl NetworkClient {
         }
     }
 
+    //The server checks the signature against the id and nonce it gave us and drops us when it doesn't match
+    pub fn login(&mut self, login: Login) {
+        if let Some(peer) = &mut self.peer {
+            let bytes: Vec<u8> = bincode::serialize(&login).expect("serialization failed");
+            let packet = Packet::new(&bytes, PacketMode::ReliableSequenced)
+                .expect("packet creation failed");
+            let _ = peer.send_packet(packet, LOGIN_CHANNEL);
+        }
+    }
+
     pub fn disconnect(&mut self) {
         if let Some(peer) = &mut self.peer {
             peer.disconnect(0);
@@ -0,0 +1,692 @@
+use std::collections::HashSet;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{Keypair, Signature};
+use raylib::color::Color;
+use raylib::consts::KeyboardKey;
+use raylib::math::{Rectangle, Vector2};
+use raylib::prelude::{RaylibDraw, RaylibDrawHandle};
+use raylib::texture::WeakTexture2D;
+use raylib::{RaylibHandle, RaylibThread};
+use skin_common::nft_program::{ProgramEvent, Recipe, SkinPurchased};
+use skin_common::skin_schema::SkinMetadata;
+use skin_common::texture_validation::{sanitize, DecodedTexture};
+
+use crate::indexer::EventStore;
+use crate::skin_loader::upload_texture;
+use crate::sol_client::{Listing, MarketListing, SolanaClient};
+
+const PREVIEW_SIZE: f32 = 256.0;
+//Seconds between two reads of the program's new transactions
+const SYNC_INTERVAL: f64 = 30.0;
+const RECENT_PURCHASES: usize = 5;
+//What can be typed in a market price
+const PRICE_KEYS: [(KeyboardKey, char); 11] = [
+    (KeyboardKey::KEY_ZERO, '0'),
+    (KeyboardKey::KEY_ONE, '1'),
+    (KeyboardKey::KEY_TWO, '2'),
+    (KeyboardKey::KEY_THREE, '3'),
+    (KeyboardKey::KEY_FOUR, '4'),
+    (KeyboardKey::KEY_FIVE, '5'),
+    (KeyboardKey::KEY_SIX, '6'),
+    (KeyboardKey::KEY_SEVEN, '7'),
+    (KeyboardKey::KEY_EIGHT, '8'),
+    (KeyboardKey::KEY_NINE, '9'),
+    (KeyboardKey::KEY_PERIOD, '.'),
+];
+
+enum ShopEvent {
+    Listings(Result<Vec<(Listing, Option<DecodedTexture>)>, String>),
+    Bought(Result<(Pubkey, Signature), String>),
+    Fused(Result<(Pubkey, Signature), String>),
+    Market(Result<Vec<MarketListing>, String>),
+    Traded(Trade, Result<Signature, String>),
+    //New events and the recent purchases with the name of their skin
+    Synced(Result<(Vec<ProgramEvent>, Vec<(SkinPurchased, Option<String>)>), String>),
+    NewSkin(Pubkey, Result<SkinMetadata, String>),
+}
+
+//What the market did with a print
+enum Trade {
+    Listed(Pubkey),
+    Cancelled(Pubkey),
+    Bought(Pubkey),
+}
+
+struct ShopEntry {
+    listing: Listing,
+    preview: Option<WeakTexture2D>,
+}
+
+/**
+ * Tab opens it, lists the skins of our program and buys the selected one with the wallet
+ * Skins with a recipe can be fused too, burning the prints it takes
+ * M switches to the market, where players sell their prints to each other
+ * Everything that talks to the chain runs on its own thread and reports back through events
+ */
+pub struct Shop {
+    pub open: bool,
+    sol_client: SolanaClient,
+    owner: Pubkey,
+    //None browses only
+    buyer: Option<Arc<Keypair>>,
+    //Wallets of the early access drops, from --allowlist
+    allowlist: Arc<Vec<Pubkey>>,
+    entries: Vec<ShopEntry>,
+    //Metadata uris of the skins the wallet has, printed editions keep the uri of their master
+    owned: HashSet<String>,
+    //Only the sync threads lock it, the frame never waits on the rpc
+    history: Arc<Mutex<EventStore>>,
+    recent: Vec<(SkinPurchased, Option<String>)>,
+    syncing: bool,
+    last_sync: f64,
+    selected: usize,
+    loading: bool,
+    buying: bool,
+    status: String,
+    //Market mode, offers of the players and the wallet's prints to sell
+    market: bool,
+    offers: Vec<MarketListing>,
+    //Mint and name of the skins the wallet has
+    wallet: Vec<(Pubkey, String)>,
+    //Typed in SOL
+    price_input: String,
+    events: Receiver<ShopEvent>,
+    sender: Sender<ShopEvent>,
+}
+
+impl Shop {
+    pub fn new(
+        sol_client: SolanaClient,
+        owner: Pubkey,
+        buyer: Option<Arc<Keypair>>,
+        allowlist: Vec<Pubkey>,
+        inventory: &[(Pubkey, SkinMetadata)],
+    ) -> Self {
+        let (sender, events) = channel();
+
+        Self {
+            open: false,
+            sol_client,
+            owner,
+            buyer,
+            allowlist: Arc::new(allowlist),
+            entries: Vec::new(),
+            owned: inventory.iter().map(|(_, skin)| skin.uri.clone()).collect(),
+            history: Arc::new(Mutex::new(EventStore::load(&EventStore::default_path()))),
+            recent: Vec::new(),
+            syncing: false,
+            last_sync: -SYNC_INTERVAL,
+            selected: 0,
+            loading: false,
+            buying: false,
+            status: String::new(),
+            market: false,
+            offers: Vec::new(),
+            wallet: inventory.iter().map(|(mint, skin)| (*mint, skin.name.clone())).collect(),
+            price_input: String::new(),
+            events,
+            sender,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+
+        if self.open && self.entries.is_empty() && !self.loading {
+            self.refresh();
+        }
+    }
+
+    fn refresh(&mut self) {
+        if self.sol_client.is_offline() {
+            self.status = "The shop is not available offline".to_string();
+            return;
+        }
+
+        self.loading = true;
+        let sol_client = self.sol_client.clone();
+        let sender = self.sender.clone();
+
+        thread::spawn(move || {
+            //Decoded here, only the upload is left for the main thread
+            let listings = sol_client.fetch_listings().map(|listings| {
+                listings
+                    .into_iter()
+                    .map(|listing| {
+                        let preview = listing.preview.as_ref().and_then(|bytes| sanitize(bytes).ok());
+                        (listing, preview)
+                    })
+                    .collect()
+            });
+            let _ = sender.send(ShopEvent::Listings(listings));
+        });
+    }
+
+    fn refresh_market(&mut self) {
+        if self.sol_client.is_offline() {
+            self.status = "The market is not available offline".to_string();
+            return;
+        }
+
+        self.loading = true;
+        let sol_client = self.sol_client.clone();
+        let sender = self.sender.clone();
+
+        thread::spawn(move || {
+            let _ = sender.send(ShopEvent::Market(sol_client.fetch_market()));
+        });
+    }
+
+    //Replays the program's transactions since the last sync, purchases of the wallet show up without a rescan
+    fn sync(&mut self, now: f64) {
+        if self.sol_client.is_offline() || self.syncing {
+            return;
+        }
+
+        self.syncing = true;
+        self.last_sync = now;
+        let sol_client = self.sol_client.clone();
+        let sender = self.sender.clone();
+        let history = self.history.clone();
+
+        thread::spawn(move || {
+            let mut history = history.lock().unwrap();
+            let synced = sol_client.sync_events(&mut history).map(|events| {
+                let recent = history
+                    .recent_purchases(RECENT_PURCHASES)
+                    .map(|purchase| (purchase.clone(), history.skin_name(&purchase.master_mint).map(str::to_string)))
+                    .collect();
+                (events, recent)
+            });
+            let _ = sender.send(ShopEvent::Synced(synced));
+        });
+    }
+
+    fn add_bought_skin(&self, mint: Pubkey) {
+        let sol_client = self.sol_client.clone();
+        let sender = self.sender.clone();
+        let owner = self.owner;
+
+        thread::spawn(move || {
+            let _ = sender.send(ShopEvent::NewSkin(mint, sol_client.add_bought_skin(&owner, &mint)));
+        });
+    }
+
+    //"CAT_Ak #12 by 7xKX.. for 0.5 SOL"
+    fn purchase_text(&self, purchase: &SkinPurchased, name: Option<&str>) -> String {
+        let listing = self
+            .entries
+            .iter()
+            .map(|entry| &entry.listing)
+            .find(|listing| listing.master_mint == purchase.master_mint);
+        let name = name
+            .or(listing.map(|listing| listing.skin.name.as_str()))
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}..", &purchase.master_mint.to_string()[..4]));
+        let price = match listing {
+            Some(listing) => listing.amount_text(purchase.price),
+            None => format!("{} units", purchase.price),
+        };
+
+        format!("{} #{} by {}.. for {}", name, purchase.edition, &purchase.buyer.to_string()[..4], price)
+    }
+
+    //"2x CAT_Ak + 1x DOG_M4", names of the skins the shop lists
+    fn recipe_text(&self, recipe: &Recipe) -> String {
+        let ingredients: Vec<String> = recipe
+            .ingredients
+            .iter()
+            .map(|ingredient| {
+                let name = self
+                    .entries
+                    .iter()
+                    .find(|entry| entry.listing.master_mint == ingredient.master_mint)
+                    .map(|entry| entry.listing.skin.name.clone())
+                    .unwrap_or_else(|| format!("{}..", &ingredient.master_mint.to_string()[..4]));
+                format!("{}x {}", ingredient.count, name)
+            })
+            .collect();
+
+        ingredients.join(" + ")
+    }
+
+    fn fuse(&mut self) {
+        let Some(entry) = self.entries.get(self.selected) else {
+            return;
+        };
+        let Some(recipe) = &entry.listing.recipe else {
+            self.status = "This skin has no recipe".to_string();
+            return;
+        };
+        let Some(player) = self.buyer.clone() else {
+            self.status = "Start with a wallet (--keypair, --seed-phrase or --wallet) to fuse".to_string();
+            return;
+        };
+        if entry.listing.remaining() == Some(0) {
+            self.status = "Sold out".to_string();
+            return;
+        }
+
+        self.buying = true;
+        self.status = format!("Fusing {} into {}...", self.recipe_text(recipe), entry.listing.skin.name);
+
+        let sol_client = self.sol_client.clone();
+        let sender = self.sender.clone();
+        let upgrade_mint = entry.listing.master_mint;
+
+        thread::spawn(move || {
+            let _ = sender.send(ShopEvent::Fused(sol_client.fuse(&player, &upgrade_mint)));
+        });
+    }
+
+    fn buy(&mut self) {
+        let Some(entry) = self.entries.get(self.selected) else {
+            return;
+        };
+        let Some(buyer) = self.buyer.clone() else {
+            self.status = "Start with a wallet (--keypair, --seed-phrase or --wallet) to buy".to_string();
+            return;
+        };
+        if entry.listing.remaining() == Some(0) {
+            self.status = "Sold out".to_string();
+            return;
+        }
+        if let Some(status) = entry.listing.sale_status() {
+            self.status = format!("Not on sale: {}", status);
+            return;
+        }
+
+        self.buying = true;
+        self.status = format!("Buying {} for {}...", entry.listing.skin.name, entry.listing.price_text());
+
+        let sol_client = self.sol_client.clone();
+        let sender = self.sender.clone();
+        let master_mint = entry.listing.master_mint;
+        let allowlist = self.allowlist.clone();
+
+        thread::spawn(move || {
+            let _ = sender.send(ShopEvent::Bought(sol_client.buy(&buyer, &master_mint, &allowlist)));
+        });
+    }
+
+    //Enter in the market: buys an offer, takes back one of ours or lists the selected print of the wallet
+    fn trade(&mut self) {
+        let Some(wallet) = self.buyer.clone() else {
+            self.status = "Start with a wallet (--keypair, --seed-phrase or --wallet) to trade".to_string();
+            return;
+        };
+
+        let sol_client = self.sol_client.clone();
+        let sender = self.sender.clone();
+        if let Some(offer) = self.offers.get(self.selected) {
+            let mint = offer.mint;
+            if offer.seller == self.owner {
+                self.status = format!("Taking {} off the market...", offer.skin.name);
+                thread::spawn(move || {
+                    let _ = sender.send(ShopEvent::Traded(Trade::Cancelled(mint), sol_client.cancel(&wallet, &mint)));
+                });
+            } else {
+                self.status = format!("Buying {} for {}...", offer.skin.name, offer.price_text());
+                let price = offer.price;
+                thread::spawn(move || {
+                    let signature = sol_client.purchase_listing(&wallet, &mint, price);
+                    let _ = sender.send(ShopEvent::Traded(Trade::Bought(mint), signature));
+                });
+            }
+        } else if let Some((mint, name)) = self.wallet.get(self.selected - self.offers.len()) {
+            let price = match self.price_input.parse::<f64>() {
+                Ok(price) if price > 0.0 => (price * 1e9).round() as u64,
+                _ => {
+                    self.status = "Type a price in SOL first".to_string();
+                    return;
+                }
+            };
+            self.status = format!("Listing {} for {} SOL...", name, self.price_input);
+            let mint = *mint;
+            thread::spawn(move || {
+                let _ = sender.send(ShopEvent::Traded(Trade::Listed(mint), sol_client.list(&wallet, &mint, price)));
+            });
+        } else {
+            return;
+        }
+
+        self.buying = true;
+    }
+
+    fn update_market(&mut self, rl: &mut RaylibHandle) {
+        for (key, c) in PRICE_KEYS {
+            if rl.is_key_pressed(key) && !(c == '.' && self.price_input.contains('.')) {
+                self.price_input.push(c);
+            }
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            self.price_input.pop();
+        }
+
+        let rows = self.offers.len() + self.wallet.len();
+        if rows == 0 {
+            return;
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_DOWN) {
+            self.selected = (self.selected + 1) % rows;
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_UP) {
+            self.selected = (self.selected + rows - 1) % rows;
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) && !self.buying {
+            self.trade();
+        }
+    }
+
+    //Call once per frame, handles the keys while open and what the threads sent back
+    pub fn update(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                ShopEvent::Listings(Ok(listings)) => {
+                    self.unload_previews(rl, thread);
+                    self.entries = listings
+                        .into_iter()
+                        .map(|(listing, preview)| ShopEntry {
+                            listing,
+                            preview: preview.and_then(|preview| upload_texture(rl, thread, &preview).ok()),
+                        })
+                        .collect();
+                    self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+                    self.loading = false;
+                }
+                ShopEvent::Listings(Err(e)) => {
+                    self.status = format!("Could not load the shop: {}", e);
+                    self.loading = false;
+                }
+                ShopEvent::Bought(Ok((mint, signature))) => {
+                    self.status = format!("Bought edition {} (tx {})", mint, signature);
+                    self.buying = false;
+                    //Supply went up, the sync brings the new skin
+                    self.refresh();
+                    self.sync(rl.get_time());
+                }
+                ShopEvent::Bought(Err(e)) => {
+                    self.status = format!("Purchase failed: {}", e);
+                    self.buying = false;
+                }
+                ShopEvent::Fused(Ok((mint, signature))) => {
+                    self.status = format!("Fused edition {} (tx {})", mint, signature);
+                    self.buying = false;
+                    self.refresh();
+                    self.sync(rl.get_time());
+                }
+                ShopEvent::Fused(Err(e)) => {
+                    self.status = format!("Fuse failed: {}", e);
+                    self.buying = false;
+                }
+                ShopEvent::Market(Ok(offers)) => {
+                    self.offers = offers;
+                    self.selected = self.selected.min((self.offers.len() + self.wallet.len()).saturating_sub(1));
+                    self.loading = false;
+                }
+                ShopEvent::Market(Err(e)) => {
+                    self.status = format!("Could not load the market: {}", e);
+                    self.loading = false;
+                }
+                ShopEvent::Traded(trade, Ok(signature)) => {
+                    self.status = match trade {
+                        Trade::Listed(mint) => {
+                            self.wallet.retain(|(owned, _)| *owned != mint);
+                            self.price_input.clear();
+                            format!("Listed {} (tx {})", mint, signature)
+                        }
+                        Trade::Cancelled(mint) => format!("{} is back from the market (tx {})", mint, signature),
+                        Trade::Bought(mint) => format!("Bought {} (tx {})", mint, signature),
+                    };
+                    self.buying = false;
+                    //The sync brings prints back to the wallet
+                    self.refresh_market();
+                    self.sync(rl.get_time());
+                }
+                ShopEvent::Traded(_, Err(e)) => {
+                    self.status = format!("Trade failed: {}", e);
+                    self.buying = false;
+                }
+                ShopEvent::Synced(Ok((events, recent))) => {
+                    self.syncing = false;
+                    self.recent = recent;
+
+                    //Prints that went to a wallet, bought or fused
+                    let prints: Vec<(Pubkey, Pubkey)> = events
+                        .iter()
+                        .filter_map(|event| match event {
+                            ProgramEvent::SkinPurchased(purchase) => Some((purchase.buyer, purchase.edition_mint)),
+                            ProgramEvent::SkinFused(fused) => Some((fused.player, fused.edition_mint)),
+                            _ => None,
+                        })
+                        .collect();
+                    //Prints that left the market for a wallet, bought or taken back
+                    let traded: Vec<(Pubkey, Pubkey)> = events
+                        .iter()
+                        .filter_map(|event| match event {
+                            ProgramEvent::SkinTraded(trade) => Some((trade.buyer, trade.mint)),
+                            ProgramEvent::SkinDelisted(delisted) => Some((delisted.seller, delisted.mint)),
+                            _ => None,
+                        })
+                        .collect();
+                    for (_, mint) in prints.iter().chain(&traded).filter(|(wallet, _)| *wallet == self.owner) {
+                        self.add_bought_skin(*mint);
+                    }
+                    //Someone else bought, the supplies moved
+                    if self.open && !self.loading && !self.market && !prints.is_empty() {
+                        self.refresh();
+                    }
+                    let listed = events.iter().any(|event| matches!(event, ProgramEvent::SkinListed(_)));
+                    if self.open && !self.loading && self.market && (listed || !traded.is_empty()) {
+                        self.refresh_market();
+                    }
+                }
+                ShopEvent::Synced(Err(e)) => {
+                    self.syncing = false;
+                    println!("Could not sync the program events: {}", e);
+                }
+                ShopEvent::NewSkin(mint, Ok(skin)) => {
+                    if !self.wallet.iter().any(|(owned, _)| *owned == mint) {
+                        self.wallet.push((mint, skin.name.clone()));
+                    }
+                    if self.owned.insert(skin.uri.clone()) {
+                        self.status = format!("{} is in the wallet now", skin.name);
+                    }
+                }
+                ShopEvent::NewSkin(_, Err(e)) => {
+                    println!("Could not load a bought skin: {}", e);
+                }
+            }
+        }
+
+        if rl.get_time() - self.last_sync >= SYNC_INTERVAL {
+            self.sync(rl.get_time());
+        }
+
+        if !self.open {
+            return;
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_M) && !self.buying {
+            self.market = !self.market;
+            self.selected = 0;
+            if self.market {
+                self.refresh_market();
+            } else if self.entries.is_empty() && !self.loading {
+                self.refresh();
+            }
+        }
+        if self.market {
+            self.update_market(rl);
+            return;
+        }
+        if self.entries.is_empty() {
+            return;
+        }
+
+        if rl.is_key_pressed(KeyboardKey::KEY_DOWN) {
+            self.selected = (self.selected + 1) % self.entries.len();
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_UP) {
+            self.selected = (self.selected + self.entries.len() - 1) % self.entries.len();
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) && !self.buying {
+            self.buy();
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_F) && !self.buying {
+            self.fuse();
+        }
+    }
+
+    pub fn draw(&self, d: &mut RaylibDrawHandle) {
+        if !self.open {
+            return;
+        }
+
+        let (width, height) = (d.get_screen_width(), d.get_screen_height());
+        d.draw_rectangle(40, 40, width - 80, height - 80, Color::new(0, 0, 0, 200));
+        if self.market {
+            self.draw_market(d);
+            d.draw_text(&self.status, 60, height - 70, 18, Color::LIGHTGRAY);
+            return;
+        }
+        d.draw_text("Shop  (up/down, enter to buy, f to fuse, m for the market, tab to close)", 60, 60, 20, Color::WHITE);
+
+        if self.loading && self.entries.is_empty() {
+            d.draw_text("Loading...", 60, 100, 20, Color::LIGHTGRAY);
+        } else if self.entries.is_empty() {
+            d.draw_text("Nothing for sale", 60, 100, 20, Color::LIGHTGRAY);
+        }
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let listing = &entry.listing;
+            let left = match (listing.remaining(), listing.sale_status()) {
+                (Some(0), _) => "sold out".to_string(),
+                (_, Some(status)) => status,
+                (Some(remaining), None) => format!("{}/{} left", remaining, listing.max_supply.unwrap_or(0)),
+                (None, None) => "unlimited".to_string(),
+            };
+            let left = if listing.early_access() { format!("{} (early access)", left) } else { left };
+            let owned = if self.owned.contains(&listing.skin.uri) { "  [owned]" } else { "" };
+
+            d.draw_text(
+                &format!(
+                    "{}/{}  {}  price: {}{}",
+                    listing.skin.name,
+                    listing.skin.symbol,
+                    left,
+                    listing.price_text(),
+                    owned
+                ),
+                60,
+                100 + i as i32 * 24,
+                18,
+                if i == self.selected { Color::GOLD } else { Color::WHITE },
+            );
+        }
+
+        if let Some(entry) = self.entries.get(self.selected) {
+            let x = width - 60 - PREVIEW_SIZE as i32;
+            d.draw_text(&entry.listing.royalties_text(), x, 110 + PREVIEW_SIZE as i32, 16, Color::LIGHTGRAY);
+            if let Some(recipe) = &entry.listing.recipe {
+                let text = format!("Fuse: {}", self.recipe_text(recipe));
+                d.draw_text(&text, x, 130 + PREVIEW_SIZE as i32, 16, Color::LIGHTGRAY);
+            }
+        }
+
+        if !self.recent.is_empty() {
+            let x = width - 60 - PREVIEW_SIZE as i32;
+            let y = 160 + PREVIEW_SIZE as i32;
+            d.draw_text("Recent purchases", x, y, 16, Color::WHITE);
+            for (i, (purchase, name)) in self.recent.iter().enumerate() {
+                let text = self.purchase_text(purchase, name.as_deref());
+                d.draw_text(&text, x, y + 22 + i as i32 * 20, 14, Color::LIGHTGRAY);
+            }
+        }
+
+        if let Some(preview) = self.entries.get(self.selected).and_then(|entry| entry.preview.as_ref()) {
+            let x = (width - 60) as f32 - PREVIEW_SIZE;
+            d.draw_texture_pro(
+                preview,
+                Rectangle::new(0.0, 0.0, preview.width as f32, preview.height as f32),
+                Rectangle::new(x, 100.0, PREVIEW_SIZE, PREVIEW_SIZE),
+                Vector2::zero(),
+                0.0,
+                Color::WHITE,
+            );
+        }
+
+        d.draw_text(&self.status, 60, height - 70, 18, Color::LIGHTGRAY);
+    }
+
+    fn draw_market(&self, d: &mut RaylibDrawHandle) {
+        let width = d.get_screen_width();
+        d.draw_text(
+            "Market  (up/down, enter to buy/take back/sell, type a price in SOL, m for the shop, tab to close)",
+            60,
+            60,
+            20,
+            Color::WHITE,
+        );
+
+        if self.loading && self.offers.is_empty() {
+            d.draw_text("Loading...", 60, 100, 20, Color::LIGHTGRAY);
+        } else if self.offers.is_empty() {
+            d.draw_text("Nobody is selling", 60, 100, 20, Color::LIGHTGRAY);
+        }
+
+        for (i, offer) in self.offers.iter().enumerate() {
+            let yours = if offer.seller == self.owner { "  [yours]" } else { "" };
+            d.draw_text(
+                &format!(
+                    "{}/{}  {}  by {}..{}",
+                    offer.skin.name,
+                    offer.skin.symbol,
+                    offer.price_text(),
+                    &offer.seller.to_string()[..4],
+                    yours
+                ),
+                60,
+                100 + i as i32 * 24,
+                18,
+                if i == self.selected { Color::GOLD } else { Color::WHITE },
+            );
+        }
+
+        //The wallet's prints under the offers, one row each
+        let y = 124 + self.offers.len().max(1) as i32 * 24;
+        d.draw_text("Sell from the wallet", 60, y, 18, Color::WHITE);
+        let price = if self.price_input.is_empty() { "?" } else { self.price_input.as_str() };
+        for (i, (_, name)) in self.wallet.iter().enumerate() {
+            let row = self.offers.len() + i;
+            d.draw_text(
+                &format!("{}  for {} SOL", name, price),
+                60,
+                y + 24 + i as i32 * 24,
+                18,
+                if row == self.selected { Color::GOLD } else { Color::WHITE },
+            );
+        }
+
+        if let Some(offer) = self.offers.get(self.selected) {
+            let x = width - 60 - PREVIEW_SIZE as i32;
+            d.draw_text(&offer.royalties_text(), x, 100, 16, Color::LIGHTGRAY);
+        }
+    }
+
+    fn unload_previews(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.entries
+            .drain(..)
+            .filter_map(|entry| entry.preview)
+            .for_each(|preview| unsafe { rl.unload_texture(thread, preview) });
+    }
+
+    //Textures must be cleared manually
+    pub fn clear(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.unload_previews(rl, thread);
+    }
+}
@@ -0,0 +1,502 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use skin_common::texture_validation::MAX_TEXTURE_BYTES;
+
+use crate::backend::{HttpBackend, ReqwestBackend};
+use crate::uri_resolver::{Source, UriResolver};
+
+pub const DEFAULT_MAX_BYTES: u64 = 512 * 1024 * 1024;
+//Nothing a skin pulls is bigger than one of its textures
+const MAX_RESPONSE_BYTES: usize = MAX_TEXTURE_BYTES;
+const NOT_MODIFIED: u16 = 304;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct EntryMeta {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    size: u64,
+    //unix seconds, used for the LRU
+    last_access: u64,
+}
+
+/**
+ * Content addressed disk cache for everything a skin pulls over http, uris go through the UriResolver
+ * An entry is keyed by the hash of (mint, metadata uri, texture url) so a new uri never hits a stale entry
+ * Layout:
+ *  <root>/objects/<hash>.bin   the bytes
+ *  <root>/objects/<hash>.json  validators (etag/last-modified) and last access for the eviction
+ *  <root>/wallets/<pubkey>.json  the skins of a wallet, enough to start without rpc
+ *  <root>/mints/<mint>  the metadata uri of a mint, for the remote players while offline
+ *  <root>/mints/<mint>.revision  the last revision of its skin seen on chain
+ */
+#[derive(Clone)]
+pub struct SkinCache {
+    root: PathBuf,
+    max_bytes: u64,
+    offline: bool,
+    http: Arc<dyn HttpBackend>,
+    resolver: UriResolver,
+}
+
+impl SkinCache {
+    pub fn new(root: PathBuf, max_bytes: u64, offline: bool, resolver: UriResolver) -> Self {
+        for dir in ["objects", "wallets", "mints"] {
+            let _ = fs::create_dir_all(root.join(dir));
+        }
+
+        Self {
+            root,
+            max_bytes,
+            offline,
+            http: Arc::new(ReqwestBackend::new()),
+            resolver,
+        }
+    }
+
+    //Fetch through something else than reqwest
+    pub fn with_http(mut self, http: Arc<dyn HttpBackend>) -> Self {
+        self.http = http;
+        self
+    }
+
+    // $XDG_CACHE_HOME/fps or the platform equivalent
+    pub fn default_dir() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from(".cache"))
+            .join("fps")
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    pub fn key(parts: &[&str]) -> String {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part.as_bytes());
+            //separator so ("ab", "c") and ("a", "bc") don't collide
+            hasher.update([0u8]);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    //Returns the cached bytes, revalidating them with the server when they carry validators
+    pub fn fetch(&self, key: &[&str], uri: &str) -> Result<Vec<u8>, String> {
+        let urls = match self.resolver.resolve(uri)? {
+            //Nothing to cache, the bytes are in the metadata
+            Source::Inline(bytes) => return Ok(bytes),
+            Source::Http(urls) => urls,
+        };
+
+        let hash = SkinCache::key(key);
+        let cached = self.read_entry(&hash);
+
+        if self.offline {
+            return cached
+                .map(|(_, bytes)| bytes)
+                .ok_or(format!("{} is not cached (offline)", uri));
+        }
+
+        if let Some((meta, bytes)) = &cached {
+            //Nothing to revalidate with, the key already pins the uri so trust it
+            if meta.etag.is_none() && meta.last_modified.is_none() {
+                self.touch(&hash, meta.clone());
+                return Ok(bytes.clone());
+            }
+        }
+
+        //Gateways in fallback order
+        let mut last_error = format!("Could not fetch {}", uri);
+        for url in &urls {
+            match self.fetch_from(&hash, url, cached.as_ref()) {
+                Ok(bytes) => return Ok(bytes),
+                Err(e) => last_error = e,
+            }
+        }
+
+        //Better a stale skin than none
+        cached.map(|(_, bytes)| bytes).ok_or(last_error)
+    }
+
+    fn fetch_from(&self, hash: &str, url: &str, cached: Option<&(EntryMeta, Vec<u8>)>) -> Result<Vec<u8>, String> {
+        let validators = cached.map(|(meta, _)| meta);
+        let response = self.http.get(
+            url,
+            validators.and_then(|meta| meta.etag.as_deref()),
+            validators.and_then(|meta| meta.last_modified.as_deref()),
+            MAX_RESPONSE_BYTES,
+        )?;
+
+        if response.status == NOT_MODIFIED
+            && let Some((meta, bytes)) = cached
+        {
+            self.touch(hash, meta.clone());
+            return Ok(bytes.clone());
+        }
+
+        if !(200..300).contains(&response.status) {
+            return Err(format!("{} returned {}", url, response.status));
+        }
+        if response.body.len() > MAX_RESPONSE_BYTES {
+            return Err(format!("{} is larger than {} bytes", url, MAX_RESPONSE_BYTES));
+        }
+
+        let meta = EntryMeta {
+            url: url.to_string(),
+            etag: response.etag,
+            last_modified: response.last_modified,
+            size: response.body.len() as u64,
+            last_access: now(),
+        };
+        if let Err(e) = self.write_entry(hash, &meta, &response.body) {
+            println!("Could not cache {}: {}", url, e);
+        }
+        self.evict();
+
+        Ok(response.body)
+    }
+
+    pub fn store_wallet(&self, wallet: &str, skins: &[(String, String)]) {
+        if let Ok(json) = serde_json::to_vec(skins) {
+            let _ = fs::write(self.root.join("wallets").join(format!("{}.json", wallet)), json);
+        }
+    }
+
+    //(mint, metadata uri) pairs of the last online start
+    pub fn load_wallet(&self, wallet: &str) -> Option<Vec<(String, String)>> {
+        let bytes = fs::read(self.root.join("wallets").join(format!("{}.json", wallet))).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    pub fn store_uri(&self, mint: &str, uri: &str) {
+        let _ = fs::write(self.root.join("mints").join(mint), uri);
+    }
+
+    pub fn load_uri(&self, mint: &str) -> Option<String> {
+        fs::read_to_string(self.root.join("mints").join(mint)).ok()
+    }
+
+    pub fn store_revision(&self, mint: &str, revision: u32) {
+        let _ = fs::write(self.root.join("mints").join(format!("{}.revision", mint)), revision.to_string());
+    }
+
+    //0 when never seen, like a skin never updated
+    pub fn load_revision(&self, mint: &str) -> u32 {
+        fs::read_to_string(self.root.join("mints").join(format!("{}.revision", mint)))
+            .ok()
+            .and_then(|revision| revision.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    //The cached bytes without going to the network or touching the entry
+    pub fn peek(&self, key: &[&str]) -> Option<Vec<u8>> {
+        self.read_entry(&SkinCache::key(key)).map(|(_, bytes)| bytes)
+    }
+
+    fn paths(&self, hash: &str) -> (PathBuf, PathBuf) {
+        let objects = self.root.join("objects");
+        (
+            objects.join(format!("{}.bin", hash)),
+            objects.join(format!("{}.json", hash)),
+        )
+    }
+
+    fn read_entry(&self, hash: &str) -> Option<(EntryMeta, Vec<u8>)> {
+        let (bin, json) = self.paths(hash);
+        let meta: EntryMeta = serde_json::from_slice(&fs::read(json).ok()?).ok()?;
+        let bytes = fs::read(bin).ok()?;
+
+        //Half written entry
+        if bytes.len() as u64 != meta.size {
+            return None;
+        }
+        Some((meta, bytes))
+    }
+
+    fn write_entry(&self, hash: &str, meta: &EntryMeta, bytes: &[u8]) -> std::io::Result<()> {
+        let (bin, json) = self.paths(hash);
+        fs::write(bin, bytes)?;
+        fs::write(json, serde_json::to_vec(meta)?)
+    }
+
+    fn touch(&self, hash: &str, mut meta: EntryMeta) {
+        meta.last_access = now();
+        let (_, json) = self.paths(hash);
+        if let Ok(bytes) = serde_json::to_vec(&meta) {
+            let _ = fs::write(json, bytes);
+        }
+    }
+
+    pub fn remove(&self, key: &[&str]) {
+        let (bin, json) = self.paths(&SkinCache::key(key));
+        let _ = fs::remove_file(bin);
+        let _ = fs::remove_file(json);
+    }
+
+    //Drop the least recently used entries until the cache fits in max_bytes
+    fn evict(&self) {
+        let mut entries: Vec<(String, EntryMeta)> = match fs::read_dir(self.root.join("objects")) {
+            Ok(dir) => dir
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .filter_map(|path| {
+                    let hash = path.file_stem()?.to_str()?.to_string();
+                    let meta = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+                    Some((hash, meta))
+                })
+                .collect(),
+            Err(_) => return,
+        };
+
+        let mut total: u64 = entries.iter().map(|(_, meta)| meta.size).sum();
+        if total <= self.max_bytes {
+            return;
+        }
+
+        entries.sort_by_key(|(_, meta)| meta.last_access);
+        for (hash, meta) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            let (bin, json) = self.paths(&hash);
+            let _ = fs::remove_file(bin);
+            let _ = fs::remove_file(json);
+            total -= meta.size;
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use crate::backend::HttpResponse;
+
+    use super::*;
+
+    //Serves its pages with an etag and answers 304 when it still matches
+    #[derive(Default)]
+    struct Server {
+        pages: Mutex<HashMap<String, (String, Vec<u8>)>>,
+        requests: Mutex<Vec<(String, Option<String>)>>,
+    }
+
+    impl Server {
+        fn put(&self, url: &str, etag: &str, body: &[u8]) {
+            self.pages.lock().unwrap().insert(url.to_string(), (etag.to_string(), body.to_vec()));
+        }
+
+        fn requests(&self) -> Vec<(String, Option<String>)> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl HttpBackend for Server {
+        fn get(
+            &self,
+            url: &str,
+            etag: Option<&str>,
+            _last_modified: Option<&str>,
+            _max_bytes: usize,
+        ) -> Result<HttpResponse, String> {
+            self.requests.lock().unwrap().push((url.to_string(), etag.map(|etag| etag.to_string())));
+
+            let pages = self.pages.lock().unwrap();
+            let Some((current, body)) = pages.get(url) else {
+                return Ok(HttpResponse { status: 404, etag: None, last_modified: None, body: vec![] });
+            };
+            if etag == Some(current.as_str()) {
+                return Ok(HttpResponse { status: 304, etag: None, last_modified: None, body: vec![] });
+            }
+
+            Ok(HttpResponse {
+                status: 200,
+                etag: Some(current.clone()),
+                last_modified: None,
+                body: body.clone(),
+            })
+        }
+    }
+
+    fn cache(dir: &std::path::Path, max_bytes: u64, server: &Arc<Server>) -> SkinCache {
+        let resolver = UriResolver::new(vec!["https://gateway".to_string()], vec![]);
+        SkinCache::new(dir.to_path_buf(), max_bytes, false, resolver).with_http(server.clone())
+    }
+
+    fn entry(url: &str, etag: Option<&str>, bytes: &[u8], last_access: u64) -> EntryMeta {
+        EntryMeta {
+            url: url.to_string(),
+            etag: etag.map(|etag| etag.to_string()),
+            last_modified: None,
+            size: bytes.len() as u64,
+            last_access,
+        }
+    }
+
+    fn cached(cache: &SkinCache, key: &[&str]) -> Option<Vec<u8>> {
+        cache.read_entry(&SkinCache::key(key)).map(|(_, bytes)| bytes)
+    }
+
+    #[test]
+    fn key_separates_its_parts() {
+        assert_eq!(SkinCache::key(&["mint", "uri"]), SkinCache::key(&["mint", "uri"]));
+        assert_ne!(SkinCache::key(&["ab", "c"]), SkinCache::key(&["a", "bc"]));
+        assert_eq!(SkinCache::key(&["mint"]).len(), 64);
+    }
+
+    #[test]
+    fn trusts_an_entry_without_validators() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(Server::default());
+        let cache = cache(dir.path(), DEFAULT_MAX_BYTES, &server);
+        let url = "https://host/skin.png";
+        let hash = SkinCache::key(&["mint", url]);
+        cache.write_entry(&hash, &entry(url, None, b"first", 1), b"first").unwrap();
+
+        assert_eq!(cache.fetch(&["mint", url], url).unwrap(), b"first");
+        assert!(server.requests().is_empty());
+        //and the access is recorded for the eviction
+        assert!(cache.read_entry(&hash).unwrap().0.last_access > 1);
+    }
+
+    #[test]
+    fn data_uris_are_not_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(Server::default());
+        let cache = cache(dir.path(), DEFAULT_MAX_BYTES, &server);
+
+        assert_eq!(cache.fetch(&["mint"], "data:,plain").unwrap(), b"plain");
+        assert!(cached(&cache, &["mint"]).is_none());
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn drops_half_written_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(dir.path(), DEFAULT_MAX_BYTES, &Arc::new(Server::default()));
+        let hash = SkinCache::key(&["mint"]);
+        cache.write_entry(&hash, &entry("https://host/skin.png", None, b"1234", 1), b"1234").unwrap();
+        fs::write(cache.paths(&hash).0, b"12").unwrap();
+
+        assert!(cache.read_entry(&hash).is_none());
+    }
+
+    #[test]
+    fn revalidates_with_the_etag() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(Server::default());
+        let cache = cache(dir.path(), DEFAULT_MAX_BYTES, &server);
+        let url = "https://host/skin.png";
+
+        server.put(url, "v1", b"first");
+        assert_eq!(cache.fetch(&["mint", url], url).unwrap(), b"first");
+        //Still v1, served from the disk after a 304
+        assert_eq!(cache.fetch(&["mint", url], url).unwrap(), b"first");
+
+        server.put(url, "v2", b"second");
+        assert_eq!(cache.fetch(&["mint", url], url).unwrap(), b"second");
+        assert_eq!(cached(&cache, &["mint", url]).unwrap(), b"second");
+
+        let etags: Vec<Option<String>> = server.requests().into_iter().map(|(_, etag)| etag).collect();
+        assert_eq!(etags, vec![None, Some("v1".to_string()), Some("v1".to_string())]);
+    }
+
+    #[test]
+    fn serves_the_stale_copy_when_the_server_is_gone() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(Server::default());
+        let cache = cache(dir.path(), DEFAULT_MAX_BYTES, &server);
+        let url = "https://host/skin.png";
+
+        server.put(url, "v1", b"first");
+        cache.fetch(&[url], url).unwrap();
+        server.pages.lock().unwrap().clear();
+
+        assert_eq!(cache.fetch(&[url], url).unwrap(), b"first");
+        assert!(cache.fetch(&["other"], "https://host/other.png").is_err());
+    }
+
+    #[test]
+    fn neither_returns_nor_caches_what_is_over_the_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(Server::default());
+        let cache = cache(dir.path(), DEFAULT_MAX_BYTES, &server);
+        let url = "https://host/huge.png";
+
+        server.put(url, "v1", &vec![0; MAX_RESPONSE_BYTES + 1]);
+        let error = cache.fetch(&[url], url).unwrap_err();
+        assert_eq!(error, format!("{} is larger than {} bytes", url, MAX_RESPONSE_BYTES));
+        assert!(cached(&cache, &[url]).is_none());
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(Server::default());
+        //Room for two entries of 4 bytes
+        let cache = cache(dir.path(), 8, &server);
+
+        for (url, last_access) in [("https://host/a", 1), ("https://host/b", 2)] {
+            server.put(url, url, b"1234");
+            cache.fetch(&[url], url).unwrap();
+            //The clock is in seconds, set the order by hand
+            let hash = SkinCache::key(&[url]);
+            let (meta, _) = cache.read_entry(&hash).unwrap();
+            cache.write_entry(&hash, &EntryMeta { last_access, ..meta }, b"1234").unwrap();
+        }
+
+        server.put("https://host/c", "c", b"1234");
+        cache.fetch(&["https://host/c"], "https://host/c").unwrap();
+
+        assert!(cached(&cache, &["https://host/a"]).is_none());
+        assert!(cached(&cache, &["https://host/b"]).is_some());
+        assert!(cached(&cache, &["https://host/c"]).is_some());
+    }
+
+    #[test]
+    fn offline_only_reads_the_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = Arc::new(Server::default());
+        let url = "https://host/skin.png";
+        server.put(url, "v1", b"first");
+        cache(dir.path(), DEFAULT_MAX_BYTES, &server).fetch(&[url], url).unwrap();
+
+        let resolver = UriResolver::new(vec![], vec![]);
+        let offline = SkinCache::new(dir.path().to_path_buf(), DEFAULT_MAX_BYTES, true, resolver).with_http(server.clone());
+        assert_eq!(offline.fetch(&[url], url).unwrap(), b"first");
+        assert!(offline.fetch(&["other"], "https://host/other.png").is_err());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn wallets_and_uris_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = cache(dir.path(), DEFAULT_MAX_BYTES, &Arc::new(Server::default()));
+        let skins = vec![("mint".to_string(), "https://host/skin.json".to_string())];
+        cache.store_wallet("wallet", &skins);
+        cache.store_uri("mint", "https://host/skin.json");
+
+        assert_eq!(cache.load_wallet("wallet"), Some(skins));
+        assert_eq!(cache.load_wallet("other"), None);
+        assert_eq!(cache.load_uri("mint").as_deref(), Some("https://host/skin.json"));
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::{HashMap, VecDeque};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use raylib::color::Color;
+use raylib::consts::TextureFilter;
+use raylib::texture::{Image, RaylibTexture2D, WeakTexture2D};
+use raylib::{RaylibHandle, RaylibThread};
+use skin_common::skin_schema::SkinStyle;
+use skin_common::texture_validation::{sanitize, DecodedTexture};
+
+use crate::sol_client::SolanaClient;
+
+pub const DEFAULT_WORKERS: usize = 4;
+//Each upload is a full texture plus its mipmaps, a couple per frame keeps the frame time flat
+pub const DEFAULT_UPLOADS_PER_FRAME: usize = 2;
+
+//Fetch failures are retried with an exponential backoff, a skin that doesn't decode is never retried
+pub const MAX_ATTEMPTS: u32 = 5;
+const BASE_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+struct DecodedSkin {
+    textures: HashMap<String, DecodedTexture>,
+    style: SkinStyle,
+}
+
+//A skin on the gpu, what the gun is drawn with
+#[derive(Clone)]
+pub struct LoadedSkin {
+    pub textures: HashMap<String, WeakTexture2D>,
+    pub style: SkinStyle,
+}
+
+#[derive(Debug, Clone)]
+pub enum SkinLoadState {
+    //Queued, fetching, decoding or uploading
+    Pending { attempt: u32 },
+    //Handed over to the TextureManager
+    Loaded,
+    //Failed, queued again once the time comes
+    RetryAt { reason: String, attempts: u32, at: Instant },
+    //Gave up on it
+    Failed { reason: String, attempts: u32 },
+}
+
+impl SkinLoadState {
+    //Shown above the players whose skin is not there
+    pub fn describe(&self) -> Option<String> {
+        match self {
+            SkinLoadState::RetryAt { reason, at, .. } => Some(format!(
+                "skin retry in {}s: {}",
+                at.saturating_duration_since(Instant::now()).as_secs(),
+                reason
+            )),
+            SkinLoadState::Failed { reason, .. } => Some(format!("skin failed: {}", reason)),
+            _ => None,
+        }
+    }
+}
+
+//Whether another attempt could change anything
+enum LoadError {
+    Transient(String),
+    Permanent(String),
+}
+
+//A skin that is decoded and goes to the gpu a few textures per frame
+struct PendingUpload {
+    mint: String,
+    decoded: Vec<(String, DecodedTexture)>,
+    uploaded: HashMap<String, WeakTexture2D>,
+    style: SkinStyle,
+}
+
+/**
+ * Loads the remote skins without touching the frame
+ * A fixed pool of workers fetches and decodes to raw RGBA, the main thread only uploads
+ * This needs to be split as OpenGL only allows texture loading from the thread its context was created on
+ */
+pub struct SkinLoader {
+    jobs: Sender<String>,
+    results: Receiver<(String, Result<DecodedSkin, LoadError>)>,
+    //Every mint that was ever requested, also avoids duplicate concurrent fetches
+    states: HashMap<String, SkinLoadState>,
+    uploads: VecDeque<PendingUpload>,
+    //Uploaded and waiting to be taken by the TextureManager
+    finished: HashMap<String, LoadedSkin>,
+    uploads_per_frame: usize,
+}
+
+impl SkinLoader {
+    pub fn new(sol_client: SolanaClient, workers: usize, uploads_per_frame: usize) -> Self {
+        let (jobs, job_queue) = channel::<String>();
+        let (result_sender, results) = channel();
+        let job_queue = Arc::new(Mutex::new(job_queue));
+
+        for _ in 0..workers.max(1) {
+            let job_queue = job_queue.clone();
+            let result_sender = result_sender.clone();
+            let sol_client = sol_client.clone();
+
+            thread::spawn(move || loop {
+                //The guard is dropped at the end of the statement, the other workers can wait meanwhile
+                let mint = match job_queue.lock().unwrap().recv() {
+                    Ok(mint) => mint,
+                    //The loader is gone
+                    Err(_) => break,
+                };
+
+                //A panic must not take the worker down with it
+                let result = catch_unwind(AssertUnwindSafe(|| {
+                    let (skin_md, imgs) = sol_client
+                        .fetch_skin(&mint)
+                        .map_err(LoadError::Transient)?;
+                    let textures = SkinLoader::decode(&imgs).map_err(LoadError::Permanent)?;
+                    Ok(DecodedSkin { textures, style: skin_md.style() })
+                }))
+                .unwrap_or_else(|_| Err(LoadError::Transient("Loader panicked".to_string())));
+
+                if result_sender.send((mint, result)).is_err() {
+                    break;
+                }
+            });
+        }
+
+        Self {
+            jobs,
+            results,
+            states: HashMap::new(),
+            uploads: VecDeque::new(),
+            finished: HashMap::new(),
+            uploads_per_frame: uploads_per_frame.max(1),
+        }
+    }
+
+    //All or nothing, a skin with one bad texture is not shown at all
+    //The packed orm comes out as the ao, r and m slots the shader reads
+    fn decode(imgs: &HashMap<String, Vec<u8>>) -> Result<HashMap<String, DecodedTexture>, String> {
+        let mut decoded = HashMap::new();
+
+        for (key, bytes) in imgs {
+            let texture = sanitize(bytes).map_err(|e| format!("Texture {}: {}", key, e))?;
+
+            if key == "orm" {
+                for (channel, slot) in ["ao", "r", "m"].iter().enumerate() {
+                    decoded.insert(slot.to_string(), texture.channel(channel));
+                }
+            } else {
+                decoded.insert(key.clone(), texture);
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    //The textures are handed over once, the TextureManager owns them from there
+    //None while loading, the request is queued on the first call and again when a retry is due
+    pub fn take(&mut self, mint: &String) -> Option<LoadedSkin> {
+        if let Some(skin) = self.finished.remove(mint) {
+            self.states.insert(mint.clone(), SkinLoadState::Loaded);
+            return Some(skin);
+        }
+
+        //Players on the default skin send no mint
+        if mint.is_empty() {
+            return None;
+        }
+
+        let attempt = match self.states.get(mint) {
+            None => 1,
+            Some(SkinLoadState::Pending { .. }) | Some(SkinLoadState::Failed { .. }) => return None,
+            Some(SkinLoadState::RetryAt { attempts, at, .. }) => {
+                if Instant::now() < *at {
+                    return None;
+                }
+                attempts + 1
+            }
+            //Evicted since, load it again
+            Some(SkinLoadState::Loaded) => 1,
+        };
+
+        if self.jobs.send(mint.clone()).is_ok() {
+            self.states.insert(mint.clone(), SkinLoadState::Pending { attempt });
+        }
+        None
+    }
+
+    //Why a player's skin is not shown
+    pub fn state(&self, mint: &str) -> Option<&SkinLoadState> {
+        self.states.get(mint)
+    }
+
+    fn fail(&mut self, mint: String, error: LoadError) {
+        let attempts = match self.states.get(&mint) {
+            Some(SkinLoadState::Pending { attempt }) => *attempt,
+            _ => 1,
+        };
+
+        let state = match error {
+            LoadError::Transient(reason) if attempts < MAX_ATTEMPTS => {
+                let backoff = (BASE_BACKOFF * 2u32.pow(attempts - 1)).min(MAX_BACKOFF);
+                SkinLoadState::RetryAt { reason, attempts, at: Instant::now() + backoff }
+            }
+            LoadError::Transient(reason) | LoadError::Permanent(reason) => {
+                SkinLoadState::Failed { reason, attempts }
+            }
+        };
+
+        println!("Skin {}: {:?}", mint, state);
+        self.states.insert(mint, state);
+    }
+
+    //Call once per frame, collects the workers' results and uploads at most uploads_per_frame textures
+    pub fn pump(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        while let Ok((mint, result)) = self.results.try_recv() {
+            match result {
+                Ok(decoded) => self.uploads.push_back(PendingUpload {
+                    mint,
+                    decoded: decoded.textures.into_iter().collect(),
+                    uploaded: HashMap::new(),
+                    style: decoded.style,
+                }),
+                //The player keeps the default skin meanwhile
+                Err(e) => self.fail(mint, e),
+            }
+        }
+
+        let mut budget = self.uploads_per_frame;
+        while budget > 0 {
+            let Some(pending) = self.uploads.front_mut() else {
+                break;
+            };
+
+            if let Some((key, decoded)) = pending.decoded.pop() {
+                match upload_texture(rl, thread, &decoded) {
+                    Ok(texture) => {
+                        pending.uploaded.insert(key, texture);
+                    }
+                    Err(e) => {
+                        let pending = self.uploads.pop_front().unwrap();
+                        unload_textures(rl, thread, &pending.uploaded);
+                        self.fail(pending.mint, LoadError::Permanent(format!("Texture {}: {}", key, e)));
+                    }
+                }
+                budget -= 1;
+                continue;
+            }
+
+            let pending = self.uploads.pop_front().unwrap();
+            self.finished.insert(
+                pending.mint,
+                LoadedSkin {
+                    textures: pending.uploaded,
+                    style: pending.style,
+                },
+            );
+        }
+    }
+
+    //Textures must be cleared manually
+    pub fn clear(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.finished
+            .drain()
+            .for_each(|(_, skin)| unload_textures(rl, thread, &skin.textures));
+        self.uploads
+            .drain(..)
+            .for_each(|pending| unload_textures(rl, thread, &pending.uploaded));
+    }
+}
+
+//-! Weak textures need to be unloaded manually
+//Blocking version for the skin of the local player at start up
+pub fn load_textures(rl: &mut RaylibHandle, thread: &RaylibThread, imgs: HashMap<String, Vec<u8>>, style: SkinStyle) -> Result<LoadedSkin, String>
+{
+    let mut map: HashMap<String, WeakTexture2D> = HashMap::new();
+
+    for (key, decoded) in SkinLoader::decode(&imgs)? {
+        match upload_texture(rl, thread, &decoded) {
+            Ok(texture) => {
+                map.insert(key, texture);
+            }
+            Err(e) => {
+                unload_textures(rl, thread, &map);
+                return Err(format!("Texture {}: {}", key, e));
+            }
+        }
+    }
+
+    Ok(LoadedSkin { textures: map, style })
+}
+
+pub fn upload_texture(rl: &mut RaylibHandle, thread: &RaylibThread, decoded: &DecodedTexture) -> Result<WeakTexture2D, String>
+{
+    //Let raylib own the pixel buffer, then fill it
+    let img = Image::gen_image_color(decoded.width as i32, decoded.height as i32, Color::BLANK);
+    unsafe {
+        std::ptr::copy_nonoverlapping(decoded.rgba.as_ptr(), img.data as *mut u8, decoded.rgba.len());
+    }
+
+    let mut texture = unsafe {
+        rl.load_texture_from_image(thread, &img)
+            .map_err(|e| e.to_string())?
+            .make_weak()
+    };
+
+    //Sides are powers of two after sanitize
+    texture.gen_texture_mipmaps();
+    texture.set_texture_filter(thread, TextureFilter::TEXTURE_FILTER_TRILINEAR);
+
+    Ok(texture)
+}
+
+fn unload_textures(rl: &mut RaylibHandle, thread: &RaylibThread, textures: &HashMap<String, WeakTexture2D>) {
+    textures.values().for_each(|texture| {
+        unsafe { rl.unload_texture(thread, texture.to_owned()) };
+    });
+}
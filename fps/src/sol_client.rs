@@ -1,267 +1,771 @@
 use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::Arc;
-use std::thread;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use anchor_client::anchor_lang::AnchorDeserialize;
-use anchor_client::solana_client::rpc_client::RpcClient;
-use anchor_client::solana_client::rpc_request::TokenAccountsFilter;
 use anchor_client::solana_sdk::pubkey::Pubkey;
-use dashmap::DashSet;
-use dashmap::DashMap;
-use mpl_token_metadata::accounts::Metadata;
+use anchor_client::solana_sdk::signature::{Keypair, Signature, Signer};
+use anchor_client::solana_sdk::transaction::Transaction;
+use mpl_token_metadata::accounts::{Edition, MasterEdition, Metadata};
+use mpl_token_metadata::types::Key;
+use skin_common::merkle;
+use skin_common::nft_program::{
+    self, BurnIngredient, BuyNft, Cancel, Fuse, FuseProgress, Ingredient, List, ProgramEvent, PurchaseListing, Recipe, SkinListing, TradeListing,
+};
+use skin_common::skin_schema::{SkinMetadata, WEAPONS};
+use skin_common::texture_validation::inspect;
+
+use crate::backend::{ChainBackend, RpcBackend};
+use crate::indexer::EventStore;
+use crate::skin_cache::SkinCache;
+use crate::utils::sha256_hex;
+
+//Offset of the first creator's address in a metadata account, name symbol and uri are padded to their max length
+//key(1) update_authority(32) mint(32) name(4+32) symbol(4+10) uri(4+200) seller_fee(2) creators option(1) vec len(4)
+const FIRST_CREATOR_OFFSET: usize = 326;
+
+//A skin on sale, a master edition of our program
+pub struct Listing {
+    pub master_mint: Pubkey,
+    pub skin: SkinMetadata,
+    pub max_supply: Option<u64>,
+    //Preview image bytes, checked by inspect
+    pub preview: Option<Vec<u8>>,
+    //Price and where it goes, from the program's listing account
+    pub price: SkinListing,
+    //Of the payment mint, 9 for SOL
+    pub decimals: u8,
+    //Royalties on resales and who gets them, the program's authority left out
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<(Pubkey, u8)>,
+    //What a fuse burns for a print, None when the skin is only sold
+    pub recipe: Option<Recipe>,
+}
+
+impl Listing {
+    pub fn remaining(&self) -> Option<u64> {
+        self.price.remaining(self.max_supply)
+    }
+
+    //"free", "0.5 SOL" or the amount and the first characters of the token mint
+    pub fn price_text(&self) -> String {
+        self.amount_text(self.price.price)
+    }
+
+    //An amount in the currency of the listing, like what a purchase paid
+    pub fn amount_text(&self, amount: u64) -> String {
+        if amount == 0 {
+            return "free".to_string();
+        }
+
+        let amount = amount as f64 / 10f64.powi(self.decimals as i32);
+        match self.price.payment_mint {
+            Some(mint) => format!("{} {}..", amount, &mint.to_string()[..4]),
+            None => format!("{} SOL", amount),
+        }
+    }
 
-use raylib::texture::{Image, WeakTexture2D};
-use raylib::{RaylibHandle, RaylibThread};
-use solana_account_decoder::UiAccountData;
+    //Why it can't be bought right now, None when it can
+    pub fn sale_status(&self) -> Option<String> {
+        sale_status(&self.price, unix_now())
+    }
 
-use reqwest::blocking::get;
+    //Only the allowlist can buy for now
+    pub fn early_access(&self) -> bool {
+        self.price.allowlist_root.is_some() && unix_now() < self.price.public_start
+    }
 
-use serde::Deserialize;
+    pub fn royalties_text(&self) -> String {
+        royalties_text(self.seller_fee_basis_points, &self.creators)
+    }
+}
 
-#[derive(Deserialize, Debug)]
-pub struct SkinMetadata {
-    pub name:   String,
-    pub symbol: String,
-    pub identifier: String,
-    pub textures: TextureField,
+//A print a player sells on the market, its token is in the program's escrow
+pub struct MarketListing {
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    //Lamports, royalties included
+    pub price: u64,
+    pub skin: SkinMetadata,
+    //Copied from the master, the creators with a share get the royalties
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<(Pubkey, u8)>,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct TextureField {
-    pub a : String,
-    pub r : String,
-    pub n : String,
-    pub m : String,
-    pub ao : String,
+impl MarketListing {
+    pub fn price_text(&self) -> String {
+        format!("{} SOL", self.price as f64 / 1e9)
+    }
+
+    pub fn royalties_text(&self) -> String {
+        royalties_text(self.seller_fee_basis_points, &self.creators)
+    }
+}
+
+//"5% royalties: 7xKX.. 70%, 9aBc.. 30%"
+fn royalties_text(seller_fee_basis_points: u16, creators: &[(Pubkey, u8)]) -> String {
+    if seller_fee_basis_points == 0 {
+        return "no royalties".to_string();
+    }
+
+    let split: Vec<String> = creators
+        .iter()
+        .map(|(address, share)| format!("{}.. {}%", &address.to_string()[..4], share))
+        .collect();
+    format!("{}% royalties: {}", seller_fee_basis_points as f32 / 100.0, split.join(", "))
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+//Paused, not started or over, early access only counts as a status when it is not the public sale
+fn sale_status(listing: &SkinListing, now: i64) -> Option<String> {
+    if listing.paused {
+        return Some("paused".to_string());
+    }
+    if now < listing.sale_start {
+        return Some(format!("starts in {}", duration_text(listing.sale_start - now)));
+    }
+    if listing.sale_end != 0 && now >= listing.sale_end {
+        return Some("sale over".to_string());
+    }
+    None
+}
+
+//"3d 4h", "5h 12m" or "40m"
+fn duration_text(seconds: i64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes.max(1)),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
 }
 
 /**
- * Arc on fields to copy the object in the async tasks
- * Dashmap for concurrent access
+ * Arc on fields to copy the object in the loader workers
+ * Everything here blocks, the gpu side is in the SkinLoader
  */
 #[derive(Clone)]
 pub struct SolanaClient {
-    sol_client: Arc<RpcClient>,
-    token_program_id: Pubkey,
-    // to avoid duplicate concurrent fetches
-    in_flight: Arc<DashSet<String>>, 
-    //Just a hack to cache the texture requests that are on each frame.. yeah 
-    //Mint -> Map(texture_identifier -> texture)
-    skin_map: Arc<DashMap<String, Arc<HashMap<String, WeakTexture2D>>>>,
-    // skin_imgs_map: Arc<DashMap<String, Arc<HashMap<String, Image>>>>
-    raw_bytes: Arc<DashMap<String, HashMap<String, Vec<u8>>>>
+    chain: Arc<dyn ChainBackend>,
+    cache: SkinCache,
+    //Master edition address -> master mint of the program's skins, to follow prints to their master
+    masters: Arc<Mutex<HashMap<Pubkey, Pubkey>>>,
 }
 
 impl SolanaClient {
-    pub fn new() -> Self {
+    pub fn new(cache: SkinCache) -> Self {
         let rpc_url = "https://api.devnet.solana.com"; // Changed to Devnet
-        let sol_client = RpcClient::new(rpc_url.to_string());
 
-        let token_program_id = Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap();
+        SolanaClient::with_backend(Arc::new(RpcBackend::new(rpc_url)), cache)
+    }
 
+    pub fn with_backend(chain: Arc<dyn ChainBackend>, cache: SkinCache) -> Self {
         Self {
-            sol_client: Arc::new(sol_client),
-            token_program_id,
-            skin_map: Arc::new(DashMap::new()),
-            raw_bytes: Arc::new(DashMap::new()),
-            in_flight: Arc::new(DashSet::new()),
+            chain,
+            cache,
+            masters: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    pub fn is_offline(&self) -> bool {
+        self.cache.is_offline()
+    }
+
     //General blocking fetch at the initialization
     pub fn fetch_skins(&self, public_key: Pubkey) -> Result<Vec<(Pubkey, SkinMetadata)>, String> {
-    
-        let token_accounts = self.sol_client
-            .get_token_accounts_by_owner(
-                &public_key,
-                TokenAccountsFilter::ProgramId(self.token_program_id),
-            )
-            .unwrap();
-    
-        let skins: Vec<(Pubkey, SkinMetadata)> = token_accounts
-            .iter()
-            //Get mints from ATAs
-            .map(|account| match &account.account.data {
-                UiAccountData::Json(parsed_account) => {
-                    Some(
-                        parsed_account.parsed["info"]["mint"].as_str().unwrap()
-                    )
-                }
-                _ => {
-                    None
-                }
-            })
-            //Filter out None values
-            .filter_map(|mint| mint)
+
+        //Start from what the last online run has seen
+        if self.cache.is_offline() {
+            let nfts = self.cache
+                .load_wallet(&public_key.to_string())
+                .ok_or("Wallet is not cached, run once online")?;
+
+            let skins = nfts
+                .iter()
+                .filter_map(|(mint, uri)| {
+                    let skin_data = self.fetch_metadata(mint, uri).ok()?;
+                    Some((Pubkey::from_str(mint).ok()?, skin_data))
+                })
+                .collect();
+
+            return Ok(skins);
+        }
+
+        //Anything that is not a skin, or is broken, is skipped
+        let skins: Vec<(Pubkey, SkinMetadata)> = self.chain
+            .token_mints_by_owner(&public_key)?
+            .into_iter()
             //Filter the nft mints
-            .filter_map(|mint|{
-                let pk = Pubkey::from_str(mint).unwrap();
-                let decimals = self.sol_client.get_token_supply(&pk).unwrap().decimals;
-                if decimals == 0 {
-                    return Some(pk);
-                }
-                None
-            })
+            .filter(|mint| self.chain.mint_decimals(mint) == Ok(0))
             //Fetch metadata uri's
-            .map(|mint| {
-                //let mint: solana_program::pubkey::Pubkey = solana_program::pubkey::Pubkey::new_from_array(mint.to_bytes());
-                
-                let metadata_pda = Pubkey::find_program_address(
-                    &[
-                        b"metadata",
-                        mpl_token_metadata::ID.as_ref(),
-                        mint.as_ref(),
-                    ],
-                    &mpl_token_metadata::ID,
-                ).0;
-    
-                let data = self.sol_client.get_account_data(&metadata_pda).unwrap();
-                let metadata: Metadata = Metadata::deserialize(&mut data.as_slice()).unwrap();
-                
+            .filter_map(|mint| {
+                let uri = self.skin_uri(&mint).ok()?;
+
                 //probably is a collection
-                if metadata.uri.trim_matches('\0').is_empty()
+                if uri.is_empty()
                 {
                     return None;
                 }
 
-                Some((mint, metadata.uri))
-    
+                Some((mint, uri))
             })
-            //Filter out None values
-            .filter_map(|nft| nft)
-            .filter_map(|nft| {
-                let uri = nft.1.as_str();
-                
-                let response = get(uri).unwrap();
-                if !response.status().is_success()
-                {
-                    return None;
+            .filter_map(|(mint, uri)| match self.fetch_metadata(&mint.to_string(), &uri) {
+                Ok(skin_data) if WEAPONS.contains(&skin_data.weapon()) => Some((mint, skin_data)),
+                Ok(skin_data) => {
+                    println!("Skipping {}: made for the {}", mint, skin_data.weapon());
+                    None
+                }
+                Err(e) => {
+                    println!("Skipping {}: {}", mint, e);
+                    None
                 }
-
-                return match response.json::<SkinMetadata>() {
-                    Ok(skin_data) => {
-                        if skin_data.identifier == "fps+bq"
-                        {
-                            Some((nft.0, skin_data))
-                        }
-                        else {
-                            None
-                        }
-                    },
-                    _ => {None}
-                };
-
             })
             .collect();
+
+        let index: Vec<(String, String)> = skins
+            .iter()
+            .map(|(mint, skin_data)| (mint.to_string(), skin_data.uri.clone()))
+            .collect();
+        self.cache.store_wallet(&public_key.to_string(), &index);
     
         Ok(skins)
     }
 
-    //Fetches at runtime so should not block, the current implementation is for invoking this function at each frame
-    pub fn fetch_skin(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, mint: &String) -> Option<HashMap<String, WeakTexture2D>> {
+    /**
+     * The uri a mint's skin loads from
+     * Prints of the program follow their master, whose uri update_skin changes, any other nft keeps its own
+     * A revision newer than the last one seen drops what the disk cache has of the skin
+     */
+    fn skin_uri(&self, mint: &Pubkey) -> Result<String, String> {
+        let metadata = self.metadata(mint)?;
+        let uri = metadata.uri.trim_matches('\0').to_string();
+
+        //Prints copy the creators of their master, the program's authority comes first
+        let authority = nft_program::pda::mint_authority();
+        if metadata.creators.as_ref().and_then(|creators| creators.first()).map(|creator| creator.address) != Some(authority) {
+            return Ok(uri);
+        }
+        let Some(master) = self.master_mint(mint) else {
+            return Ok(uri);
+        };
+        let Ok(listing) = self.chain.account_data(&nft_program::pda::listing(&master)).and_then(|data| SkinListing::parse(&data)) else {
+            return Ok(uri);
+        };
+
+        let uri = if master == *mint { uri } else { self.metadata_uri(&master)? };
+        self.invalidate(&mint.to_string(), listing.revision);
+
+        Ok(uri)
+    }
+
+    //The master mint of one of the program's skins, itself for a master edition
+    fn master_mint(&self, mint: &Pubkey) -> Option<Pubkey> {
+        let data = self.chain.account_data(&nft_program::pda::edition(mint)).ok()?;
 
-        let mint = Pubkey::from_str(mint).unwrap();
-        let key = mint.to_string();
-        //Return if it is available
-        if let Some(entry) = self.skin_map.get(&key) {
-            return Some((**entry).clone());
+        match Key::deserialize(&mut data.as_slice()).ok()? {
+            Key::MasterEditionV2 => return Some(*mint),
+            Key::EditionV1 => {}
+            _ => return None,
         }
 
-        // if its not available check if it has fullfiled the image bytes load
-        // This needs to be done in the main thread as OpenGL only allows texture loading from the thread it's context was created
-        // The Image objects are wrapping a C pointer so can't pass them in a closure, game dev in rust is a joke
-        if let Some(entry) = self.raw_bytes.get(&key) {
-            let textures = SolanaClient::fetch_textures(rl, thread, (*entry).clone()).unwrap();
-            self.skin_map.insert(key.clone(), Arc::new(textures));
-            return Some((**self.skin_map.get(&key).unwrap()).clone());
+        let parent = Edition::deserialize(&mut data.as_slice()).ok()?.parent;
+        if let Some(master) = self.masters.lock().unwrap().get(&parent) {
+            return Some(*master);
         }
 
-        //if there is no loaded images for it spawn a thread to fetch it
-        if self.in_flight.insert(key.clone()) {
-            let loader = self.clone();
-            
-            thread::spawn(move || {
-                let metadata_pda = Pubkey::find_program_address(
-                    &[
-                        b"metadata",
-                        mpl_token_metadata::ID.as_ref(),
-                        mint.as_ref(),
-                    ],
-                    &mpl_token_metadata::ID,
-                ).0;
-        
-                let data = loader.sol_client.get_account_data(&metadata_pda).unwrap();
-                let metadata: Metadata = Metadata::deserialize(&mut data.as_slice()).unwrap();
-        
-                let uri = metadata.uri;
-                let response = get(uri).unwrap();
-                if response.status().is_success()
-                {
-                    let skin_md = response.json::<SkinMetadata>().unwrap();
-                    let imgs = SolanaClient::fetch_images_bytes(&skin_md.textures).unwrap();
-                    loader.raw_bytes.insert(key.clone(), imgs);
+        //A master we haven't seen yet, the listings know all of them
+        let listings = self.chain.program_accounts(&nft_program::PROGRAM_ID, 0, &nft_program::SKIN_LISTING).ok()?;
+        let mut masters = self.masters.lock().unwrap();
+        for (_, data) in listings {
+            if let Ok(listing) = SkinListing::parse(&data) {
+                masters.insert(nft_program::pda::edition(&listing.master_mint), listing.master_mint);
+            }
+        }
+
+        masters.get(&parent).copied()
+    }
+
+    //Removes the cached json, textures and preview of a mint's skin when the revision moved past the stored one
+    fn invalidate(&self, mint: &str, revision: u32) {
+        if self.cache.load_revision(mint) >= revision {
+            return;
+        }
+
+        if let Some(uri) = self.cache.load_uri(mint) {
+            let old = self.cache.peek(&[mint, &uri]).and_then(|bytes| serde_json::from_slice::<SkinMetadata>(&bytes).ok());
+            if let Some(old) = old {
+                for (_, url) in old.textures.slots() {
+                    self.cache.remove(&[mint, &uri, url]);
                 }
+                if let Some(image) = &old.image {
+                    self.cache.remove(&[mint, &uri, image]);
+                }
+            }
+            self.cache.remove(&[mint, &uri]);
+        }
+
+        self.cache.store_revision(mint, revision);
+    }
+
+    //The Metaplex metadata account of a mint
+    fn metadata(&self, mint: &Pubkey) -> Result<Metadata, String> {
+        let metadata_pda = Pubkey::find_program_address(
+            &[
+                b"metadata",
+                mpl_token_metadata::ID.as_ref(),
+                mint.as_ref(),
+            ],
+            &mpl_token_metadata::ID,
+        ).0;
+
+        let data = self.chain.account_data(&metadata_pda)?;
+        Metadata::deserialize(&mut data.as_slice()).map_err(|e| e.to_string())
+    }
 
-                // Remove from in-flight
-                loader.in_flight.remove(&key);
-            });
+    //The uri in the Metaplex metadata account of a mint
+    fn metadata_uri(&self, mint: &Pubkey) -> Result<String, String> {
+        let metadata = self.metadata(mint)?;
+
+        Ok(metadata.uri.trim_matches('\0').to_string())
+    }
+
+    //New events of the program since the last sync of the store
+    pub fn sync_events(&self, store: &mut EventStore) -> Result<Vec<ProgramEvent>, String> {
+        if self.cache.is_offline() {
+            return Err("Offline".to_string());
+        }
+        store.sync(self.chain.as_ref())
+    }
+
+    //A print the indexer saw the wallet buy, added to the cached wallet so the next offline start has it too
+    pub fn add_bought_skin(&self, owner: &Pubkey, mint: &Pubkey) -> Result<SkinMetadata, String> {
+        let uri = self.skin_uri(mint)?;
+        let skin_data = self.fetch_metadata(&mint.to_string(), &uri)?;
+
+        let mut index = self.cache.load_wallet(&owner.to_string()).unwrap_or_default();
+        if !index.iter().any(|(cached, _)| *cached == mint.to_string()) {
+            index.push((mint.to_string(), uri));
+            self.cache.store_wallet(&owner.to_string(), &index);
         }
 
-        None
-       
+        Ok(skin_data)
+    }
+
+    //Skin json of a mint, through the disk cache
+    pub fn fetch_metadata(&self, mint: &str, uri: &str) -> Result<SkinMetadata, String> {
+        let bytes = self.cache.fetch(&[mint, uri], uri)?;
+        let mut skin_data = SkinMetadata::parse(&bytes)?;
+        skin_data.uri = uri.to_string();
+        self.cache.store_uri(mint, uri);
+
+        Ok(skin_data)
     }
 
+    //Json and texture bytes of any skin by its mint, run by the SkinLoader workers
+    pub fn fetch_skin(&self, mint: &str) -> Result<(SkinMetadata, HashMap<String, Vec<u8>>), String> {
+        let pk = Pubkey::from_str(mint).map_err(|e| e.to_string())?;
 
-    pub fn fetch_images_bytes(tf: &TextureField) -> Result<HashMap<String, Vec<u8>>, reqwest::Error>
+        //Offline there is no rpc, the uri is whatever we have seen last time
+        let uri = match self.cache.load_uri(mint) {
+            Some(uri) if self.cache.is_offline() => uri,
+            _ => self.skin_uri(&pk)?,
+        };
+
+        let skin_md = self.fetch_metadata(mint, &uri)?;
+        let imgs = self.fetch_images_bytes(mint, &skin_md)?;
+
+        Ok((skin_md, imgs))
+    }
+
+    pub fn fetch_images_bytes(&self, mint: &str, skin_md: &SkinMetadata) -> Result<HashMap<String, Vec<u8>>, String>
     {
         let mut map: HashMap<String, Vec<u8>> = HashMap::new();
+        let hashes = skin_md.sha256.as_ref();
+
+        for (key, url) in skin_md.textures.slots() {
+            let hash = hashes.and_then(|h| h.get(key));
+            let cache_key = [mint, skin_md.uri.as_str(), url.as_str()];
+            let mut bytes = self.cache.fetch(&cache_key, url)?;
+
+            if let Some(hash) = hash {
+                //Could be a bad cache entry, give the network one more chance
+                if !sha256_hex(&bytes).eq_ignore_ascii_case(hash.trim()) {
+                    self.cache.remove(&cache_key);
+                    bytes = self.cache.fetch(&cache_key, url)?;
+                }
 
-        for (key, url) in &[
-            ("a",  &tf.a),
-            ("r",  &tf.r),
-            ("n",  &tf.n),
-            ("m",  &tf.m),
-            ("ao", &tf.ao),
-        ] {
-            let resp = get(*url)?;
-            let bytes = resp.bytes()?;
-
-            map.insert((*key).to_string(), bytes.to_vec());
+                if !sha256_hex(&bytes).eq_ignore_ascii_case(hash.trim()) {
+                    self.cache.remove(&cache_key);
+                    return Err(format!("Texture {} does not match its sha256", key));
+                }
+            }
+
+            //Refuse it here, before it gets anywhere near the render thread
+            inspect(&bytes).map_err(|e| format!("Texture {}: {}", key, e))?;
+
+            map.insert(key.to_string(), bytes);
         }
 
         Ok(map)
     }
 
-    //-! Weak textures need to be unloaded manually
-    pub fn fetch_textures(rl: &mut RaylibHandle, thread: &RaylibThread, imgs: HashMap<String, Vec<u8>>) -> Result<HashMap<String, WeakTexture2D>, String>
-    {
-        let mut map: HashMap<String, WeakTexture2D> = HashMap::new();
+    //Every skin our program created, the program is the first creator of all of them
+    pub fn fetch_listings(&self) -> Result<Vec<Listing>, String> {
+        let authority = nft_program::pda::mint_authority();
+
+        let metadatas: Vec<Metadata> = self.chain
+            .program_accounts(&mpl_token_metadata::ID, FIRST_CREATOR_OFFSET, authority.as_ref())?
+            .into_iter()
+            .filter_map(|(_, data)| Metadata::deserialize(&mut data.as_slice()).ok())
+            //Collections have no uri
+            .filter(|metadata| !metadata.uri.trim_matches('\0').is_empty())
+            .collect();
+
+        //Printed editions copy the creators, only the master editions are for sale
+        let editions: Vec<Pubkey> = metadatas.iter().map(|m| nft_program::pda::edition(&m.mint)).collect();
+        let editions = self.chain.multiple_account_data(&editions)?;
+        let prices: Vec<Pubkey> = metadatas.iter().map(|m| nft_program::pda::listing(&m.mint)).collect();
+        let prices = self.chain.multiple_account_data(&prices)?;
+        let recipes: Vec<Pubkey> = metadatas.iter().map(|m| nft_program::pda::recipe(&m.mint)).collect();
+        let recipes = self.chain.multiple_account_data(&recipes)?;
+        let mut decimals: HashMap<Pubkey, u8> = HashMap::new();
+
+        let listings = metadatas
+            .iter()
+            .zip(editions)
+            .zip(prices)
+            .zip(recipes)
+            .filter_map(|(((metadata, edition), price), recipe)| {
+                let edition = MasterEdition::deserialize(&mut edition?.as_slice()).ok()?;
+                if edition.key != Key::MasterEditionV2 {
+                    return None;
+                }
+                //Skins made before prices existed can't be bought
+                let price = SkinListing::parse(&price?).ok()?;
+                let price_decimals = match price.payment_mint {
+                    Some(mint) if decimals.contains_key(&mint) => decimals[&mint],
+                    Some(mint) => {
+                        let mint_decimals = self.chain.mint_decimals(&mint).ok()?;
+                        decimals.insert(mint, mint_decimals);
+                        mint_decimals
+                    }
+                    None => 9,
+                };
+
+                let mint = metadata.mint.to_string();
+                self.invalidate(&mint, price.revision);
+                let skin = match self.fetch_metadata(&mint, metadata.uri.trim_matches('\0')) {
+                    Ok(skin) if WEAPONS.contains(&skin.weapon()) => skin,
+                    Ok(_) => return None,
+                    Err(e) => {
+                        println!("Skipping listing {}: {}", mint, e);
+                        return None;
+                    }
+                };
+
+                //A listing without a preview is still a listing
+                let preview = skin.image.as_ref().and_then(|image| {
+                    let bytes = self.cache.fetch(&[mint.as_str(), skin.uri.as_str(), image.as_str()], image).ok()?;
+                    inspect(&bytes).ok()?;
+                    Some(bytes)
+                });
+
+                Some(Listing {
+                    master_mint: metadata.mint,
+                    skin,
+                    max_supply: edition.max_supply,
+                    preview,
+                    price,
+                    decimals: price_decimals,
+                    seller_fee_basis_points: metadata.seller_fee_basis_points,
+                    creators: metadata
+                        .creators
+                        .iter()
+                        .flatten()
+                        .filter(|creator| creator.address != authority)
+                        .map(|creator| (creator.address, creator.share))
+                        .collect(),
+                    recipe: recipe.and_then(|data| Recipe::parse(&data).ok()),
+                })
+            })
+            .collect();
+
+        Ok(listings)
+    }
+
+    //Pays the listing price and prints the next edition of a skin to the buyer, returns the new mint once confirmed
+    //allowlist is the list of wallets of an early access drop, it proves the buyer is in it
+    pub fn buy(&self, buyer: &Keypair, master_mint: &Pubkey, allowlist: &[Pubkey]) -> Result<(Pubkey, Signature), String> {
+        //The creator may have changed the price or the sale since the shop loaded
+        let listing = SkinListing::parse(&self.chain.account_data(&nft_program::pda::listing(master_mint))?)?;
+        if let Some(status) = sale_status(&listing, unix_now()) {
+            return Err(status);
+        }
+
+        //Same checks as the program, a clear message instead of a failed transaction
+        let purchases = self.chain
+            .account_data(&nft_program::pda::wallet_purchases(master_mint, &buyer.pubkey()))
+            .and_then(|data| nft_program::parse_wallet_purchases(&data))
+            .unwrap_or(0);
+        if listing.max_per_wallet != 0 && purchases >= listing.max_per_wallet {
+            return Err(format!("Limit of {} per wallet reached", listing.max_per_wallet));
+        }
+
+        let allowlist_proof = match listing.allowlist_root {
+            Some(root) if unix_now() < listing.public_start => merkle::proof(allowlist, &buyer.pubkey())
+                .filter(|proof| merkle::verify(proof, &root, &buyer.pubkey()))
+                .ok_or("Early access, this wallet is not on the allowlist (--allowlist)")?,
+            _ => Vec::new(),
+        };
+
+        //The marker depends on the editions printed, the listing read above is fresh
+        let data = self.chain.account_data(&nft_program::pda::edition(master_mint))?;
+        let edition = nft_program::parse_master_edition(&data)?;
+        if listing.remaining(edition.max_supply) == Some(0) {
+            return Err("Sold out".to_string());
+        }
+
+        let new_mint = Keypair::new();
+        let instruction = BuyNft {
+            buyer: buyer.pubkey(),
+            master_mint: *master_mint,
+            new_mint: new_mint.pubkey(),
+            editions_printed: listing.editions_printed,
+            treasury: listing.treasury,
+            price: listing.price,
+            payment_mint: listing.payment_mint,
+            allowlist_proof,
+        }
+        .instruction();
+
+        let blockhash = self.chain.latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&buyer.pubkey()),
+            &[buyer, &new_mint],
+            blockhash,
+        );
+        let signature = self.chain.send_transaction(&transaction)?;
+
+        Ok((new_mint.pubkey(), signature))
+    }
+
+    /**
+     * Burns the prints the recipe of upgrade_mint still needs, one transaction each, then prints the upgrade
+     * Burns already made count, a fuse that fails after them can be retried without burning again
+     * and they keep counting toward the recipe they were made for if the creator changes it
+     */
+    pub fn fuse(&self, player: &Keypair, upgrade_mint: &Pubkey) -> Result<(Pubkey, Signature), String> {
+        let recipe = self.chain
+            .account_data(&nft_program::pda::recipe(upgrade_mint))
+            .and_then(|data| Recipe::parse(&data))
+            .map_err(|_| "This skin has no recipe".to_string())?;
+        let progress = self.chain
+            .account_data(&nft_program::pda::fuse_progress(upgrade_mint, &player.pubkey()))
+            .and_then(|data| FuseProgress::parse(&data))
+            .ok();
+
+        //Nothing gets burned for a skin that can't be printed anymore
+        let listing = SkinListing::parse(&self.chain.account_data(&nft_program::pda::listing(upgrade_mint))?)?;
+        let data = self.chain.account_data(&nft_program::pda::edition(upgrade_mint))?;
+        let edition = nft_program::parse_master_edition(&data)?;
+        if listing.remaining(edition.max_supply) == Some(0) {
+            return Err("Sold out".to_string());
+        }
+
+        //A fuse under way finishes the recipe it started on, even if the creator changed it since
+        let ingredients = match &progress {
+            Some(progress) if !progress.burned.is_empty() => progress.ingredients.clone(),
+            _ => recipe.ingredients,
+        };
+
+        let mut prints = self.prints_of(&player.pubkey(), &ingredients)?;
+        let mut burns = Vec::new();
+        for ingredient in &ingredients {
+            let burned = progress.as_ref().map_or(0, |progress| progress.burned(&ingredient.master_mint));
+            let missing = ingredient.count.saturating_sub(burned) as usize;
+            let owned: Vec<(Pubkey, u64)> = prints.remove(&ingredient.master_mint).unwrap_or_default();
+            if owned.len() < missing {
+                return Err(format!(
+                    "Needs {} more prints of {}..",
+                    missing - owned.len(),
+                    &ingredient.master_mint.to_string()[..4]
+                ));
+            }
+            burns.extend(owned.into_iter().take(missing).map(|(print_mint, edition)| BurnIngredient {
+                player: player.pubkey(),
+                upgrade_mint: *upgrade_mint,
+                print_mint,
+                master_mint: ingredient.master_mint,
+                edition,
+            }));
+        }
+
+        for burn in burns {
+            let blockhash = self.chain.latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(&[burn.instruction()], Some(&player.pubkey()), &[player], blockhash);
+            self.chain.send_transaction(&transaction)?;
+            self.forget_skin(&player.pubkey(), &burn.print_mint);
+        }
 
-        for(key, bytes) in &imgs {
+        let new_mint = Keypair::new();
+        let instruction = Fuse {
+            player: player.pubkey(),
+            upgrade_mint: *upgrade_mint,
+            new_mint: new_mint.pubkey(),
+            editions_printed: listing.editions_printed,
+        }
+        .instruction();
+
+        let blockhash = self.chain.latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&player.pubkey()),
+            &[player, &new_mint],
+            blockhash,
+        );
+        let signature = self.chain.send_transaction(&transaction)?;
+
+        Ok((new_mint.pubkey(), signature))
+    }
+
+    //Prints the wallet holds of a recipe's ingredients, master mint -> (print mint, edition number)
+    fn prints_of(&self, owner: &Pubkey, ingredients: &[Ingredient]) -> Result<HashMap<Pubkey, Vec<(Pubkey, u64)>>, String> {
+        //A print's Edition points to the master edition account, not the mint
+        let masters: HashMap<Pubkey, Pubkey> = ingredients
+            .iter()
+            .map(|ingredient| (nft_program::pda::edition(&ingredient.master_mint), ingredient.master_mint))
+            .collect();
 
-            let img = Image::load_image_from_mem(".png", &bytes)
-            .expect("Failed to load image from memory");
+        let mints = self.chain.token_mints_by_owner(owner)?;
+        let editions: Vec<Pubkey> = mints.iter().map(nft_program::pda::edition).collect();
+        let editions = self.chain.multiple_account_data(&editions)?;
 
-            let texture = unsafe {
-                rl.load_texture_from_image(thread, &img)
-                .expect("Failed to load image to texture")
-                .make_weak()
+        let mut prints: HashMap<Pubkey, Vec<(Pubkey, u64)>> = HashMap::new();
+        for (mint, data) in mints.into_iter().zip(editions) {
+            let Some(edition) = data.and_then(|data| Edition::deserialize(&mut data.as_slice()).ok()) else {
+                continue;
             };
+            if edition.key != Key::EditionV1 {
+                continue;
+            }
+            if let Some(master) = masters.get(&edition.parent) {
+                prints.entry(*master).or_default().push((mint, edition.edition));
+            }
+        }
+
+        Ok(prints)
+    }
 
-            map.insert((*key).to_string(), texture);
+    //A burned print leaves the cached wallet, the next offline start doesn't show it
+    fn forget_skin(&self, owner: &Pubkey, mint: &Pubkey) {
+        if let Some(mut index) = self.cache.load_wallet(&owner.to_string()) {
+            index.retain(|(cached, _)| *cached != mint.to_string());
+            self.cache.store_wallet(&owner.to_string(), &index);
         }
+    }
 
-        Ok(map)
+    //Every print on the market, with the skin it loads like any print of the program
+    pub fn fetch_market(&self) -> Result<Vec<MarketListing>, String> {
+        let listings = self.chain
+            .program_accounts(&nft_program::PROGRAM_ID, 0, &nft_program::TRADE_LISTING)?
+            .into_iter()
+            .filter_map(|(_, data)| TradeListing::parse(&data).ok())
+            .filter_map(|listing| {
+                let metadata = self.metadata(&listing.mint).ok()?;
+                let uri = self.skin_uri(&listing.mint).ok()?;
+                let skin = match self.fetch_metadata(&listing.mint.to_string(), &uri) {
+                    Ok(skin) => skin,
+                    Err(e) => {
+                        println!("Skipping market listing {}: {}", listing.mint, e);
+                        return None;
+                    }
+                };
+
+                Some(MarketListing {
+                    mint: listing.mint,
+                    seller: listing.seller,
+                    price: listing.price,
+                    skin,
+                    seller_fee_basis_points: metadata.seller_fee_basis_points,
+                    creators: royalty_creators(&metadata),
+                })
+            })
+            .collect();
+
+        Ok(listings)
     }
 
-    //Textures must be cleared manually
-    pub fn clear(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread,) {
-        self.skin_map.iter().for_each(|entry| {
-            entry.value().iter().for_each(|(_, texture)| {
-                unsafe { rl.unload_texture(&thread, texture.to_owned()) };
-            });
-        });
+    //Puts a print of the wallet on the market, price in lamports
+    pub fn list(&self, seller: &Keypair, mint: &Pubkey, price: u64) -> Result<Signature, String> {
+        let instruction = List {
+            seller: seller.pubkey(),
+            mint: *mint,
+            price,
+        }
+        .instruction();
+
+        let blockhash = self.chain.latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&seller.pubkey()), &[seller], blockhash);
+        let signature = self.chain.send_transaction(&transaction)?;
+        self.forget_skin(&seller.pubkey(), mint);
+
+        Ok(signature)
     }
 
-}
\ No newline at end of file
+    //Takes a listed print back to the wallet
+    pub fn cancel(&self, seller: &Keypair, mint: &Pubkey) -> Result<Signature, String> {
+        let instruction = Cancel {
+            seller: seller.pubkey(),
+            mint: *mint,
+        }
+        .instruction();
+
+        let blockhash = self.chain.latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&seller.pubkey()), &[seller], blockhash);
+        self.chain.send_transaction(&transaction)
+    }
+
+    //Buys a listed print at the price the market showed, the program refuses it if the seller changed it since
+    pub fn purchase_listing(&self, buyer: &Keypair, mint: &Pubkey, price: u64) -> Result<Signature, String> {
+        let listing = self.chain
+            .account_data(&nft_program::pda::trade_listing(mint))
+            .and_then(|data| TradeListing::parse(&data))
+            .map_err(|_| "Not on the market anymore".to_string())?;
+        if listing.price != price {
+            return Err(format!("The price changed to {} SOL", listing.price as f64 / 1e9));
+        }
+        let metadata = self.metadata(mint)?;
+
+        let instruction = PurchaseListing {
+            buyer: buyer.pubkey(),
+            seller: listing.seller,
+            mint: *mint,
+            price,
+            creators: royalty_creators(&metadata).into_iter().map(|(address, _)| address).collect(),
+        }
+        .instruction();
+
+        let blockhash = self.chain.latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&buyer.pubkey()), &[buyer], blockhash);
+        self.chain.send_transaction(&transaction)
+    }
+}
+
+//The creators paid on a resale, in the order of the metadata like the program expects, our authority has no share
+fn royalty_creators(metadata: &Metadata) -> Vec<(Pubkey, u8)> {
+    metadata
+        .creators
+        .iter()
+        .flatten()
+        .filter(|creator| creator.share > 0)
+        .map(|creator| (creator.address, creator.share))
+        .collect()
+}
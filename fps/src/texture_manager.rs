@@ -0,0 +1,249 @@
+use std::collections::{HashMap, HashSet};
+
+use raylib::color::Color;
+use raylib::prelude::{RaylibDraw, RaylibDrawHandle};
+use raylib::texture::WeakTexture2D;
+use raylib::{RaylibHandle, RaylibThread};
+
+use crate::skin_loader::{LoadedSkin, SkinLoader};
+
+pub const DEFAULT_BUDGET_BYTES: usize = 256 * 1024 * 1024;
+//A player that was not drawn for this long lets go of its skin (~5s at 60fps)
+const PLAYER_TIMEOUT_FRAMES: u64 = 300;
+
+struct ResidentSkin {
+    skin: LoadedSkin,
+    bytes: usize,
+    //Players wearing it, a skin with players is never evicted
+    players: HashSet<i32>,
+    last_used: u64,
+}
+
+/**
+ * Owns the gpu side of the remote skins
+ * Skins are counted per player wearing them, the unused ones are evicted least recently used first
+ * once the resident textures go over the budget
+ */
+pub struct TextureManager {
+    budget: usize,
+    resident: HashMap<String, ResidentSkin>,
+    //Player id -> (mint, last frame it was drawn)
+    players: HashMap<i32, (String, u64)>,
+    frame: u64,
+    pub show_overlay: bool,
+}
+
+impl TextureManager {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            resident: HashMap::new(),
+            players: HashMap::new(),
+            frame: 0,
+            show_overlay: false,
+        }
+    }
+
+    //Call once per frame before acquiring
+    pub fn begin_frame(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.frame += 1;
+
+        let stale: Vec<i32> = self
+            .players
+            .iter()
+            .filter(|(_, (_, seen))| self.frame - seen > PLAYER_TIMEOUT_FRAMES)
+            .map(|(id, _)| *id)
+            .collect();
+        stale.into_iter().for_each(|id| self.release(id));
+
+        self.evict(rl, thread);
+    }
+
+    //The skin a player wears, None while it is loading
+    pub fn acquire(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        loader: &mut SkinLoader,
+        player: i32,
+        mint: &String,
+    ) -> Option<LoadedSkin> {
+        //Switched skins
+        if self.players.get(&player).is_some_and(|(worn, _)| worn != mint) {
+            self.release(player);
+        }
+        self.players.insert(player, (mint.clone(), self.frame));
+
+        if !self.resident.contains_key(mint) {
+            let skin = loader.take(mint)?;
+            let bytes = skin.textures.values().map(TextureManager::texture_bytes).sum();
+            for evicted in self.admit(player, mint, skin, bytes) {
+                TextureManager::unload(rl, thread, &evicted);
+            }
+        }
+
+        let resident = self.resident.get_mut(mint)?;
+        resident.players.insert(player);
+        resident.last_used = self.frame;
+
+        Some(resident.skin.clone())
+    }
+
+    //Already worn by the player, so going over the budget evicts the others and never the skin just uploaded
+    fn admit(&mut self, player: i32, mint: &str, skin: LoadedSkin, bytes: usize) -> Vec<ResidentSkin> {
+        self.resident.insert(
+            mint.to_string(),
+            ResidentSkin {
+                skin,
+                bytes,
+                players: HashSet::from([player]),
+                last_used: self.frame,
+            },
+        );
+
+        self.over_budget()
+    }
+
+    fn release(&mut self, player: i32) {
+        if let Some((mint, _)) = self.players.remove(&player)
+            && let Some(skin) = self.resident.get_mut(&mint)
+        {
+            skin.players.remove(&player);
+        }
+    }
+
+    fn evict(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        for evicted in self.over_budget() {
+            TextureManager::unload(rl, thread, &evicted);
+        }
+    }
+
+    //Takes out the least recently used skins nobody wears until the rest fits in the budget
+    fn over_budget(&mut self) -> Vec<ResidentSkin> {
+        let mut evicted = Vec::new();
+        while self.resident_bytes() > self.budget {
+            let lru = self
+                .resident
+                .iter()
+                .filter(|(_, skin)| skin.players.is_empty())
+                .min_by_key(|(_, skin)| skin.last_used)
+                .map(|(mint, _)| mint.clone());
+
+            //Everything left is worn by someone
+            let Some(mint) = lru else {
+                break;
+            };
+
+            evicted.extend(self.resident.remove(&mint));
+        }
+
+        evicted
+    }
+
+    pub fn resident_bytes(&self) -> usize {
+        self.resident.values().map(|skin| skin.bytes).sum()
+    }
+
+    fn texture_bytes(texture: &WeakTexture2D) -> usize {
+        //RGBA8, the mip chain adds a third
+        let base = texture.width as usize * texture.height as usize * 4;
+        if texture.mipmaps > 1 { base * 4 / 3 } else { base }
+    }
+
+    fn unload(rl: &mut RaylibHandle, thread: &RaylibThread, resident: &ResidentSkin) {
+        resident.skin.textures.values().for_each(|texture| {
+            unsafe { rl.unload_texture(thread, texture.to_owned()) };
+        });
+    }
+
+    pub fn draw_overlay(&self, d: &mut RaylibDrawHandle) {
+        if !self.show_overlay {
+            return;
+        }
+
+        let mb = |bytes: usize| bytes as f32 / (1024.0 * 1024.0);
+        d.draw_text(
+            &format!(
+                "skins: {}  {:.1}/{:.1} MB",
+                self.resident.len(),
+                mb(self.resident_bytes()),
+                mb(self.budget)
+            ),
+            10,
+            10,
+            20,
+            Color::BLACK,
+        );
+
+        let mut skins: Vec<(&String, &ResidentSkin)> = self.resident.iter().collect();
+        skins.sort_by_key(|(_, skin)| std::cmp::Reverse(skin.last_used));
+
+        for (i, (mint, skin)) in skins.iter().enumerate() {
+            d.draw_text(
+                &format!(
+                    "{}  {:.1} MB  players: {}",
+                    mint,
+                    mb(skin.bytes),
+                    skin.players.len()
+                ),
+                10,
+                34 + i as i32 * 18,
+                16,
+                if skin.players.is_empty() { Color::GRAY } else { Color::DARKGREEN },
+            );
+        }
+    }
+
+    //Textures must be cleared manually
+    pub fn clear(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.resident
+            .drain()
+            .for_each(|(_, skin)| TextureManager::unload(rl, thread, &skin));
+        self.players.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    //What acquire does once the loader hands the skin over
+    fn wear(manager: &mut TextureManager, player: i32, mint: &str, bytes: usize) -> Vec<ResidentSkin> {
+        manager.players.insert(player, (mint.to_string(), manager.frame));
+        let skin = LoadedSkin {
+            textures: HashMap::new(),
+            style: Default::default(),
+        };
+        manager.admit(player, mint, skin, bytes)
+    }
+
+    #[test]
+    fn keeps_the_skin_just_uploaded_when_everything_is_worn() {
+        let mut manager = TextureManager::new(100);
+        assert!(wear(&mut manager, 1, "a", 60).is_empty());
+
+        //Over the budget, but both are worn
+        assert!(wear(&mut manager, 2, "b", 60).is_empty());
+        assert!(manager.resident["b"].players.contains(&2));
+        assert_eq!(manager.resident_bytes(), 120);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_skin_nobody_wears() {
+        let mut manager = TextureManager::new(100);
+        wear(&mut manager, 1, "a", 40);
+        manager.frame += 1;
+        wear(&mut manager, 2, "b", 40);
+        manager.frame += 1;
+        manager.release(1);
+        manager.release(2);
+
+        let evicted = wear(&mut manager, 3, "c", 40);
+        assert_eq!(evicted.len(), 1);
+        assert!(!manager.resident.contains_key("a"));
+        assert!(manager.resident.contains_key("b"));
+        assert!(manager.resident.contains_key("c"));
+    }
+}
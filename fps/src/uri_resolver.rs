@@ -0,0 +1,185 @@
+use std::env;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+const DEFAULT_IPFS_GATEWAYS: [&str; 3] = ["https://ipfs.io", "https://dweb.link", "https://cloudflare-ipfs.com"];
+const DEFAULT_ARWEAVE_GATEWAYS: [&str; 2] = ["https://arweave.net", "https://ar-io.net"];
+
+pub enum Source {
+    //data: uris carry the bytes themselves
+    Inline(Vec<u8>),
+    //Urls to try in order, the first one that answers wins
+    Http(Vec<String>),
+}
+
+/**
+ * Turns whatever uri ends up in a metadata json into something reqwest can get
+ * ipfs:// and ar:// are expanded over the configured gateways, data: is decoded in place
+ */
+#[derive(Clone)]
+pub struct UriResolver {
+    ipfs_gateways: Vec<String>,
+    arweave_gateways: Vec<String>,
+}
+
+impl UriResolver {
+    pub fn new(ipfs_gateways: Vec<String>, arweave_gateways: Vec<String>) -> Self {
+        let trim = |gateways: Vec<String>| -> Vec<String> {
+            gateways
+                .into_iter()
+                .map(|g| g.trim().trim_end_matches('/').to_string())
+                .filter(|g| !g.is_empty())
+                .collect()
+        };
+
+        Self {
+            ipfs_gateways: trim(ipfs_gateways),
+            arweave_gateways: trim(arweave_gateways),
+        }
+    }
+
+    //FPS_IPFS_GATEWAYS / FPS_ARWEAVE_GATEWAYS as comma separated lists in fallback order
+    pub fn from_env() -> Self {
+        let gateways = |var: &str, defaults: &[&str]| -> Vec<String> {
+            match env::var(var) {
+                Ok(list) => list.split(',').map(|g| g.to_string()).collect(),
+                Err(_) => defaults.iter().map(|g| g.to_string()).collect(),
+            }
+        };
+
+        UriResolver::new(
+            gateways("FPS_IPFS_GATEWAYS", &DEFAULT_IPFS_GATEWAYS),
+            gateways("FPS_ARWEAVE_GATEWAYS", &DEFAULT_ARWEAVE_GATEWAYS),
+        )
+    }
+
+    pub fn resolve(&self, uri: &str) -> Result<Source, String> {
+        let uri = uri.trim().trim_matches('\0');
+
+        if let Some(rest) = uri.strip_prefix("ipfs://") {
+            //Both ipfs://<cid>/path and the older ipfs://ipfs/<cid>/path are around
+            let path = rest.strip_prefix("ipfs/").unwrap_or(rest);
+            return UriResolver::expand(&self.ipfs_gateways, &format!("ipfs/{}", path), uri);
+        }
+
+        if let Some(rest) = uri.strip_prefix("ar://") {
+            return UriResolver::expand(&self.arweave_gateways, rest, uri);
+        }
+
+        if let Some(rest) = uri.strip_prefix("data:") {
+            return decode_data_uri(rest).map(Source::Inline);
+        }
+
+        if uri.starts_with("http://") || uri.starts_with("https://") {
+            return Ok(Source::Http(vec![uri.to_string()]));
+        }
+
+        Err(format!("Unsupported uri {}", uri))
+    }
+
+    fn expand(gateways: &[String], path: &str, uri: &str) -> Result<Source, String> {
+        if gateways.is_empty() {
+            return Err(format!("No gateway configured for {}", uri));
+        }
+
+        Ok(Source::Http(
+            gateways
+                .iter()
+                .map(|gateway| format!("{}/{}", gateway, path))
+                .collect(),
+        ))
+    }
+}
+
+// data:[<mediatype>][;base64],<data>
+fn decode_data_uri(rest: &str) -> Result<Vec<u8>, String> {
+    let (header, data) = rest.split_once(',').ok_or("Malformed data uri")?;
+
+    if header.split(';').any(|param| param.eq_ignore_ascii_case("base64")) {
+        return STANDARD
+            .decode(data.trim())
+            .map_err(|e| format!("Malformed base64 in data uri: {}", e));
+    }
+
+    percent_decode(data)
+}
+
+fn percent_decode(data: &str) -> Result<Vec<u8>, String> {
+    let bytes = data.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = data.get(i + 1..i + 3).ok_or("Truncated escape in data uri")?;
+            out.push(u8::from_str_radix(hex, 16).map_err(|_| "Invalid escape in data uri")?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver() -> UriResolver {
+        UriResolver::new(
+            vec!["https://one.io/".to_string(), " https://two.io".to_string(), "".to_string()],
+            vec!["https://arweave.net".to_string()],
+        )
+    }
+
+    fn urls(uri: &str) -> Vec<String> {
+        match resolver().resolve(uri).unwrap() {
+            Source::Http(urls) => urls,
+            Source::Inline(_) => panic!("{} resolved inline", uri),
+        }
+    }
+
+    fn inline(uri: &str) -> Result<Vec<u8>, String> {
+        match resolver().resolve(uri)? {
+            Source::Inline(bytes) => Ok(bytes),
+            Source::Http(_) => panic!("{} resolved to http", uri),
+        }
+    }
+
+    #[test]
+    fn ipfs_goes_over_every_gateway_in_order() {
+        let expected = vec!["https://one.io/ipfs/bafy/skin.json", "https://two.io/ipfs/bafy/skin.json"];
+        assert_eq!(urls("ipfs://bafy/skin.json"), expected);
+        //The older form with the ipfs/ prefix
+        assert_eq!(urls("ipfs://ipfs/bafy/skin.json"), expected);
+    }
+
+    #[test]
+    fn arweave_and_http_uris() {
+        assert_eq!(urls("ar://tx_id"), vec!["https://arweave.net/tx_id"]);
+        //Metadata strings come zero padded from the chain
+        assert_eq!(urls("https://host/skin.json\0\0"), vec!["https://host/skin.json"]);
+        assert!(resolver().resolve("ftp://host/skin.json").is_err());
+        assert!(UriResolver::new(vec![], vec![]).resolve("ipfs://bafy").is_err());
+    }
+
+    #[test]
+    fn data_uris_are_decoded_in_place() {
+        assert_eq!(inline("data:application/json;base64,eyJhIjoxfQ==").unwrap(), b"{\"a\":1}");
+        assert_eq!(inline("data:,plain").unwrap(), b"plain");
+        assert!(inline("data:;base64,not base64!").is_err());
+        assert!(inline("data:no comma").is_err());
+    }
+
+    #[test]
+    fn percent_escapes() {
+        assert_eq!(inline("data:application/json,%7B%22a%22%3A1%7D").unwrap(), b"{\"a\":1}");
+        assert_eq!(inline("data:,%00%ff").unwrap(), vec![0, 255]);
+        assert!(inline("data:,50%").is_err());
+        assert!(inline("data:,%4").is_err());
+        assert!(inline("data:,%zz").is_err());
+    }
+}
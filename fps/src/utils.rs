@@ -1,5 +1,7 @@
 use std::ffi::CStr;
 
+use sha2::{Digest, Sha256};
+
 pub fn c_bytesto_string(buf: &[i8]) -> String {
     let c_str = unsafe {
         let ptr = buf.as_ptr() as *const i8;
@@ -7,4 +9,11 @@ pub fn c_bytesto_string(buf: &[i8]) -> String {
     };
 
     c_str.to_string_lossy().into_owned()
-}
\ No newline at end of file
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
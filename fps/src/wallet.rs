@@ -0,0 +1,271 @@
+use std::fs;
+use std::io::stdin;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anchor_client::solana_sdk::derivation_path::DerivationPath;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{keypair_from_seed_and_derivation_path, read_keypair_file, Keypair, Signature, Signer};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use bip39::Mnemonic;
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+//What Phantom and most wallets use for the first account
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/501'/0'/0'";
+const KDF_ITERATIONS: u32 = 600_000;
+//What a wallet file may ask for, below is a weak key and above stalls the start for minutes
+const KDF_ITERATIONS_RANGE: RangeInclusive<u32> = 100_000..=10_000_000;
+
+//The keypair on disk, sealed with a key derived from the passphrase
+#[derive(Serialize, Deserialize, Clone)]
+struct EncryptedWallet {
+    version: u32,
+    pubkey: String,
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/**
+ * A keypair the client can sign with, for the purchases and the server login
+ * Comes from a Solana CLI keypair file, a BIP39 seed phrase or the encrypted wallet file
+ */
+#[derive(Clone)]
+pub struct Wallet {
+    keypair: Arc<Keypair>,
+}
+
+impl Wallet {
+    pub fn from_keypair_file(path: &str) -> Result<Self, String> {
+        let keypair = read_keypair_file(path).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Self { keypair: Arc::new(keypair) })
+    }
+
+    //passphrase is the optional BIP39 one, not the one of the wallet file
+    pub fn from_seed_phrase(phrase: &str, passphrase: &str, derivation_path: &str) -> Result<Self, String> {
+        let mnemonic = Mnemonic::parse_normalized(phrase.trim()).map_err(|e| format!("Invalid seed phrase: {}", e))?;
+        let path = DerivationPath::from_absolute_path_str(derivation_path)
+            .map_err(|e| format!("Invalid derivation path {}: {}", derivation_path, e))?;
+
+        let keypair = keypair_from_seed_and_derivation_path(&mnemonic.to_seed(passphrase), Some(path))
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self { keypair: Arc::new(keypair) })
+    }
+
+    //config dir/fps/wallet.json
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("fps")
+            .join("wallet.json")
+    }
+
+    fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Key {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+        key.into()
+    }
+
+    pub fn save_encrypted(&self, path: &Path, passphrase: &str) -> Result<(), String> {
+        let wallet = self.seal(passphrase, KDF_ITERATIONS)?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_vec_pretty(&wallet).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn seal(&self, passphrase: &str, iterations: u32) -> Result<EncryptedWallet, String> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = Nonce::default();
+        OsRng.fill_bytes(&mut nonce);
+
+        let cipher = ChaCha20Poly1305::new(&Wallet::derive_key(passphrase, &salt, iterations));
+        let ciphertext = cipher
+            .encrypt(&nonce, self.keypair.to_bytes().as_ref())
+            .map_err(|e| e.to_string())?;
+
+        Ok(EncryptedWallet {
+            version: 1,
+            pubkey: self.pubkey().to_string(),
+            kdf: "pbkdf2-sha256".to_string(),
+            iterations,
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    pub fn load_encrypted(path: &Path, passphrase: &str) -> Result<Self, String> {
+        let json = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let wallet: EncryptedWallet = serde_json::from_slice(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        if wallet.version != 1 || wallet.kdf != "pbkdf2-sha256" {
+            return Err(format!("{}: unsupported wallet file", path.display()));
+        }
+        if !KDF_ITERATIONS_RANGE.contains(&wallet.iterations) {
+            return Err(format!(
+                "{}: {} iterations, expected {} to {}",
+                path.display(),
+                wallet.iterations,
+                KDF_ITERATIONS_RANGE.start(),
+                KDF_ITERATIONS_RANGE.end()
+            ));
+        }
+
+        let decode = |field: &str| BASE64.decode(field).map_err(|e| e.to_string());
+        let salt = decode(&wallet.salt)?;
+        let nonce = decode(&wallet.nonce)?;
+        let ciphertext = decode(&wallet.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err(format!("{}: bad nonce", path.display()));
+        }
+
+        let cipher = ChaCha20Poly1305::new(&Wallet::derive_key(passphrase, &salt, wallet.iterations));
+        //Authenticated, a wrong passphrase fails here and not with a random key
+        let bytes = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| "Wrong passphrase".to_string())?;
+        let keypair = Keypair::from_bytes(&bytes).map_err(|e| e.to_string())?;
+
+        if keypair.pubkey().to_string() != wallet.pubkey {
+            return Err(format!("{}: the keypair doesn't match its pubkey", path.display()));
+        }
+
+        Ok(Self { keypair: Arc::new(keypair) })
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    //Shared with the threads that send the transactions
+    pub fn signer(&self) -> Arc<Keypair> {
+        self.keypair.clone()
+    }
+
+    pub fn sign_message(&self, message: &[u8]) -> Signature {
+        self.keypair.sign_message(message)
+    }
+
+    //What the server gets to tie a connection to the wallet, the nonce it gave us makes it single use
+    pub fn sign_login(&self, client_id: i32, nonce: &[u8]) -> Signature {
+        self.sign_message(Wallet::login_message(&self.pubkey(), client_id, nonce).as_bytes())
+    }
+
+    pub fn login_message(pubkey: &Pubkey, client_id: i32, nonce: &[u8]) -> String {
+        let nonce: String = nonce.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("fps login {} {} {}", pubkey, client_id, nonce)
+    }
+
+    /**
+     * --keypair <path>, --seed-phrase [--derivation-path <path>] or --wallet [path]
+     * The seed phrase can be saved encrypted for --wallet on the next start
+     * None when none is given, the client only reads then
+     */
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, String> {
+        let value = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|i| args.get(i + 1))
+                .filter(|value| !value.starts_with("--"))
+        };
+
+        if let Some(path) = value("--keypair") {
+            return Wallet::from_keypair_file(path).map(Some);
+        }
+
+        if args.iter().any(|arg| arg == "--seed-phrase") {
+            let phrase = rpassword::prompt_password("Seed phrase: ").map_err(|e| e.to_string())?;
+            let passphrase = rpassword::prompt_password("BIP39 passphrase (empty for none): ").map_err(|e| e.to_string())?;
+            let derivation_path = value("--derivation-path").map(String::as_str).unwrap_or(DEFAULT_DERIVATION_PATH);
+
+            let wallet = Wallet::from_seed_phrase(&phrase, &passphrase, derivation_path)?;
+            println!("Wallet {}", wallet.pubkey());
+
+            println!("Save it encrypted to {}? [y/N]", Wallet::default_path().display());
+            let mut answer = String::new();
+            stdin().read_line(&mut answer).map_err(|e| e.to_string())?;
+            if answer.trim().eq_ignore_ascii_case("y") {
+                let passphrase = rpassword::prompt_password("New wallet passphrase: ").map_err(|e| e.to_string())?;
+                if passphrase != rpassword::prompt_password("Again: ").map_err(|e| e.to_string())? {
+                    return Err("The passphrases don't match".to_string());
+                }
+                wallet.save_encrypted(&Wallet::default_path(), &passphrase)?;
+                println!("Saved, start with --wallet next time");
+            }
+
+            return Ok(Some(wallet));
+        }
+
+        if args.iter().any(|arg| arg == "--wallet") {
+            let path = value("--wallet").map(PathBuf::from).unwrap_or_else(Wallet::default_path);
+            let passphrase = rpassword::prompt_password("Wallet passphrase: ").map_err(|e| e.to_string())?;
+            return Wallet::load_encrypted(&path, &passphrase).map(Some);
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    //Sealed with the fewest iterations allowed, the tests run unoptimized
+    fn sealed(wallet: &Wallet) -> EncryptedWallet {
+        wallet.seal("hunter2", *KDF_ITERATIONS_RANGE.start()).unwrap()
+    }
+
+    fn write(path: &Path, wallet: &EncryptedWallet) {
+        fs::write(path, serde_json::to_vec(wallet).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn loads_back_the_same_keypair() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("wallet.json");
+        let wallet = Wallet { keypair: Arc::new(Keypair::new()) };
+        write(&path, &sealed(&wallet));
+
+        assert_eq!(Wallet::load_encrypted(&path, "hunter2").unwrap().pubkey(), wallet.pubkey());
+        assert_eq!(Wallet::load_encrypted(&path, "hunter3").err().unwrap(), "Wrong passphrase");
+    }
+
+    #[test]
+    fn refuses_iterations_out_of_range_before_deriving() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("wallet.json");
+        let sealed = sealed(&Wallet { keypair: Arc::new(Keypair::new()) });
+
+        for iterations in [0, 1, 99_999, 10_000_001, u32::MAX] {
+            write(&path, &EncryptedWallet { iterations, ..sealed.clone() });
+
+            let error = Wallet::load_encrypted(&path, "hunter2").err().unwrap();
+            assert!(error.ends_with(&format!("{} iterations, expected 100000 to 10000000", iterations)), "{}", error);
+        }
+    }
+
+    #[test]
+    fn signs_the_login_of_its_connection() {
+        let wallet = Wallet { keypair: Arc::new(Keypair::new()) };
+        let message = Wallet::login_message(&wallet.pubkey(), 3, &[0xab, 0x01]);
+
+        assert_eq!(message, format!("fps login {} 3 ab01", wallet.pubkey()));
+        assert!(wallet.sign_login(3, &[0xab, 0x01]).verify(wallet.pubkey().as_ref(), message.as_bytes()));
+        assert!(!wallet.sign_login(3, &[0xab, 0x02]).verify(wallet.pubkey().as_ref(), message.as_bytes()));
+    }
+}
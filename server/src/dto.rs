@@ -7,4 +7,10 @@ pub struct PlayerInfo {
     pub position_z: f32,
     pub yaw: f32,
     pub pitch: f32,
-}
\ No newline at end of file
+}
+//Sent once on the login channel, the wallet's signature of the login message with the connection's nonce
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Login {
+    pub wallet: String,
+    pub signature: Vec<u8>,
+}
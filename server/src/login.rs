@@ -0,0 +1,96 @@
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use rand_core::{OsRng, RngCore};
+
+use crate::dto::Login;
+
+pub const NONCE_LEN: usize = 32;
+
+//Sent with the id of each connection, a signature of another connection's nonce is worthless
+pub fn new_nonce() -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    nonce
+}
+
+//What a new connection gets, its id then its login nonce
+pub fn welcome(id: i32, nonce: &[u8; NONCE_LEN]) -> Vec<u8> {
+    let mut packet = id.to_le_bytes().to_vec();
+    packet.extend_from_slice(nonce);
+    packet
+}
+
+//What the client's wallet signs, the nonce of the connection makes it single use
+pub fn login_message(wallet: &str, id: i32, nonce: &[u8; NONCE_LEN]) -> String {
+    let nonce: String = nonce.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("fps login {} {} {}", wallet, id, nonce)
+}
+
+//The wallet the player with this id and nonce logged in with
+pub fn verify(data: &[u8], id: i32, nonce: &[u8; NONCE_LEN]) -> Result<String, String> {
+    let login: Login = bincode::deserialize(data).map_err(|e| e.to_string())?;
+
+    let key: [u8; 32] = bs58::decode(&login.wallet)
+        .into_vec()
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| format!("{} is not a wallet address", login.wallet))?;
+    let key = VerifyingKey::from_bytes(&key).map_err(|e| e.to_string())?;
+    let signature = Signature::from_slice(&login.signature).map_err(|e| e.to_string())?;
+
+    key.verify(login_message(&login.wallet, id, nonce).as_bytes(), &signature)
+        .map_err(|_| format!("Bad login signature for {}", login.wallet))?;
+
+    Ok(login.wallet)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+
+    const NONCE: [u8; NONCE_LEN] = [9; NONCE_LEN];
+
+    fn login(key: &SigningKey, id: i32, nonce: &[u8; NONCE_LEN]) -> Vec<u8> {
+        let wallet = bs58::encode(key.verifying_key().as_bytes()).into_string();
+        let signature = key.sign(login_message(&wallet, id, nonce).as_bytes());
+        bincode::serialize(&Login { wallet, signature: signature.to_bytes().to_vec() }).unwrap()
+    }
+
+    #[test]
+    fn accepts_the_wallet_that_signed_its_id_and_nonce() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let wallet = verify(&login(&key, 3, &NONCE), 3, &NONCE).unwrap();
+        assert_eq!(wallet, bs58::encode(key.verifying_key().as_bytes()).into_string());
+    }
+
+    #[test]
+    fn rejects_a_login_replayed_on_another_connection() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        assert!(verify(&login(&key, 3, &NONCE), 4, &NONCE).is_err());
+        //The ids start over with the server, the nonces don't
+        assert!(verify(&login(&key, 3, &NONCE), 3, &[10; NONCE_LEN]).is_err());
+    }
+
+    #[test]
+    fn rejects_a_signature_of_another_wallet() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let other = SigningKey::from_bytes(&[8; 32]);
+        let mut forged: Login = bincode::deserialize(&login(&other, 3, &NONCE)).unwrap();
+        forged.wallet = bs58::encode(key.verifying_key().as_bytes()).into_string();
+
+        assert!(verify(&bincode::serialize(&forged).unwrap(), 3, &NONCE).is_err());
+        assert!(verify(b"garbage", 3, &NONCE).is_err());
+    }
+
+    #[test]
+    fn welcomes_with_the_id_then_a_fresh_nonce() {
+        let nonce = new_nonce();
+        let packet = welcome(3, &nonce);
+
+        assert_eq!(packet.len(), 4 + NONCE_LEN);
+        assert_eq!(packet[..4], 3i32.to_le_bytes());
+        assert_eq!(packet[4..], nonce);
+        assert_ne!(new_nonce(), nonce);
+    }
+}
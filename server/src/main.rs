@@ -6,12 +6,20 @@ use anyhow::Context;
 use enet::*;
 mod dto;
 use dto::PlayerInfo;
+mod login;
 
 const MAX_PEERS: usize = 5;
 const PORT: u16 = 6969;
+//Channel 0 is relayed to the other players, what comes on 1 is for the server only
+const LOGIN_CHANNEL: u8 = 1;
 
 pub struct PlayerDetails<'a> {
     peer: Peer<'a, ()>,
+    id: i32,
+    //Set once its login signature checks out, None for the clients without a wallet
+    wallet: Option<String>,
+    //What its login signs, taken by the first attempt
+    login_nonce: Option<[u8; login::NONCE_LEN]>,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -43,10 +51,14 @@ fn handle_event<'a>(event: Event<'a, ()>, players: &mut Vec<PlayerDetails<'a>>)
     match event {
         Event::Connect(ref peer) => {
             let mut peer_clone = peer.clone();
-            let id_bytes: [u8; 4] = unsafe { id.to_le_bytes() };
-            let _ = peer_clone.send_packet(Packet::new(id_bytes.as_ref(), PacketMode::ReliableSequenced).unwrap(), 0);
+            let nonce = login::new_nonce();
+            let welcome = login::welcome(unsafe { id }, &nonce);
+            let _ = peer_clone.send_packet(Packet::new(&welcome, PacketMode::ReliableSequenced).unwrap(), 0);
             players.push(PlayerDetails {
                 peer: peer_clone,
+                id: unsafe { id },
+                wallet: None,
+                login_nonce: Some(nonce),
             });
             println!("new connection: {}", players.len());
             unsafe { id += 1 };
@@ -62,8 +74,29 @@ fn handle_event<'a>(event: Event<'a, ()>, players: &mut Vec<PlayerDetails<'a>>)
         Event::Receive {
             ref packet,
             ref sender,
-            ..
+            channel_id,
         } => {
+            if channel_id == LOGIN_CHANNEL {
+                let Some(player) = players.iter_mut().find(|p| p.peer.address() == sender.address()) else {
+                    return;
+                };
+                let Some(nonce) = player.login_nonce.take() else {
+                    println!("player {} logged in twice", player.id);
+                    player.peer.disconnect(0);
+                    return;
+                };
+                match login::verify(packet.data(), player.id, &nonce) {
+                    Ok(wallet) => {
+                        println!("player {} logged in as {}", player.id, wallet);
+                        player.wallet = Some(wallet);
+                    }
+                    Err(e) => {
+                        println!("player {} failed to log in: {}", player.id, e);
+                        player.peer.disconnect(0);
+                    }
+                }
+                return;
+            }
 
             players
                 .iter_mut()
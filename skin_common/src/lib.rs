@@ -0,0 +1,5 @@
+//What the game client and the skin_tool both need to agree on
+pub mod merkle;
+pub mod nft_program;
+pub mod skin_schema;
+pub mod texture_validation;
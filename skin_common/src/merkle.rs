@@ -0,0 +1,115 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use sha2::{Digest, Sha256};
+
+/**
+ * Allowlists of the early access drops, same tree as the program checks
+ * Leaves are sha256(wallet), pairs are hashed sorted so a proof is only the siblings
+ */
+pub fn leaf(wallet: &Pubkey) -> [u8; 32] {
+    Sha256::digest(wallet.as_ref()).into()
+}
+
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Sha256::new();
+    hasher.update(first);
+    hasher.update(second);
+    hasher.finalize().into()
+}
+
+//Every level from the sorted leaves to the root, an odd node goes up as is
+fn levels(wallets: &[Pubkey]) -> Vec<Vec<[u8; 32]>> {
+    let mut leaves: Vec<[u8; 32]> = wallets.iter().map(leaf).collect();
+    leaves.sort();
+    leaves.dedup();
+
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+
+    levels
+}
+
+//None for an empty list
+pub fn root(wallets: &[Pubkey]) -> Option<[u8; 32]> {
+    levels(wallets).last().unwrap().first().copied()
+}
+
+//The siblings from the wallet's leaf up, None when it is not in the list
+pub fn proof(wallets: &[Pubkey], wallet: &Pubkey) -> Option<Vec<[u8; 32]>> {
+    let levels = levels(wallets);
+    let mut index = levels[0].iter().position(|node| *node == leaf(wallet))?;
+
+    let mut proof = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+
+    Some(proof)
+}
+
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], wallet: &Pubkey) -> bool {
+    proof.iter().fold(leaf(wallet), |node, sibling| hash_pair(&node, sibling)) == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_wallet_proves_against_the_root() {
+        //Odd sizes too, the last node goes up without a sibling
+        for count in 1..8 {
+            let wallets: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+            let root = root(&wallets).unwrap();
+
+            for wallet in &wallets {
+                let proof = proof(&wallets, wallet).unwrap();
+                assert!(verify(&proof, &root, wallet));
+            }
+        }
+    }
+
+    #[test]
+    fn outsiders_have_no_proof() {
+        let wallets: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let root = root(&wallets).unwrap();
+        let outsider = Pubkey::new_unique();
+
+        assert!(proof(&wallets, &outsider).is_none());
+        //Nor can they borrow someone else's
+        let borrowed = proof(&wallets, &wallets[0]).unwrap();
+        assert!(!verify(&borrowed, &root, &outsider));
+    }
+
+    #[test]
+    fn the_order_and_duplicates_do_not_change_the_root() {
+        let wallets: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let mut shuffled: Vec<Pubkey> = wallets.iter().rev().copied().collect();
+        shuffled.push(wallets[1]);
+
+        assert_eq!(root(&wallets), root(&shuffled));
+        assert_eq!(root(&[]), None);
+    }
+
+    #[test]
+    fn a_single_wallet_is_its_own_root() {
+        let wallet = Pubkey::new_unique();
+        assert_eq!(root(&[wallet]), Some(leaf(&wallet)));
+        assert_eq!(proof(&[wallet], &wallet), Some(vec![]));
+    }
+}
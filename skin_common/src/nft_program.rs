@@ -0,0 +1,1002 @@
+use anchor_client::anchor_lang::solana_program::pubkey;
+use anchor_client::anchor_lang::{AnchorDeserialize, AnchorSerialize};
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::instruction::{AccountMeta, Instruction};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{Keypair, Signature, Signer};
+use anchor_client::solana_sdk::{bpf_loader_upgradeable, system_program};
+use anchor_client::solana_sdk::transaction::Transaction;
+use mpl_token_metadata::accounts::MasterEdition;
+use serde::{Deserialize, Serialize};
+
+/**
+ * Bindings for the nft program in anchor/nft, one builder per instruction
+ * The accounts are in the order of the program's Accounts structs, anchor/nft/programs/nft/tests/bindings.rs checks them
+ * Builders only build, sending is up to the caller (or submit)
+ */
+pub const PROGRAM_ID: Pubkey = pubkey!("ADidMwkBx687QFpAFmYVJs3fqVLQz1BHNfb1dH4o5UgK");
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+//Editions tracked per marker account
+pub const EDITION_MARKER_BIT_SIZE: u64 = 248;
+//Same caps as the program's constants
+pub const MAX_SELLER_FEE_BASIS_POINTS: u16 = 2500;
+pub const MAX_CREATORS: usize = 4;
+
+//sha256("global:<instruction>")[..8]
+const CREATE_COLLECTION: [u8; 8] = [156, 251, 92, 54, 233, 2, 16, 82];
+const CREATE_NFT: [u8; 8] = [231, 119, 61, 97, 217, 46, 142, 109];
+const BUY_NFT: [u8; 8] = [96, 0, 28, 190, 49, 107, 83, 222];
+const SET_PRICE: [u8; 8] = [16, 19, 182, 8, 149, 83, 72, 181];
+const INITIALIZE_CONFIG: [u8; 8] = [208, 127, 21, 1, 194, 190, 196, 70];
+const SET_ADMIN: [u8; 8] = [251, 163, 0, 52, 91, 194, 187, 92];
+const ADD_CREATOR: [u8; 8] = [120, 140, 147, 174, 149, 203, 237, 81];
+const SET_CREATOR_STATUS: [u8; 8] = [112, 120, 28, 160, 166, 186, 49, 242];
+const REMOVE_CREATOR: [u8; 8] = [125, 152, 5, 6, 49, 239, 31, 166];
+const UPDATE_SKIN: [u8; 8] = [41, 254, 183, 31, 72, 217, 231, 16];
+const SET_SALE: [u8; 8] = [123, 117, 196, 134, 96, 101, 255, 77];
+const SET_PAUSED: [u8; 8] = [91, 60, 125, 192, 176, 225, 166, 218];
+const SET_RECIPE: [u8; 8] = [96, 20, 161, 246, 236, 30, 170, 219];
+const BURN_INGREDIENT: [u8; 8] = [212, 90, 177, 253, 99, 88, 102, 93];
+const FUSE: [u8; 8] = [217, 196, 80, 243, 178, 186, 12, 156];
+const LIST: [u8; 8] = [54, 174, 193, 67, 17, 41, 132, 38];
+const CANCEL: [u8; 8] = [232, 219, 223, 41, 219, 236, 220, 190];
+const PURCHASE_LISTING: [u8; 8] = [246, 29, 226, 161, 105, 118, 198, 150];
+//sha256("event:<Event>")[..8], first bytes of the "Program data: " logs
+const COLLECTION_CREATED: [u8; 8] = [69, 167, 76, 142, 182, 183, 233, 139];
+const SKIN_CREATED: [u8; 8] = [249, 214, 147, 239, 7, 131, 165, 104];
+const SKIN_PURCHASED: [u8; 8] = [120, 227, 216, 6, 255, 113, 22, 85];
+const SKIN_FUSED: [u8; 8] = [250, 64, 254, 163, 0, 217, 129, 181];
+const SKIN_LISTED: [u8; 8] = [60, 0, 205, 17, 186, 222, 121, 87];
+const SKIN_DELISTED: [u8; 8] = [16, 71, 27, 36, 12, 86, 43, 233];
+const SKIN_TRADED: [u8; 8] = [165, 22, 45, 61, 167, 238, 129, 251];
+//sha256("account:WalletPurchases")[..8]
+const WALLET_PURCHASES: [u8; 8] = [105, 171, 133, 123, 198, 88, 16, 51];
+//sha256("account:SkinListing")[..8], also filters them in getProgramAccounts
+pub const SKIN_LISTING: [u8; 8] = [249, 237, 20, 216, 238, 21, 179, 212];
+//sha256("account:Recipe")[..8]
+const RECIPE: [u8; 8] = [10, 162, 156, 100, 56, 193, 205, 77];
+//sha256("account:FuseProgress")[..8]
+const FUSE_PROGRESS: [u8; 8] = [235, 180, 184, 68, 78, 152, 143, 141];
+//sha256("account:TradeListing")[..8], also filters them in getProgramAccounts
+pub const TRADE_LISTING: [u8; 8] = [69, 43, 175, 151, 184, 142, 145, 85];
+//Same cap as the program, skins a recipe takes
+pub const MAX_RECIPE_INGREDIENTS: usize = 4;
+
+pub mod pda {
+    use super::*;
+
+    //Mint and update authority of everything the program creates, owns the master edition vaults
+    pub fn mint_authority() -> Pubkey {
+        Pubkey::find_program_address(&[b"authority"], &PROGRAM_ID).0
+    }
+
+    pub fn metadata(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+            &mpl_token_metadata::ID,
+        )
+        .0
+    }
+
+    //Master edition of a master mint, edition of a printed one
+    pub fn edition(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref(), b"edition"],
+            &mpl_token_metadata::ID,
+        )
+        .0
+    }
+
+    //Marker of the next print, editions_printed of the listing + 1, so edition 248 is already on the second marker
+    //Not the master edition supply, a burn lowers it and the burned edition stays marked
+    pub fn edition_marker(master_mint: &Pubkey, editions_printed: u64) -> Pubkey {
+        print_marker(master_mint, editions_printed + 1)
+    }
+
+    //Marker holding the bit of an edition number, burning the print clears it
+    pub fn print_marker(master_mint: &Pubkey, edition: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"metadata",
+                mpl_token_metadata::ID.as_ref(),
+                master_mint.as_ref(),
+                b"edition",
+                (edition / EDITION_MARKER_BIT_SIZE).to_string().as_bytes(),
+            ],
+            &mpl_token_metadata::ID,
+        )
+        .0
+    }
+
+    pub fn associated_token(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[owner.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint.as_ref()],
+            &ASSOCIATED_TOKEN_PROGRAM_ID,
+        )
+        .0
+    }
+
+    //Where the program keeps the master edition token of a skin
+    pub fn vault(master_mint: &Pubkey) -> Pubkey {
+        associated_token(&mint_authority(), master_mint)
+    }
+
+    //Price of a skin, created with it
+    pub fn listing(master_mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"listing", master_mint.as_ref()], &PROGRAM_ID).0
+    }
+
+    //How many prints of a skin a wallet bought
+    pub fn wallet_purchases(master_mint: &Pubkey, wallet: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"purchases", master_mint.as_ref(), wallet.as_ref()], &PROGRAM_ID).0
+    }
+
+    //Prints a fuse takes for a print of the upgrade
+    pub fn recipe(upgrade_mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"recipe", upgrade_mint.as_ref()], &PROGRAM_ID).0
+    }
+
+    //Prints a player burned toward an upgrade, until the fuse
+    pub fn fuse_progress(upgrade_mint: &Pubkey, player: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"fuse", upgrade_mint.as_ref(), player.as_ref()], &PROGRAM_ID).0
+    }
+
+    //A print a player sells on the market
+    pub fn trade_listing(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"trade", mint.as_ref()], &PROGRAM_ID).0
+    }
+
+    //Holds the listed print, owned by its trade listing
+    pub fn escrow(mint: &Pubkey) -> Pubkey {
+        associated_token(&trade_listing(mint), mint)
+    }
+
+    //Holds the admin
+    pub fn config() -> Pubkey {
+        Pubkey::find_program_address(&[b"config"], &PROGRAM_ID).0
+    }
+
+    //Registers a wallet as a skin creator
+    pub fn creator_record(wallet: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"creator", wallet.as_ref()], &PROGRAM_ID).0
+    }
+
+    //Upgrade authority of the program, checked by initialize_config
+    pub fn program_data() -> Pubkey {
+        Pubkey::find_program_address(&[PROGRAM_ID.as_ref()], &bpf_loader_upgradeable::ID).0
+    }
+}
+
+//A wallet in the royalty split of a skin
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq)]
+pub struct CreatorShare {
+    pub address: Pubkey,
+    pub share: u8,
+}
+
+//Prints of a skin the wallet bought, 0 when the account doesn't exist yet
+pub fn parse_wallet_purchases(data: &[u8]) -> Result<u32, String> {
+    if data.len() < 12 || data[..8] != WALLET_PURCHASES {
+        return Err("Not a purchase counter".to_string());
+    }
+    <u32 as AnchorDeserialize>::deserialize(&mut &data[8..]).map_err(|e| e.to_string())
+}
+
+//Mirrors of the program's events
+#[derive(AnchorSerialize, AnchorDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CollectionCreated {
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+    pub name: String,
+    pub symbol: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SkinCreated {
+    pub master_mint: Pubkey,
+    pub creator: Pubkey,
+    pub collection_mint: Pubkey,
+    pub name: String,
+    pub uri: String,
+    pub max_supply: u64,
+    pub seller_fee_basis_points: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SkinPurchased {
+    pub master_mint: Pubkey,
+    pub edition_mint: Pubkey,
+    pub edition: u64,
+    pub buyer: Pubkey,
+    //Lamports, or base units of payment_mint
+    pub price: u64,
+    pub payment_mint: Option<Pubkey>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SkinFused {
+    pub upgrade_mint: Pubkey,
+    pub edition_mint: Pubkey,
+    pub edition: u64,
+    pub player: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SkinListed {
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SkinDelisted {
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SkinTraded {
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    //Lamports, royalties included
+    pub price: u64,
+    pub royalties: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgramEvent {
+    CollectionCreated(CollectionCreated),
+    SkinCreated(SkinCreated),
+    SkinPurchased(SkinPurchased),
+    SkinFused(SkinFused),
+    SkinListed(SkinListed),
+    SkinDelisted(SkinDelisted),
+    SkinTraded(SkinTraded),
+}
+
+impl ProgramEvent {
+    //Decoded base64 of a "Program data: " log, None for what is not one of our events
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let discriminator: [u8; 8] = data.get(..8)?.try_into().ok()?;
+        let mut event = &data[8..];
+        match discriminator {
+            COLLECTION_CREATED => <CollectionCreated as AnchorDeserialize>::deserialize(&mut event).ok().map(ProgramEvent::CollectionCreated),
+            SKIN_CREATED => <SkinCreated as AnchorDeserialize>::deserialize(&mut event).ok().map(ProgramEvent::SkinCreated),
+            SKIN_PURCHASED => <SkinPurchased as AnchorDeserialize>::deserialize(&mut event).ok().map(ProgramEvent::SkinPurchased),
+            SKIN_FUSED => <SkinFused as AnchorDeserialize>::deserialize(&mut event).ok().map(ProgramEvent::SkinFused),
+            SKIN_LISTED => <SkinListed as AnchorDeserialize>::deserialize(&mut event).ok().map(ProgramEvent::SkinListed),
+            SKIN_DELISTED => <SkinDelisted as AnchorDeserialize>::deserialize(&mut event).ok().map(ProgramEvent::SkinDelisted),
+            SKIN_TRADED => <SkinTraded as AnchorDeserialize>::deserialize(&mut event).ok().map(ProgramEvent::SkinTraded),
+            _ => None,
+        }
+    }
+
+    //What emit! logs, for tests and mocks
+    pub fn to_log_data(&self) -> Vec<u8> {
+        match self {
+            ProgramEvent::CollectionCreated(event) => instruction_data(COLLECTION_CREATED, event),
+            ProgramEvent::SkinCreated(event) => instruction_data(SKIN_CREATED, event),
+            ProgramEvent::SkinPurchased(event) => instruction_data(SKIN_PURCHASED, event),
+            ProgramEvent::SkinFused(event) => instruction_data(SKIN_FUSED, event),
+            ProgramEvent::SkinListed(event) => instruction_data(SKIN_LISTED, event),
+            ProgramEvent::SkinDelisted(event) => instruction_data(SKIN_DELISTED, event),
+            ProgramEvent::SkinTraded(event) => instruction_data(SKIN_TRADED, event),
+        }
+    }
+}
+
+//Mirror of the program's CreatorStatus
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum CreatorStatus {
+    Active,
+    Suspended,
+}
+
+//Mirror of the program's SkinListing account
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq)]
+pub struct SkinListing {
+    pub master_mint: Pubkey,
+    pub creator: Pubkey,
+    pub treasury: Pubkey,
+    //Lamports, or base units of payment_mint
+    pub price: u64,
+    //None is paid in SOL
+    pub payment_mint: Option<Pubkey>,
+    pub bump: u8,
+    //Bumped by every update_skin
+    pub revision: u32,
+    //Unix seconds, 0 is no limit
+    pub sale_start: i64,
+    pub sale_end: i64,
+    pub paused: bool,
+    //0 is no cap
+    pub max_per_wallet: u32,
+    //Until public_start only the wallets of this merkle tree can buy, see merkle
+    pub allowlist_root: Option<[u8; 32]>,
+    pub public_start: i64,
+    //Printed by buy and fuse, burns don't lower it
+    pub editions_printed: u64,
+}
+
+impl SkinListing {
+    //What is left to print, None without a cap
+    pub fn remaining(&self, max_supply: Option<u64>) -> Option<u64> {
+        max_supply.map(|max| max.saturating_sub(self.editions_printed))
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 8 || data[..8] != SKIN_LISTING {
+            return Err("Not a skin listing".to_string());
+        }
+        SkinListing::deserialize(&mut &data[8..]).map_err(|e| e.to_string())
+    }
+
+    //What the program stores, for tests and mocks
+    pub fn to_account_data(&self) -> Vec<u8> {
+        instruction_data(SKIN_LISTING, self)
+    }
+}
+
+//Prints of a master burned by a fuse
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Ingredient {
+    pub master_mint: Pubkey,
+    pub count: u8,
+}
+
+//Mirror of the program's Recipe account
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq)]
+pub struct Recipe {
+    pub upgrade_mint: Pubkey,
+    pub creator: Pubkey,
+    pub ingredients: Vec<Ingredient>,
+    pub revision: u32,
+    pub bump: u8,
+}
+
+impl Recipe {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 8 || data[..8] != RECIPE {
+            return Err("Not a recipe".to_string());
+        }
+        Recipe::deserialize(&mut &data[8..]).map_err(|e| e.to_string())
+    }
+
+    //What the program stores, for tests and mocks
+    pub fn to_account_data(&self) -> Vec<u8> {
+        instruction_data(RECIPE, self)
+    }
+}
+
+//Mirror of the program's FuseProgress account
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq)]
+pub struct FuseProgress {
+    pub player: Pubkey,
+    pub upgrade_mint: Pubkey,
+    //The recipe at the first burn, the one fuse takes
+    pub recipe_revision: u32,
+    pub ingredients: Vec<Ingredient>,
+    pub burned: Vec<Ingredient>,
+    pub bump: u8,
+}
+
+impl FuseProgress {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 8 || data[..8] != FUSE_PROGRESS {
+            return Err("Not a fuse progress".to_string());
+        }
+        FuseProgress::deserialize(&mut &data[8..]).map_err(|e| e.to_string())
+    }
+
+    //What the program stores, for tests and mocks
+    pub fn to_account_data(&self) -> Vec<u8> {
+        instruction_data(FUSE_PROGRESS, self)
+    }
+
+    //Burned prints of a master, recipe or not
+    pub fn burned(&self, master_mint: &Pubkey) -> u8 {
+        self.burned
+            .iter()
+            .find(|burned| burned.master_mint == *master_mint)
+            .map_or(0, |burned| burned.count)
+    }
+}
+
+//Mirror of the program's TradeListing account
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq)]
+pub struct TradeListing {
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    //Lamports, royalties included
+    pub price: u64,
+    pub bump: u8,
+}
+
+impl TradeListing {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < 8 || data[..8] != TRADE_LISTING {
+            return Err("Not a trade listing".to_string());
+        }
+        TradeListing::deserialize(&mut &data[8..]).map_err(|e| e.to_string())
+    }
+
+    //What the program stores, for tests and mocks
+    pub fn to_account_data(&self) -> Vec<u8> {
+        instruction_data(TRADE_LISTING, self)
+    }
+}
+
+fn instruction_data(discriminator: [u8; 8], args: impl AnchorSerialize) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+    data.extend(args.try_to_vec().unwrap());
+    data
+}
+
+//The programs every instruction ends with
+fn programs() -> [AccountMeta; 4] {
+    [
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+        AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+    ]
+}
+
+//mint is a fresh keypair, it signs too
+pub struct CreateCollection {
+    pub creator: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+}
+
+impl CreateCollection {
+    pub fn instruction(&self) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(self.creator, true),
+            AccountMeta::new(self.mint, true),
+            AccountMeta::new_readonly(pda::mint_authority(), false),
+            AccountMeta::new(pda::metadata(&self.mint), false),
+            AccountMeta::new(pda::edition(&self.mint), false),
+            AccountMeta::new(pda::associated_token(&self.creator, &self.mint), false),
+        ];
+        accounts.extend(programs());
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: instruction_data(CREATE_COLLECTION, (self.name.clone(), self.symbol.clone())),
+        }
+    }
+}
+
+//A skin, its master edition goes to the program's vault, mint signs too
+//creator must be registered by the admin, see AddCreator
+//collection_mint must come from create_collection, the program verifies the skin into it
+pub struct CreateNft {
+    pub creator: Pubkey,
+    pub mint: Pubkey,
+    pub collection_mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub metadata_uri: String,
+    pub supply: u64,
+    //Royalties on secondary sales, capped at MAX_SELLER_FEE_BASIS_POINTS
+    pub seller_fee_basis_points: u16,
+    //Sums to 100 and includes creator, empty gives everything to creator
+    pub creators: Vec<CreatorShare>,
+    //What a print costs, paid to creator until SetPrice changes it
+    pub price: u64,
+    pub payment_mint: Option<Pubkey>,
+}
+
+impl CreateNft {
+    pub fn instruction(&self) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(self.creator, true),
+            AccountMeta::new_readonly(pda::creator_record(&self.creator), false),
+            AccountMeta::new(self.mint, true),
+            AccountMeta::new_readonly(pda::mint_authority(), false),
+            AccountMeta::new(pda::vault(&self.mint), false),
+            AccountMeta::new(pda::metadata(&self.mint), false),
+            AccountMeta::new(pda::edition(&self.mint), false),
+            AccountMeta::new(self.collection_mint, false),
+            AccountMeta::new(pda::metadata(&self.collection_mint), false),
+            AccountMeta::new_readonly(pda::edition(&self.collection_mint), false),
+            AccountMeta::new(pda::listing(&self.mint), false),
+        ];
+        accounts.extend(programs());
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: instruction_data(
+                CREATE_NFT,
+                (
+                    self.name.clone(),
+                    self.symbol.clone(),
+                    self.metadata_uri.clone(),
+                    self.supply,
+                    self.seller_fee_basis_points,
+                    self.creators.clone(),
+                    self.price,
+                    self.payment_mint,
+                ),
+            ),
+        }
+    }
+}
+
+//Prints the next edition of a skin to the buyer, new_mint is a fresh keypair that signs too
+//editions_printed, treasury, price and payment_mint come from the skin's listing, the buy fails if the creator changed the price since
+//allowlist_proof is only needed in the early access of a drop, see merkle::proof
+pub struct BuyNft {
+    pub buyer: Pubkey,
+    pub master_mint: Pubkey,
+    pub new_mint: Pubkey,
+    pub editions_printed: u64,
+    pub treasury: Pubkey,
+    pub price: u64,
+    pub payment_mint: Option<Pubkey>,
+    pub allowlist_proof: Vec<[u8; 32]>,
+}
+
+impl BuyNft {
+    pub fn instruction(&self) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(self.buyer, true),
+            AccountMeta::new_readonly(self.master_mint, false),
+            AccountMeta::new_readonly(pda::mint_authority(), false),
+            AccountMeta::new(pda::vault(&self.master_mint), false),
+            AccountMeta::new(pda::metadata(&self.master_mint), false),
+            AccountMeta::new(pda::edition(&self.master_mint), false),
+            AccountMeta::new(self.new_mint, true),
+            AccountMeta::new(pda::associated_token(&self.buyer, &self.new_mint), false),
+            AccountMeta::new(pda::metadata(&self.new_mint), false),
+            AccountMeta::new(pda::edition(&self.new_mint), false),
+            AccountMeta::new(pda::edition_marker(&self.master_mint, self.editions_printed), false),
+            AccountMeta::new(pda::listing(&self.master_mint), false),
+            AccountMeta::new(self.treasury, false),
+            AccountMeta::new(pda::wallet_purchases(&self.master_mint, &self.buyer), false),
+        ];
+        //Anchor reads the program id as a missing optional account
+        accounts.extend(match self.payment_mint {
+            Some(mint) => [
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(pda::associated_token(&self.buyer, &mint), false),
+                AccountMeta::new(pda::associated_token(&self.treasury, &mint), false),
+            ],
+            None => [
+                AccountMeta::new_readonly(PROGRAM_ID, false),
+                AccountMeta::new_readonly(PROGRAM_ID, false),
+                AccountMeta::new_readonly(PROGRAM_ID, false),
+            ],
+        });
+        accounts.extend(programs());
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: instruction_data(BUY_NFT, (&self.allowlist_proof, self.price, self.payment_mint)),
+        }
+    }
+}
+
+//Only the creator of the skin can, payment_mint None is SOL
+pub struct SetPrice {
+    pub creator: Pubkey,
+    pub master_mint: Pubkey,
+    pub price: u64,
+    pub payment_mint: Option<Pubkey>,
+    pub treasury: Pubkey,
+}
+
+impl SetPrice {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(self.creator, true),
+                AccountMeta::new(pda::listing(&self.master_mint), false),
+            ],
+            data: instruction_data(SET_PRICE, (self.price, self.payment_mint, self.treasury)),
+        }
+    }
+}
+
+//The creator of a skin changes its name or metadata uri, None keeps the current one
+pub struct UpdateSkin {
+    pub creator: Pubkey,
+    pub master_mint: Pubkey,
+    pub name: Option<String>,
+    pub metadata_uri: Option<String>,
+}
+
+impl UpdateSkin {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(self.creator, true),
+                AccountMeta::new_readonly(pda::creator_record(&self.creator), false),
+                AccountMeta::new(pda::listing(&self.master_mint), false),
+                AccountMeta::new_readonly(pda::mint_authority(), false),
+                AccountMeta::new(pda::metadata(&self.master_mint), false),
+                AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+            ],
+            data: instruction_data(UPDATE_SKIN, (self.name.clone(), self.metadata_uri.clone())),
+        }
+    }
+}
+
+//The creator sets when a skin sells, timestamps in unix seconds, 0 is no limit
+pub struct SetSale {
+    pub creator: Pubkey,
+    pub master_mint: Pubkey,
+    pub sale_start: i64,
+    pub sale_end: i64,
+    pub max_per_wallet: u32,
+    pub allowlist_root: Option<[u8; 32]>,
+    pub public_start: i64,
+}
+
+impl SetSale {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(self.creator, true),
+                AccountMeta::new(pda::listing(&self.master_mint), false),
+            ],
+            data: instruction_data(
+                SET_SALE,
+                (
+                    self.sale_start,
+                    self.sale_end,
+                    self.max_per_wallet,
+                    self.allowlist_root,
+                    self.public_start,
+                ),
+            ),
+        }
+    }
+}
+
+//The creator of the skin or the admin
+pub struct SetPaused {
+    pub authority: Pubkey,
+    pub master_mint: Pubkey,
+    pub paused: bool,
+}
+
+impl SetPaused {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(self.authority, true),
+                AccountMeta::new_readonly(pda::config(), false),
+                AccountMeta::new(pda::listing(&self.master_mint), false),
+            ],
+            data: instruction_data(SET_PAUSED, self.paused),
+        }
+    }
+}
+
+//The creator of the upgrade skin sets what a fuse takes, at most MAX_RECIPE_INGREDIENTS skins
+pub struct SetRecipe {
+    pub creator: Pubkey,
+    pub upgrade_mint: Pubkey,
+    pub ingredients: Vec<Ingredient>,
+}
+
+impl SetRecipe {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.creator, true),
+                AccountMeta::new_readonly(pda::listing(&self.upgrade_mint), false),
+                AccountMeta::new(pda::recipe(&self.upgrade_mint), false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+            data: instruction_data(SET_RECIPE, &self.ingredients),
+        }
+    }
+}
+
+//The player burns a print of an ingredient toward upgrade_mint, one per transaction
+//edition is the number of the print, from its Edition account
+pub struct BurnIngredient {
+    pub player: Pubkey,
+    pub upgrade_mint: Pubkey,
+    pub print_mint: Pubkey,
+    pub master_mint: Pubkey,
+    pub edition: u64,
+}
+
+impl BurnIngredient {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.player, true),
+                AccountMeta::new_readonly(pda::recipe(&self.upgrade_mint), false),
+                AccountMeta::new(pda::fuse_progress(&self.upgrade_mint, &self.player), false),
+                AccountMeta::new(pda::metadata(&self.print_mint), false),
+                AccountMeta::new(self.print_mint, false),
+                AccountMeta::new(pda::associated_token(&self.player, &self.print_mint), false),
+                AccountMeta::new(pda::edition(&self.print_mint), false),
+                AccountMeta::new(pda::print_marker(&self.master_mint, self.edition), false),
+                AccountMeta::new_readonly(self.master_mint, false),
+                AccountMeta::new_readonly(pda::vault(&self.master_mint), false),
+                AccountMeta::new(pda::edition(&self.master_mint), false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+            ],
+            data: BURN_INGREDIENT.to_vec(),
+        }
+    }
+}
+
+//Prints the next edition of the upgrade once the burns cover the recipe, new_mint is a fresh keypair that signs too
+//editions_printed comes from the listing of the upgrade, like BuyNft
+pub struct Fuse {
+    pub player: Pubkey,
+    pub upgrade_mint: Pubkey,
+    pub new_mint: Pubkey,
+    pub editions_printed: u64,
+}
+
+impl Fuse {
+    pub fn instruction(&self) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(self.player, true),
+            AccountMeta::new_readonly(pda::recipe(&self.upgrade_mint), false),
+            AccountMeta::new(pda::fuse_progress(&self.upgrade_mint, &self.player), false),
+            AccountMeta::new_readonly(self.upgrade_mint, false),
+            AccountMeta::new_readonly(pda::mint_authority(), false),
+            AccountMeta::new(pda::vault(&self.upgrade_mint), false),
+            AccountMeta::new(pda::metadata(&self.upgrade_mint), false),
+            AccountMeta::new(pda::edition(&self.upgrade_mint), false),
+            AccountMeta::new(self.new_mint, true),
+            AccountMeta::new(pda::associated_token(&self.player, &self.new_mint), false),
+            AccountMeta::new(pda::metadata(&self.new_mint), false),
+            AccountMeta::new(pda::edition(&self.new_mint), false),
+            AccountMeta::new(pda::edition_marker(&self.upgrade_mint, self.editions_printed), false),
+            AccountMeta::new(pda::listing(&self.upgrade_mint), false),
+        ];
+        accounts.extend(programs());
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: FUSE.to_vec(),
+        }
+    }
+}
+
+//A player puts a print of the program on the market, its token goes to the escrow until sold or cancelled
+pub struct List {
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    //Lamports, the royalties come out of it
+    pub price: u64,
+}
+
+impl List {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.seller, true),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(pda::associated_token(&self.seller, &self.mint), false),
+                AccountMeta::new_readonly(pda::mint_authority(), false),
+                AccountMeta::new_readonly(pda::metadata(&self.mint), false),
+                AccountMeta::new(pda::trade_listing(&self.mint), false),
+                AccountMeta::new(pda::escrow(&self.mint), false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+            ],
+            data: instruction_data(LIST, self.price),
+        }
+    }
+}
+
+//The seller takes a listed print back
+pub struct Cancel {
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+}
+
+impl Cancel {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.seller, true),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(pda::trade_listing(&self.mint), false),
+                AccountMeta::new(pda::escrow(&self.mint), false),
+                AccountMeta::new(pda::associated_token(&self.seller, &self.mint), false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+            ],
+            data: CANCEL.to_vec(),
+        }
+    }
+}
+
+//Buys a listed print, price and seller from the trade listing
+//creators are the metadata creators with a share, in their order, they get the royalties
+pub struct PurchaseListing {
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub creators: Vec<Pubkey>,
+}
+
+impl PurchaseListing {
+    pub fn instruction(&self) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(self.buyer, true),
+            AccountMeta::new(self.seller, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(pda::trade_listing(&self.mint), false),
+            AccountMeta::new(pda::escrow(&self.mint), false),
+            AccountMeta::new(pda::associated_token(&self.buyer, &self.mint), false),
+            AccountMeta::new_readonly(pda::metadata(&self.mint), false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+        ];
+        accounts.extend(self.creators.iter().map(|creator| AccountMeta::new(*creator, false)));
+
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: instruction_data(PURCHASE_LISTING, self.price),
+        }
+    }
+}
+
+//Once, by the upgrade authority of the program
+pub struct InitializeConfig {
+    pub authority: Pubkey,
+    pub admin: Pubkey,
+}
+
+impl InitializeConfig {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.authority, true),
+                AccountMeta::new(pda::config(), false),
+                AccountMeta::new_readonly(PROGRAM_ID, false),
+                AccountMeta::new_readonly(pda::program_data(), false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+            data: instruction_data(INITIALIZE_CONFIG, self.admin),
+        }
+    }
+}
+
+pub struct SetAdmin {
+    pub admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+impl SetAdmin {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(self.admin, true),
+                AccountMeta::new(pda::config(), false),
+            ],
+            data: instruction_data(SET_ADMIN, self.new_admin),
+        }
+    }
+}
+
+//The admin registers wallet as a creator
+pub struct AddCreator {
+    pub admin: Pubkey,
+    pub wallet: Pubkey,
+}
+
+impl AddCreator {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.admin, true),
+                AccountMeta::new_readonly(pda::config(), false),
+                AccountMeta::new(pda::creator_record(&self.wallet), false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+            data: instruction_data(ADD_CREATOR, self.wallet),
+        }
+    }
+}
+
+//Suspend or reactivate a creator
+pub struct SetCreatorStatus {
+    pub admin: Pubkey,
+    pub wallet: Pubkey,
+    pub status: CreatorStatus,
+}
+
+impl SetCreatorStatus {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(self.admin, true),
+                AccountMeta::new_readonly(pda::config(), false),
+                AccountMeta::new(pda::creator_record(&self.wallet), false),
+            ],
+            data: instruction_data(SET_CREATOR_STATUS, self.status),
+        }
+    }
+}
+
+//Closes the record, its rent goes to the admin
+pub struct RemoveCreator {
+    pub admin: Pubkey,
+    pub wallet: Pubkey,
+}
+
+impl RemoveCreator {
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(self.admin, true),
+                AccountMeta::new_readonly(pda::config(), false),
+                AccountMeta::new(pda::creator_record(&self.wallet), false),
+            ],
+            data: REMOVE_CREATOR.to_vec(),
+        }
+    }
+}
+
+//Printed so far and the cap, from the master edition account data
+pub fn parse_master_edition(data: &[u8]) -> Result<MasterEdition, String> {
+    MasterEdition::deserialize(&mut &data[..]).map_err(|e| e.to_string())
+}
+
+pub fn fetch_listing(rpc: &RpcClient, master_mint: &Pubkey) -> Result<SkinListing, String> {
+    let data = rpc
+        .get_account_data(&pda::listing(master_mint))
+        .map_err(|e| e.to_string())?;
+    SkinListing::parse(&data)
+}
+
+pub fn fetch_master_edition(rpc: &RpcClient, master_mint: &Pubkey) -> Result<MasterEdition, String> {
+    let data = rpc
+        .get_account_data(&pda::edition(master_mint))
+        .map_err(|e| e.to_string())?;
+    parse_master_edition(&data)
+}
+
+//Signs with the payer first, then the fresh mints
+pub fn submit(rpc: &RpcClient, instructions: &[Instruction], payer: &Keypair, signers: &[&Keypair]) -> Result<Signature, String> {
+    let blockhash = rpc.get_latest_blockhash().map_err(|e| e.to_string())?;
+
+    let mut all_signers: Vec<&Keypair> = vec![payer];
+    all_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &all_signers, blockhash);
+
+    rpc.send_and_confirm_transaction(&transaction)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+//Jsons without schema_version are the first one, five textures and nothing else
+pub const SCHEMA_VERSION: u32 = 2;
+pub const SKIN_IDENTIFIER: &str = "fps+bq";
+//Gun models this client has, skins for anything else are skipped
+pub const WEAPONS: [&str; 1] = ["ak"];
+pub const DEFAULT_WEAPON: &str = "ak";
+
+//Every slot a skin may fill, orm is split into ao, r and m at decode
+pub const SLOTS: [&str; 7] = ["a", "r", "n", "m", "ao", "e", "orm"];
+const V1_SLOTS: [&str; 5] = ["a", "r", "n", "m", "ao"];
+const MAX_UV_SCALE: f32 = 64.0;
+
+/**
+ * The json a skin nft points to, see the README for the fields
+ * Build it with parse, a json that doesn't pass validate is not a skin
+ */
+#[derive(Deserialize, Debug)]
+pub struct SkinMetadata {
+    #[serde(default = "SkinMetadata::v1")]
+    pub schema_version: u32,
+    pub name: String,
+    pub symbol: String,
+    pub identifier: String,
+    //The gun model it is made for, the ak when missing
+    #[serde(default)]
+    pub weapon: Option<String>,
+    pub textures: TextureField,
+    //Metaplex preview image, shown in the shop
+    #[serde(default)]
+    pub image: Option<String>,
+    //Optional sha256 (hex) of each texture, pins the content the urls must serve
+    #[serde(default)]
+    pub sha256: Option<TextureField>,
+    //#rrggbb or #rrggbbaa, multiplied with the albedo
+    #[serde(default)]
+    pub tint: Option<String>,
+    //Slot -> [u, v] repeat of its texture
+    #[serde(default)]
+    pub uv_scale: Option<HashMap<String, [f32; 2]>>,
+    //where this json was fetched from, part of the cache keys
+    #[serde(skip)]
+    pub uri: String,
+}
+
+//Missing slots are filled from the default skin
+#[derive(Deserialize, Debug, Default)]
+pub struct TextureField {
+    pub a: Option<String>,
+    pub r: Option<String>,
+    pub n: Option<String>,
+    pub m: Option<String>,
+    pub ao: Option<String>,
+    //Emissive, rgb
+    pub e: Option<String>,
+    //Occlusion, roughness and metalness packed in r, g and b
+    pub orm: Option<String>,
+}
+
+impl TextureField {
+    pub fn get(&self, slot: &str) -> Option<&String> {
+        match slot {
+            "a" => self.a.as_ref(),
+            "r" => self.r.as_ref(),
+            "n" => self.n.as_ref(),
+            "m" => self.m.as_ref(),
+            "ao" => self.ao.as_ref(),
+            "e" => self.e.as_ref(),
+            "orm" => self.orm.as_ref(),
+            _ => None,
+        }
+    }
+
+    //The slots that are set, in SLOTS order
+    pub fn slots(&self) -> Vec<(&'static str, &String)> {
+        SLOTS
+            .iter()
+            .filter_map(|slot| self.get(slot).map(|value| (*slot, value)))
+            .collect()
+    }
+}
+
+//What a skin changes besides its textures, applied when the gun is drawn
+#[derive(Debug, Clone)]
+pub struct SkinStyle {
+    pub tint: [u8; 4],
+    pub uv_scale: HashMap<String, [f32; 2]>,
+}
+
+impl Default for SkinStyle {
+    fn default() -> Self {
+        Self {
+            tint: [255; 4],
+            uv_scale: HashMap::new(),
+        }
+    }
+}
+
+impl SkinStyle {
+    //1x1 for the slots without a scale, orm scales the three slots it fills
+    pub fn uv_scale(&self, slot: &str) -> [f32; 2] {
+        let packed = matches!(slot, "ao" | "r" | "m").then(|| self.uv_scale.get("orm")).flatten();
+        self.uv_scale
+            .get(slot)
+            .or(packed)
+            .copied()
+            .unwrap_or([1.0, 1.0])
+    }
+}
+
+impl SkinMetadata {
+    fn v1() -> u32 {
+        1
+    }
+
+    //Deserialize and validate, the errors are joined to one line
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let skin_data: SkinMetadata = serde_json::from_slice(bytes).map_err(|e| format!("Not a skin json: {}", e))?;
+        skin_data.validate().map_err(|errors| errors.join("; "))?;
+
+        Ok(skin_data)
+    }
+
+    pub fn weapon(&self) -> &str {
+        self.weapon.as_deref().unwrap_or(DEFAULT_WEAPON)
+    }
+
+    //Every problem at once, so a creator can fix the json in one go
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if self.identifier != SKIN_IDENTIFIER {
+            errors.push(format!("identifier is \"{}\", expected \"{}\"", self.identifier, SKIN_IDENTIFIER));
+        }
+        if self.schema_version == 0 || self.schema_version > SCHEMA_VERSION {
+            errors.push(format!(
+                "schema_version {} is not supported, this client reads 1 to {}",
+                self.schema_version, SCHEMA_VERSION
+            ));
+        }
+        if self.name.trim().is_empty() {
+            errors.push("name is empty".to_string());
+        }
+        if self.weapon.as_ref().is_some_and(|weapon| weapon.trim().is_empty()) {
+            errors.push("weapon is empty, leave it out for the ak".to_string());
+        }
+
+        let textures = self.textures.slots();
+        if textures.is_empty() {
+            errors.push("textures has no slot set".to_string());
+        }
+        for (slot, uri) in &textures {
+            if self.schema_version < 2 && !V1_SLOTS.contains(slot) {
+                errors.push(format!("textures.{} needs schema_version 2", slot));
+            }
+            if !["http://", "https://", "ipfs://", "ar://", "data:"]
+                .iter()
+                .any(|scheme| uri.trim().starts_with(scheme))
+            {
+                errors.push(format!("textures.{} \"{}\" is not an http(s), ipfs, ar or data uri", slot, uri));
+            }
+        }
+        if self.textures.orm.is_some() {
+            let packed: Vec<&str> = ["ao", "r", "m"]
+                .into_iter()
+                .filter(|slot| self.textures.get(slot).is_some())
+                .collect();
+            if !packed.is_empty() {
+                errors.push(format!("textures.orm already fills {}, set one or the other", packed.join(", ")));
+            }
+        }
+
+        if let Some(hashes) = &self.sha256 {
+            for (slot, hash) in hashes.slots() {
+                if self.textures.get(slot).is_none() {
+                    errors.push(format!("sha256.{} is set but textures.{} is not", slot, slot));
+                }
+                let hash = hash.trim();
+                if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    errors.push(format!("sha256.{} is not 64 hex characters", slot));
+                }
+            }
+        }
+
+        if self.schema_version < 2 && (self.tint.is_some() || self.uv_scale.is_some() || self.weapon.is_some()) {
+            errors.push("tint, uv_scale and weapon need schema_version 2".to_string());
+        }
+        if let Some(tint) = &self.tint
+            && let Err(e) = parse_color(tint)
+        {
+            errors.push(format!("tint {}", e));
+        }
+        if let Some(uv_scale) = &self.uv_scale {
+            for (slot, [u, v]) in uv_scale {
+                if !SLOTS.contains(&slot.as_str()) {
+                    errors.push(format!("uv_scale.{} is not a texture slot", slot));
+                }
+                if ![u, v].iter().all(|s| s.is_finite() && **s > 0.0 && **s <= MAX_UV_SCALE) {
+                    errors.push(format!("uv_scale.{} must be within (0, {}]", slot, MAX_UV_SCALE));
+                }
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    //Validated, so the tint parses
+    pub fn style(&self) -> SkinStyle {
+        SkinStyle {
+            tint: self
+                .tint
+                .as_deref()
+                .and_then(|tint| parse_color(tint).ok())
+                .unwrap_or([255; 4]),
+            uv_scale: self.uv_scale.clone().unwrap_or_default(),
+        }
+    }
+}
+
+//#rrggbb or #rrggbbaa
+fn parse_color(color: &str) -> Result<[u8; 4], String> {
+    let hex = color.trim().strip_prefix('#').unwrap_or(color.trim());
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("\"{}\" is not #rrggbb or #rrggbbaa", color));
+    }
+
+    let mut rgba = [255u8; 4];
+    for (i, channel) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+        *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+    }
+
+    Ok(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn v1() -> Value {
+        json!({
+            "name": "Cat AK",
+            "symbol": "BQ",
+            "identifier": SKIN_IDENTIFIER,
+            "textures": {
+                "a": "https://host/a.png",
+                "r": "ipfs://bafy/r.png",
+                "n": "ar://tx/n.png",
+                "m": "http://host/m.png",
+                "ao": "data:image/png;base64,iVBORw0KGgo="
+            }
+        })
+    }
+
+    fn v2() -> Value {
+        json!({
+            "schema_version": 2,
+            "name": "Turtle AK",
+            "symbol": "BQ",
+            "identifier": SKIN_IDENTIFIER,
+            "weapon": "ak",
+            "textures": { "a": "https://host/a.png", "orm": "https://host/orm.png", "e": "https://host/e.png" },
+            "sha256": { "a": "ab".repeat(32) },
+            "tint": "#ff800080",
+            "uv_scale": { "orm": [2.0, 2.0], "a": [1.0, 0.5] }
+        })
+    }
+
+    //The document with its fields set, a null takes the field out
+    fn with(mut document: Value, fields: Value) -> Vec<u8> {
+        for (field, value) in fields.as_object().unwrap() {
+            match value {
+                Value::Null => document.as_object_mut().unwrap().remove(field),
+                value => document.as_object_mut().unwrap().insert(field.clone(), value.clone()),
+            };
+        }
+        serde_json::to_vec(&document).unwrap()
+    }
+
+    #[test]
+    fn a_v1_document_has_five_textures_and_nothing_else() {
+        let skin = SkinMetadata::parse(&with(v1(), json!({}))).unwrap();
+
+        assert_eq!(skin.schema_version, 1);
+        assert_eq!(skin.weapon(), DEFAULT_WEAPON);
+        assert_eq!(skin.textures.slots().len(), 5);
+        assert_eq!(skin.style().tint, [255; 4]);
+    }
+
+    #[test]
+    fn a_v2_document_packs_orm_and_styles_the_gun() {
+        let skin = SkinMetadata::parse(&with(v2(), json!({}))).unwrap();
+        let style = skin.style();
+
+        assert_eq!(skin.schema_version, 2);
+        assert_eq!(style.tint, [255, 128, 0, 128]);
+        //orm scales the slots it is split into
+        assert_eq!(style.uv_scale("ao"), [2.0, 2.0]);
+        assert_eq!(style.uv_scale("a"), [1.0, 0.5]);
+        assert_eq!(style.uv_scale("n"), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_skin() {
+        let cases = [
+            (
+                "unknown version",
+                with(v2(), json!({ "schema_version": 3 })),
+                "schema_version 3 is not supported, this client reads 1 to 2",
+            ),
+            (
+                "version zero",
+                with(v1(), json!({ "schema_version": 0 })),
+                "schema_version 0 is not supported, this client reads 1 to 2",
+            ),
+            (
+                "another identifier",
+                with(v1(), json!({ "identifier": "other" })),
+                "identifier is \"other\", expected \"fps+bq\"",
+            ),
+            ("empty name", with(v1(), json!({ "name": " " })), "name is empty"),
+            (
+                "empty weapon",
+                with(v2(), json!({ "weapon": "" })),
+                "weapon is empty, leave it out for the ak",
+            ),
+            (
+                "no texture set",
+                with(v1(), json!({ "textures": {} })),
+                "textures has no slot set",
+            ),
+            (
+                "v2 slot in v1",
+                with(v1(), json!({ "textures": { "e": "https://host/e.png" } })),
+                "textures.e needs schema_version 2",
+            ),
+            (
+                "ftp uri",
+                with(v1(), json!({ "textures": { "a": "ftp://host/a.png" } })),
+                "textures.a \"ftp://host/a.png\" is not an http(s), ipfs, ar or data uri",
+            ),
+            (
+                "empty uri",
+                with(v1(), json!({ "textures": { "n": "" } })),
+                "textures.n \"\" is not an http(s), ipfs, ar or data uri",
+            ),
+            (
+                "relative uri",
+                with(v1(), json!({ "textures": { "m": "m.png" } })),
+                "textures.m \"m.png\" is not an http(s), ipfs, ar or data uri",
+            ),
+            (
+                "orm and its slots",
+                with(
+                    v2(),
+                    json!({ "textures": { "a": "https://host/a.png", "orm": "https://host/orm.png", "r": "https://host/r.png" } }),
+                ),
+                "textures.orm already fills r, set one or the other",
+            ),
+            (
+                "hash of no texture",
+                with(v2(), json!({ "sha256": { "n": "ab".repeat(32) } })),
+                "sha256.n is set but textures.n is not",
+            ),
+            (
+                "short hash",
+                with(v2(), json!({ "sha256": { "a": "abcd" } })),
+                "sha256.a is not 64 hex characters",
+            ),
+            (
+                "v2 fields in v1",
+                with(v1(), json!({ "tint": "#ffffff" })),
+                "tint, uv_scale and weapon need schema_version 2",
+            ),
+            (
+                "bad tint",
+                with(v2(), json!({ "tint": "red" })),
+                "tint \"red\" is not #rrggbb or #rrggbbaa",
+            ),
+            (
+                "scale of no slot",
+                with(v2(), json!({ "uv_scale": { "x": [1.0, 1.0] } })),
+                "uv_scale.x is not a texture slot",
+            ),
+            (
+                "zero scale",
+                with(v2(), json!({ "uv_scale": { "a": [0.0, 1.0] } })),
+                "uv_scale.a must be within (0, 64]",
+            ),
+            (
+                "huge scale",
+                with(v2(), json!({ "uv_scale": { "a": [1.0, 65.0] } })),
+                "uv_scale.a must be within (0, 64]",
+            ),
+        ];
+
+        for (name, document, expected) in cases {
+            assert_eq!(SkinMetadata::parse(&document).unwrap_err(), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn a_missing_field_is_not_a_skin_json() {
+        let error = SkinMetadata::parse(&with(v1(), json!({ "textures": null }))).unwrap_err();
+        assert!(
+            error.starts_with("Not a skin json: missing field `textures`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn reports_every_problem_at_once() {
+        let document = with(
+            v1(),
+            json!({ "name": "", "textures": { "a": "ftp://host/a.png", "e": "https://host/e.png" } }),
+        );
+        let skin: SkinMetadata = serde_json::from_slice(&document).unwrap();
+
+        assert_eq!(
+            skin.validate().unwrap_err(),
+            vec![
+                "name is empty",
+                "textures.a \"ftp://host/a.png\" is not an http(s), ipfs, ar or data uri",
+                "textures.e needs schema_version 2",
+            ]
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use std::io::Cursor;
+
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageReader, Limits};
+
+//Anything above this is not a gun skin
+pub const MAX_TEXTURE_BYTES: usize = 16 * 1024 * 1024;
+pub const MAX_TEXTURE_DIMENSION: u32 = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureFormat {
+    Png,
+    Jpeg,
+    WebP,
+    Ktx2,
+}
+
+#[derive(Debug)]
+pub struct TextureInfo {
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+//Tightly packed RGBA8, ready for the upload
+pub struct DecodedTexture {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl DecodedTexture {
+    //One channel as a grey texture, for the packed orm
+    pub fn channel(&self, channel: usize) -> DecodedTexture {
+        let rgba = self
+            .rgba
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[channel], pixel[channel], pixel[channel], 255])
+            .collect();
+
+        DecodedTexture {
+            width: self.width,
+            height: self.height,
+            rgba,
+        }
+    }
+}
+
+impl TextureFormat {
+    //By the magic bytes, the extension in the url means nothing
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            return Some(TextureFormat::Png);
+        }
+        if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            return Some(TextureFormat::Jpeg);
+        }
+        if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            return Some(TextureFormat::WebP);
+        }
+        if bytes.starts_with(b"\xABKTX 20\xBB\r\n\x1A\n") {
+            return Some(TextureFormat::Ktx2);
+        }
+        None
+    }
+}
+
+/**
+ * Cheap checks on the header only, so a decompression bomb is refused before anything is allocated
+ */
+pub fn inspect(bytes: &[u8]) -> Result<TextureInfo, String> {
+    if bytes.len() > MAX_TEXTURE_BYTES {
+        return Err(format!("Texture is {} bytes, the limit is {}", bytes.len(), MAX_TEXTURE_BYTES));
+    }
+
+    let format = TextureFormat::sniff(bytes).ok_or("Unknown texture format")?;
+    let (width, height) = match format {
+        TextureFormat::Png => png_size(bytes),
+        TextureFormat::Jpeg => jpeg_size(bytes),
+        TextureFormat::WebP => webp_size(bytes),
+        TextureFormat::Ktx2 => ktx2_size(bytes),
+    }
+    .ok_or(format!("Corrupted {:?} header", format))?;
+
+    if width == 0 || height == 0 {
+        return Err("Empty texture".to_string());
+    }
+    if width > MAX_TEXTURE_DIMENSION || height > MAX_TEXTURE_DIMENSION {
+        return Err(format!(
+            "Texture is {}x{}, the limit is {}x{}",
+            width, height, MAX_TEXTURE_DIMENSION, MAX_TEXTURE_DIMENSION
+        ));
+    }
+
+    Ok(TextureInfo { format, width, height })
+}
+
+//Validates, decodes and scales the texture down to power of two sides for the mipmaps
+pub fn sanitize(bytes: &[u8]) -> Result<DecodedTexture, String> {
+    let info = inspect(bytes)?;
+
+    let format = match info.format {
+        TextureFormat::Png => ImageFormat::Png,
+        TextureFormat::Jpeg => ImageFormat::Jpeg,
+        TextureFormat::WebP => ImageFormat::WebP,
+        //Would need a basis transcoder
+        TextureFormat::Ktx2 => return Err("KTX2 textures are not supported yet".to_string()),
+    };
+
+    //The header could lie, the decoder enforces it again
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_TEXTURE_DIMENSION);
+    limits.max_image_height = Some(MAX_TEXTURE_DIMENSION);
+    limits.max_alloc = Some((MAX_TEXTURE_DIMENSION * MAX_TEXTURE_DIMENSION * 4) as u64);
+
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+    let mut img = reader.decode().map_err(|e| e.to_string())?.to_rgba8();
+
+    let (width, height) = (
+        previous_power_of_two(img.width()),
+        previous_power_of_two(img.height()),
+    );
+    if (width, height) != img.dimensions() {
+        img = image::imageops::resize(&img, width, height, FilterType::Triangle);
+    }
+
+    Ok(DecodedTexture {
+        width,
+        height,
+        rgba: img.into_raw(),
+    })
+}
+
+fn previous_power_of_two(n: u32) -> u32 {
+    if n.is_power_of_two() {
+        return n;
+    }
+    1 << (31 - n.leading_zeros())
+}
+
+fn be_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+//IHDR is always the first chunk
+fn png_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some((be_u32(bytes, 16)?, be_u32(bytes, 20)?))
+}
+
+//Walk the segments until a start of frame
+fn jpeg_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+    loop {
+        while *bytes.get(i)? != 0xFF {
+            i += 1;
+        }
+        while *bytes.get(i)? == 0xFF {
+            i += 1;
+        }
+        let marker = *bytes.get(i)?;
+        i += 1;
+
+        //Standalone markers, no length
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            continue;
+        }
+
+        let length = u16::from_be_bytes(bytes.get(i..i + 2)?.try_into().ok()?) as usize;
+        let is_sof = (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker);
+        if is_sof {
+            let height = u16::from_be_bytes(bytes.get(i + 3..i + 5)?.try_into().ok()?);
+            let width = u16::from_be_bytes(bytes.get(i + 5..i + 7)?.try_into().ok()?);
+            return Some((width as u32, height as u32));
+        }
+        i += length;
+    }
+}
+
+fn webp_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    match bytes.get(12..16)? {
+        //Lossy, the frame header follows the start code
+        b"VP8 " => {
+            let width = u16::from_le_bytes(bytes.get(26..28)?.try_into().ok()?) & 0x3FFF;
+            let height = u16::from_le_bytes(bytes.get(28..30)?.try_into().ok()?) & 0x3FFF;
+            Some((width as u32, height as u32))
+        }
+        //Lossless, 14 bits each minus one after the signature
+        b"VP8L" => {
+            let bits = le_u32(bytes, 21)?;
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        //Extended, 24 bits each minus one
+        b"VP8X" => {
+            let b = bytes.get(24..30)?;
+            let width = u32::from_le_bytes([b[0], b[1], b[2], 0]) + 1;
+            let height = u32::from_le_bytes([b[3], b[4], b[5], 0]) + 1;
+            Some((width, height))
+        }
+        _ => None,
+    }
+}
+
+//identifier(12) vkFormat(4) typeSize(4) pixelWidth(4) pixelHeight(4)
+fn ktx2_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    Some((le_u32(bytes, 20)?, le_u32(bytes, 24)?.max(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, RgbImage};
+
+    use super::*;
+
+    fn encode(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, image::Rgb([10, 20, 30])));
+        let mut bytes = Cursor::new(Vec::new());
+        img.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    fn riff(chunk: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut bytes = b"RIFF\0\0\0\0WEBP".to_vec();
+        bytes.extend_from_slice(chunk);
+        bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    //Frame tag then the start code, the sizes come after
+    fn webp_lossy(width: u16, height: u16) -> Vec<u8> {
+        let mut payload = vec![0, 0, 0, 0x9D, 0x01, 0x2A];
+        payload.extend_from_slice(&width.to_le_bytes());
+        payload.extend_from_slice(&height.to_le_bytes());
+        riff(b"VP8 ", &payload)
+    }
+
+    fn webp_extended(width: u32, height: u32) -> Vec<u8> {
+        let mut payload = vec![0; 4];
+        payload.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+        payload.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+        riff(b"VP8X", &payload)
+    }
+
+    fn ktx2(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = b"\xABKTX 20\xBB\r\n\x1A\n".to_vec();
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn sniffs_the_magic_bytes() {
+        let cases = [
+            ("png", encode(2, 2, ImageFormat::Png), Some(TextureFormat::Png)),
+            ("jpeg", encode(2, 2, ImageFormat::Jpeg), Some(TextureFormat::Jpeg)),
+            ("webp", encode(2, 2, ImageFormat::WebP), Some(TextureFormat::WebP)),
+            ("ktx2", ktx2(2, 2), Some(TextureFormat::Ktx2)),
+            ("empty", vec![], None),
+            ("text", b"<html>not a texture</html>".to_vec(), None),
+            ("riff but not webp", b"RIFF\0\0\0\0WAVEfmt ".to_vec(), None),
+            ("short riff", b"RIFF\0\0\0\0WEB".to_vec(), None),
+        ];
+
+        for (name, bytes, expected) in cases {
+            assert_eq!(TextureFormat::sniff(&bytes), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn reads_the_size_from_the_header() {
+        let cases = [
+            ("png", encode(3, 5, ImageFormat::Png), TextureFormat::Png, (3, 5)),
+            ("jpeg", encode(7, 2, ImageFormat::Jpeg), TextureFormat::Jpeg, (7, 2)),
+            ("webp lossless", encode(6, 9, ImageFormat::WebP), TextureFormat::WebP, (6, 9)),
+            ("webp lossy", webp_lossy(640, 480), TextureFormat::WebP, (640, 480)),
+            ("webp extended", webp_extended(1024, 16), TextureFormat::WebP, (1024, 16)),
+            ("ktx2", ktx2(256, 128), TextureFormat::Ktx2, (256, 128)),
+        ];
+
+        for (name, bytes, format, size) in cases {
+            let info = inspect(&bytes).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert_eq!(info.format, format, "{}", name);
+            assert_eq!((info.width, info.height), size, "{}", name);
+        }
+    }
+
+    #[test]
+    fn refuses_truncated_and_oversized_headers() {
+        let png = encode(3, 5, ImageFormat::Png);
+        let jpeg = encode(3, 5, ImageFormat::Jpeg);
+        let cases = [
+            ("png cut in the IHDR", png[..20].to_vec(), "Corrupted Png header".to_string()),
+            ("jpeg without a frame", jpeg[..4].to_vec(), "Corrupted Jpeg header".to_string()),
+            ("webp cut after the chunk", webp_lossy(4, 4)[..16].to_vec(), "Corrupted WebP header".to_string()),
+            ("ktx2 without its size", ktx2(4, 4)[..22].to_vec(), "Corrupted Ktx2 header".to_string()),
+            ("unknown", b"GIF89a".to_vec(), "Unknown texture format".to_string()),
+            ("zero wide", webp_lossy(0, 4), "Empty texture".to_string()),
+            ("too wide", webp_lossy(8192, 16), "Texture is 8192x16, the limit is 4096x4096".to_string()),
+            ("too high", webp_extended(16, 4097), "Texture is 16x4097, the limit is 4096x4096".to_string()),
+            (
+                "too many bytes",
+                vec![0; MAX_TEXTURE_BYTES + 1],
+                format!("Texture is {} bytes, the limit is {}", MAX_TEXTURE_BYTES + 1, MAX_TEXTURE_BYTES),
+            ),
+        ];
+
+        for (name, bytes, expected) in cases {
+            assert_eq!(inspect(&bytes).unwrap_err(), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn ktx2_is_refused_before_decoding() {
+        assert_eq!(sanitize(&ktx2(4, 4)).err().unwrap(), "KTX2 textures are not supported yet");
+    }
+
+    #[test]
+    fn scales_down_to_powers_of_two() {
+        let cases = [((4, 4), (4, 4)), ((5, 3), (4, 2)), ((1, 7), (1, 4)), ((100, 64), (64, 64))];
+
+        for ((width, height), expected) in cases {
+            for format in [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP] {
+                let texture = sanitize(&encode(width, height, format)).unwrap();
+                assert_eq!((texture.width, texture.height), expected, "{}x{} {:?}", width, height, format);
+                assert_eq!(texture.rgba.len(), (expected.0 * expected.1 * 4) as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn previous_powers_of_two() {
+        for (n, expected) in [(1, 1), (2, 2), (3, 2), (4096, 4096), (4095, 2048), (u32::MAX, 1 << 31)] {
+            assert_eq!(previous_power_of_two(n), expected, "{}", n);
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::instruction::Instruction;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{Keypair, Signature, Signer};
+
+use crate::nft_program::{self, AddCreator, CreatorStatus, InitializeConfig, RemoveCreator, SetAdmin, SetCreatorStatus};
+
+pub const ACTIONS: [&str; 6] = ["init", "set-admin", "add", "suspend", "resume", "remove"];
+
+//init makes wallet the admin and needs the upgrade authority, the rest need the admin
+pub fn run(rpc: &RpcClient, signer: &Keypair, action: &str, wallet: Pubkey) -> Result<Signature, String> {
+    let admin = signer.pubkey();
+
+    let instruction: Instruction = match action {
+        "init" => InitializeConfig { authority: admin, admin: wallet }.instruction(),
+        "set-admin" => SetAdmin { admin, new_admin: wallet }.instruction(),
+        "add" => AddCreator { admin, wallet }.instruction(),
+        "suspend" => SetCreatorStatus { admin, wallet, status: CreatorStatus::Suspended }.instruction(),
+        "resume" => SetCreatorStatus { admin, wallet, status: CreatorStatus::Active }.instruction(),
+        "remove" => RemoveCreator { admin, wallet }.instruction(),
+        _ => return Err(format!("Unknown action {}", action)),
+    };
+
+    nft_program::submit(rpc, &[instruction], signer, &[])
+}
@@ -0,0 +1,153 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::instruction::Instruction;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{Keypair, Signature, Signer};
+
+use crate::nft_program::{self, CreateNft, CreatorShare, Ingredient, SetPaused, SetPrice, SetRecipe, SetSale, UpdateSkin};
+
+//Creates the master edition of a skin, returns its mint
+#[allow(clippy::too_many_arguments)]
+pub fn create_nft(
+    rpc: &RpcClient,
+    creator: &Keypair,
+    collection_mint: &Pubkey,
+    name: &str,
+    symbol: &str,
+    metadata_uri: &str,
+    supply: u64,
+    royalties: (u16, Vec<CreatorShare>),
+    price: (u64, Option<Pubkey>),
+) -> Result<(Pubkey, Signature), String> {
+    let mint = Keypair::new();
+
+    let instruction = create_instruction(
+        &creator.pubkey(),
+        &mint.pubkey(),
+        collection_mint,
+        name,
+        symbol,
+        metadata_uri,
+        supply,
+        royalties,
+        price,
+    );
+    let signature = nft_program::submit(rpc, &[instruction], creator, &[&mint])?;
+
+    Ok((mint.pubkey(), signature))
+}
+
+//Listed at price from the start, paid to the creator
+#[allow(clippy::too_many_arguments)]
+pub fn create_instruction(
+    creator: &Pubkey,
+    mint: &Pubkey,
+    collection_mint: &Pubkey,
+    name: &str,
+    symbol: &str,
+    metadata_uri: &str,
+    supply: u64,
+    royalties: (u16, Vec<CreatorShare>),
+    price: (u64, Option<Pubkey>),
+) -> Instruction {
+    CreateNft {
+        creator: *creator,
+        mint: *mint,
+        collection_mint: *collection_mint,
+        name: name.to_string(),
+        symbol: symbol.to_string(),
+        metadata_uri: metadata_uri.to_string(),
+        supply,
+        seller_fee_basis_points: royalties.0,
+        creators: royalties.1,
+        price: price.0,
+        payment_mint: price.1,
+    }
+    .instruction()
+}
+
+//Changes the price of a skin, the creator signs and gets paid unless treasury says otherwise
+pub fn set_price(
+    rpc: &RpcClient,
+    creator: &Keypair,
+    master_mint: &Pubkey,
+    price: u64,
+    payment_mint: Option<Pubkey>,
+    treasury: Option<Pubkey>,
+) -> Result<Signature, String> {
+    let instruction = SetPrice {
+        creator: creator.pubkey(),
+        master_mint: *master_mint,
+        price,
+        payment_mint,
+        treasury: treasury.unwrap_or(creator.pubkey()),
+    }
+    .instruction();
+
+    nft_program::submit(rpc, &[instruction], creator, &[])
+}
+
+//Points a skin to a new json or renames it, the game drops its cached copy on the new revision
+pub fn update_skin(
+    rpc: &RpcClient,
+    creator: &Keypair,
+    master_mint: &Pubkey,
+    name: Option<String>,
+    metadata_uri: Option<String>,
+) -> Result<Signature, String> {
+    let instruction = UpdateSkin {
+        creator: creator.pubkey(),
+        master_mint: *master_mint,
+        name,
+        metadata_uri,
+    }
+    .instruction();
+
+    nft_program::submit(rpc, &[instruction], creator, &[])
+}
+
+//Sale window, per wallet cap and allowlist of a skin, replaces the previous ones
+pub fn set_sale(
+    rpc: &RpcClient,
+    creator: &Keypair,
+    master_mint: &Pubkey,
+    window: (i64, i64),
+    max_per_wallet: u32,
+    allowlist: Option<([u8; 32], i64)>,
+) -> Result<Signature, String> {
+    let instruction = SetSale {
+        creator: creator.pubkey(),
+        master_mint: *master_mint,
+        sale_start: window.0,
+        sale_end: window.1,
+        max_per_wallet,
+        allowlist_root: allowlist.map(|(root, _)| root),
+        public_start: allowlist.map(|(_, public_start)| public_start).unwrap_or(0),
+    }
+    .instruction();
+
+    nft_program::submit(rpc, &[instruction], creator, &[])
+}
+
+//The creator or the admin
+pub fn set_paused(rpc: &RpcClient, authority: &Keypair, master_mint: &Pubkey, paused: bool) -> Result<Signature, String> {
+    let instruction = SetPaused {
+        authority: authority.pubkey(),
+        master_mint: *master_mint,
+        paused,
+    }
+    .instruction();
+
+    nft_program::submit(rpc, &[instruction], authority, &[])
+}
+
+//What players burn to fuse a print of upgrade_mint, replaces the previous recipe
+pub fn set_recipe(rpc: &RpcClient, creator: &Keypair, upgrade_mint: &Pubkey, ingredients: Vec<Ingredient>) -> Result<Signature, String> {
+    let instruction = SetRecipe {
+        creator: creator.pubkey(),
+        upgrade_mint: *upgrade_mint,
+        ingredients,
+    }
+    .instruction();
+
+    nft_program::submit(rpc, &[instruction], creator, &[])
+}
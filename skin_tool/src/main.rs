@@ -0,0 +1,631 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::str::FromStr;
+
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+//Shared with the client, so both agree on what a skin is
+use skin_common::{merkle, nft_program, skin_schema, texture_validation};
+use nft_program::{CreatorShare, Ingredient};
+use skin_schema::{SkinMetadata, DEFAULT_WEAPON, SCHEMA_VERSION, SKIN_IDENTIFIER};
+
+mod skin_dir;
+use skin_dir::SkinDir;
+
+mod storage;
+use storage::Storage;
+
+mod create_nft;
+
+mod admin;
+
+const DEFAULT_RPC: &str = "https://api.devnet.solana.com";
+
+const USAGE: &str = "Usage:
+  skin_tool validate <dir> [--weapon <weapon>]
+  skin_tool package <dir> --name <name> --symbol <symbol> --storage <dir|url> [--public-url <url>] [--weapon <weapon>]
+  skin_tool publish <dir> --name <name> --symbol <symbol> --storage <dir|url> --supply <supply> --collection <mint>
+                    --price <amount> [--payment-mint <mint>] [--public-url <url>] [--weapon <weapon>] [--keypair <path>]
+                    [--rpc <url>] [--royalty-bps <bps> [--creator <wallet>:<share>]...]
+  skin_tool price <master_mint> --price <amount> [--payment-mint <mint>] [--treasury <wallet>] [--keypair <path>] [--rpc <url>]
+  skin_tool update <master_mint> [--name <name>] [--uri <metadata_uri>] [--keypair <path>] [--rpc <url>]
+  skin_tool sale <master_mint> [--start <unix>] [--end <unix>] [--max-per-wallet <n>] [--allowlist <file> --public-start <unix>]
+                 [--keypair <path>] [--rpc <url>]
+  skin_tool <pause|unpause> <master_mint> [--keypair <path>] [--rpc <url>]
+  skin_tool recipe <upgrade_master_mint> --ingredient <master_mint>:<count>... [--keypair <path>] [--rpc <url>]
+  skin_tool admin <init|set-admin|add|suspend|resume|remove> [wallet] [--keypair <path>] [--rpc <url>]
+
+--price is in lamports, or base units of --payment-mint when paid in an spl token, 0 for a free skin
+--creator splits the royalties, shares sum to 100 and include the signer, all to the signer when left out
+--start, --end and --public-start are unix seconds, 0 or left out is no limit
+--allowlist is one wallet per line, they can buy before --public-start, hand the same file to the players
+--ingredient is a skin whose prints the players burn to fuse a print of the upgrade, replaces the previous recipe";
+
+//The value following a flag
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+}
+
+fn required<'a>(args: &'a [String], flag: &str) -> &'a String {
+    arg_value(args, flag).unwrap_or_else(|| {
+        println!("Missing {}\n{}", flag, USAGE);
+        exit(2);
+    })
+}
+
+fn pubkey_arg(args: &[String], flag: &str) -> Option<Pubkey> {
+    arg_value(args, flag).map(|value| {
+        Pubkey::from_str(value).unwrap_or_else(|e| {
+            println!("{}: {}", flag, e);
+            exit(2);
+        })
+    })
+}
+
+fn price_arg(args: &[String]) -> Option<u64> {
+    arg_value(args, "--price").map(|value| {
+        value.parse::<u64>().unwrap_or_else(|e| {
+            println!("--price: {}", e);
+            exit(2);
+        })
+    })
+}
+
+//--royalty-bps and every --creator <wallet>:<share>, checked like the program does
+fn royalties_arg(args: &[String]) -> Result<(u16, Vec<CreatorShare>), String> {
+    let bps = match arg_value(args, "--royalty-bps") {
+        Some(bps) => bps.parse::<u16>().map_err(|e| format!("--royalty-bps: {}", e))?,
+        None => 0,
+    };
+    if bps > nft_program::MAX_SELLER_FEE_BASIS_POINTS {
+        return Err(format!("--royalty-bps is capped at {}", nft_program::MAX_SELLER_FEE_BASIS_POINTS));
+    }
+
+    let creators = args
+        .iter()
+        .enumerate()
+        .filter(|(_, arg)| *arg == "--creator")
+        .map(|(i, _)| {
+            let value = args.get(i + 1).ok_or("--creator needs <wallet>:<share>")?;
+            let (wallet, share) = value.split_once(':').ok_or(format!("--creator {} is not <wallet>:<share>", value))?;
+            Ok(CreatorShare {
+                address: Pubkey::from_str(wallet).map_err(|e| format!("--creator {}: {}", wallet, e))?,
+                share: share.parse().map_err(|e| format!("--creator {}: {}", value, e))?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if !creators.is_empty() {
+        if creators.len() > nft_program::MAX_CREATORS {
+            return Err(format!("At most {} --creator", nft_program::MAX_CREATORS));
+        }
+        if creators.iter().map(|c| c.share as u32).sum::<u32>() != 100 {
+            return Err("The --creator shares must sum to 100".to_string());
+        }
+    }
+
+    Ok((bps, creators))
+}
+
+//Every --ingredient <master_mint>:<count>, checked like the program does
+fn ingredients_arg(args: &[String], upgrade_mint: &Pubkey) -> Result<Vec<Ingredient>, String> {
+    let ingredients = args
+        .iter()
+        .enumerate()
+        .filter(|(_, arg)| *arg == "--ingredient")
+        .map(|(i, _)| {
+            let value = args.get(i + 1).ok_or("--ingredient needs <master_mint>:<count>")?;
+            let (mint, count) = value.split_once(':').ok_or(format!("--ingredient {} is not <master_mint>:<count>", value))?;
+            Ok(Ingredient {
+                master_mint: Pubkey::from_str(mint).map_err(|e| format!("--ingredient {}: {}", mint, e))?,
+                count: count.parse().map_err(|e| format!("--ingredient {}: {}", value, e))?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if ingredients.is_empty() || ingredients.len() > nft_program::MAX_RECIPE_INGREDIENTS {
+        return Err(format!("1 to {} --ingredient", nft_program::MAX_RECIPE_INGREDIENTS));
+    }
+    for (i, ingredient) in ingredients.iter().enumerate() {
+        if ingredient.count == 0 {
+            return Err(format!("--ingredient {} takes no print", ingredient.master_mint));
+        }
+        if ingredient.master_mint == *upgrade_mint {
+            return Err("The upgrade can't be its own ingredient".to_string());
+        }
+        if ingredients[..i].iter().any(|other| other.master_mint == ingredient.master_mint) {
+            return Err(format!("--ingredient {} is there twice", ingredient.master_mint));
+        }
+    }
+
+    Ok(ingredients)
+}
+
+//Solana CLI keypair by default
+fn keypair(args: &[String]) -> Keypair {
+    let keypair_path = arg_value(args, "--keypair").map(PathBuf::from).unwrap_or_else(|| {
+        dirs::home_dir()
+            .unwrap_or_default()
+            .join(".config/solana/id.json")
+    });
+    read_keypair_file(&keypair_path).unwrap_or_else(|e| {
+        println!("Keypair {}: {}", keypair_path.display(), e);
+        exit(1);
+    })
+}
+
+fn rpc(args: &[String]) -> RpcClient {
+    RpcClient::new(arg_value(args, "--rpc").map(String::as_str).unwrap_or(DEFAULT_RPC).to_string())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+//Prints what is wrong with the folder, exits on errors
+fn validate(dir: &Path, weapon: &str) -> SkinDir {
+    match SkinDir::load(dir, weapon) {
+        Ok(skin) => {
+            for texture in &skin.textures {
+                println!(
+                    "{:>4}  {}  {:?} {}x{}",
+                    texture.slot,
+                    texture.path.display(),
+                    texture.info.format,
+                    texture.info.width,
+                    texture.info.height
+                );
+            }
+            skin.warnings.iter().for_each(|warning| println!("warning: {}", warning));
+            skin
+        }
+        Err(errors) => {
+            errors.iter().for_each(|error| println!("error: {}", error));
+            exit(1);
+        }
+    }
+}
+
+//Uploads the textures then the json pointing to them, returns the json uri
+fn package(skin: &SkinDir, storage: &dyn Storage, name: &str, symbol: &str, weapon: &str) -> Result<String, String> {
+    let mut textures = serde_json::Map::new();
+    let mut hashes = serde_json::Map::new();
+
+    for texture in &skin.textures {
+        let hash = sha256_hex(&texture.bytes);
+        let uri = storage.put(
+            &format!("{}.{}", hash, texture.extension()),
+            &texture.bytes,
+            texture.content_type(),
+        )?;
+        println!("Uploaded {} -> {}", texture.slot, uri);
+
+        textures.insert(texture.slot.clone(), uri.into());
+        hashes.insert(texture.slot.clone(), hash.into());
+    }
+
+    let image = match &skin.preview {
+        Some(preview) => storage.put(
+            &format!("{}.{}", sha256_hex(&preview.bytes), preview.extension()),
+            &preview.bytes,
+            preview.content_type(),
+        )?,
+        None => String::new(),
+    };
+
+    let mut metadata = json!({
+        "name": name,
+        "symbol": symbol,
+        "description": "",
+        "image": image,
+        "attributes": [],
+        "properties": {
+            "files": if image.is_empty() { json!([]) } else { json!([{ "type": "image/png", "uri": image }]) },
+        },
+        "schema_version": SCHEMA_VERSION,
+        "identifier": SKIN_IDENTIFIER,
+        "textures": textures,
+        "sha256": hashes,
+    });
+    if weapon != DEFAULT_WEAPON {
+        metadata["weapon"] = weapon.into();
+    }
+
+    let bytes = serde_json::to_vec_pretty(&metadata).map_err(|e| e.to_string())?;
+    //Same check as the client, a json it would skip is never uploaded
+    SkinMetadata::parse(&bytes)?;
+
+    storage.put(&format!("{}.json", sha256_hex(&bytes)), &bytes, "application/json")
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() < 3 {
+        println!("{}", USAGE);
+        exit(2);
+    }
+
+    let command = args[1].as_str();
+
+    //The creator registry, wallet defaults to the keypair's (init makes it the admin)
+    if command == "admin" {
+        let action = args[2].as_str();
+        if !admin::ACTIONS.contains(&action) {
+            println!("Unknown action {}\n{}", action, USAGE);
+            exit(2);
+        }
+        let signer = keypair(&args);
+        let wallet = match args.get(3).filter(|arg| !arg.starts_with("--")) {
+            Some(wallet) => Pubkey::from_str(wallet).unwrap_or_else(|e| {
+                println!("{}: {}", wallet, e);
+                exit(2);
+            }),
+            None => signer.pubkey(),
+        };
+
+        match admin::run(&rpc(&args), &signer, action, wallet) {
+            Ok(signature) => println!("Done, transaction {}", signature),
+            Err(e) => {
+                println!("{} failed: {}", action, e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    if command == "update" {
+        let master_mint = Pubkey::from_str(&args[2]).unwrap_or_else(|e| {
+            println!("{}: {}", args[2], e);
+            exit(2);
+        });
+
+        match create_nft::update_skin(
+            &rpc(&args),
+            &keypair(&args),
+            &master_mint,
+            arg_value(&args, "--name").cloned(),
+            arg_value(&args, "--uri").cloned(),
+        ) {
+            Ok(signature) => println!("Skin updated, transaction {}", signature),
+            Err(e) => {
+                println!("update_skin failed: {}", e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    if command == "sale" {
+        let master_mint = Pubkey::from_str(&args[2]).unwrap_or_else(|e| {
+            println!("{}: {}", args[2], e);
+            exit(2);
+        });
+        let number = |flag: &str| {
+            arg_value(&args, flag).map_or(0, |value| {
+                value.parse::<i64>().unwrap_or_else(|e| {
+                    println!("{}: {}", flag, e);
+                    exit(2);
+                })
+            })
+        };
+        let max_per_wallet = u32::try_from(number("--max-per-wallet")).unwrap_or_else(|e| {
+            println!("--max-per-wallet: {}", e);
+            exit(2);
+        });
+
+        let allowlist = arg_value(&args, "--allowlist").map(|path| {
+            let wallets = fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|list| {
+                    list.lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .map(|line| Pubkey::from_str(line).map_err(|e| format!("{}: {}", line, e)))
+                        .collect::<Result<Vec<_>, String>>()
+                })
+                .unwrap_or_else(|e| {
+                    println!("--allowlist {}: {}", path, e);
+                    exit(2);
+                });
+            let root = merkle::root(&wallets).unwrap_or_else(|| {
+                println!("--allowlist {} is empty", path);
+                exit(2);
+            });
+            println!("Allowlist of {} wallets", wallets.len());
+            (root, number("--public-start"))
+        });
+
+        match create_nft::set_sale(
+            &rpc(&args),
+            &keypair(&args),
+            &master_mint,
+            (number("--start"), number("--end")),
+            max_per_wallet,
+            allowlist,
+        ) {
+            Ok(signature) => println!("Sale set, transaction {}", signature),
+            Err(e) => {
+                println!("set_sale failed: {}", e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    if command == "pause" || command == "unpause" {
+        let master_mint = Pubkey::from_str(&args[2]).unwrap_or_else(|e| {
+            println!("{}: {}", args[2], e);
+            exit(2);
+        });
+
+        match create_nft::set_paused(&rpc(&args), &keypair(&args), &master_mint, command == "pause") {
+            Ok(signature) => println!("Done, transaction {}", signature),
+            Err(e) => {
+                println!("set_paused failed: {}", e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    if command == "recipe" {
+        let upgrade_mint = Pubkey::from_str(&args[2]).unwrap_or_else(|e| {
+            println!("{}: {}", args[2], e);
+            exit(2);
+        });
+        let ingredients = ingredients_arg(&args, &upgrade_mint).unwrap_or_else(|e| {
+            println!("{}", e);
+            exit(2);
+        });
+
+        match create_nft::set_recipe(&rpc(&args), &keypair(&args), &upgrade_mint, ingredients) {
+            Ok(signature) => println!("Recipe set, transaction {}", signature),
+            Err(e) => {
+                println!("set_recipe failed: {}", e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    if command == "price" {
+        let master_mint = Pubkey::from_str(&args[2]).unwrap_or_else(|e| {
+            println!("{}: {}", args[2], e);
+            exit(2);
+        });
+        let price = price_arg(&args).unwrap_or_else(|| {
+            println!("Missing --price\n{}", USAGE);
+            exit(2);
+        });
+
+        match create_nft::set_price(
+            &rpc(&args),
+            &keypair(&args),
+            &master_mint,
+            price,
+            pubkey_arg(&args, "--payment-mint"),
+            pubkey_arg(&args, "--treasury"),
+        ) {
+            Ok(signature) => println!("Price set, transaction {}", signature),
+            Err(e) => {
+                println!("set_price failed: {}", e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    let dir = PathBuf::from(&args[2]);
+    let weapon = arg_value(&args, "--weapon").map(String::as_str).unwrap_or(DEFAULT_WEAPON);
+
+    if !["validate", "package", "publish"].contains(&command) {
+        println!("Unknown command {}\n{}", command, USAGE);
+        exit(2);
+    }
+
+    let skin = validate(&dir, weapon);
+    if command == "validate" {
+        println!("{} is a valid skin", dir.display());
+        return;
+    }
+
+    let name = required(&args, "--name");
+    let symbol = required(&args, "--symbol");
+    //Check the chain arguments before uploading anything
+    let supply = (command == "publish").then(|| {
+        required(&args, "--supply").parse::<u64>().unwrap_or_else(|e| {
+            println!("--supply: {}", e);
+            exit(2);
+        })
+    });
+    let collection = (command == "publish").then(|| {
+        Pubkey::from_str(required(&args, "--collection")).unwrap_or_else(|e| {
+            println!("--collection: {}", e);
+            exit(2);
+        })
+    });
+    let price = (command == "publish").then(|| {
+        let price = price_arg(&args).unwrap_or_else(|| {
+            println!("Missing --price\n{}", USAGE);
+            exit(2);
+        });
+        (price, pubkey_arg(&args, "--payment-mint"))
+    });
+    let royalties = royalties_arg(&args).unwrap_or_else(|e| {
+        println!("{}", e);
+        exit(2);
+    });
+
+    let storage = storage::from_arg(required(&args, "--storage"), arg_value(&args, "--public-url").cloned())
+        .unwrap_or_else(|e| {
+            println!("Storage: {}", e);
+            exit(1);
+        });
+
+    let uri = package(&skin, storage.as_ref(), name, symbol, weapon).unwrap_or_else(|e| {
+        println!("Upload failed: {}", e);
+        exit(1);
+    });
+    println!("Metadata uri: {}", uri);
+
+    let (Some(supply), Some(collection), Some(price)) = (supply, collection, price) else {
+        return;
+    };
+
+    let creator = keypair(&args);
+    let rpc = rpc(&args);
+
+    match create_nft::create_nft(&rpc, &creator, &collection, name, symbol, &uri, supply, royalties, price) {
+        Ok((mint, signature)) => {
+            println!("Skin created, mint {}", mint);
+            println!("Transaction {}", signature);
+        }
+        Err(e) => {
+            println!("create_nft failed: {}", e);
+            exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageFormat, RgbImage};
+    use tempfile::TempDir;
+
+    use crate::storage::LocalDir;
+
+    use super::*;
+
+    const PUBLIC_URL: &str = "https://cdn.test/skins/";
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, image::Rgb([10, 20, 30])));
+        let mut bytes = Cursor::new(Vec::new());
+        img.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    //An albedo, a roughness and a preview
+    fn skin() -> (TempDir, SkinDir) {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("ak_a.png"), png(64, 64)).unwrap();
+        fs::write(dir.path().join("ak_r.png"), png(64, 64)).unwrap();
+        fs::write(dir.path().join("ak_p.png"), png(40, 30)).unwrap();
+
+        let skin = validate(dir.path(), DEFAULT_WEAPON);
+        (dir, skin)
+    }
+
+    //What package left in the storage dir
+    fn stored(store: &TempDir) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(store.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn read(store: &TempDir, uri: &str) -> Vec<u8> {
+        fs::read(store.path().join(uri.strip_prefix(PUBLIC_URL).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn packages_the_textures_and_a_json_the_client_accepts() {
+        let (_dir, skin) = skin();
+        let store = TempDir::new().unwrap();
+        let storage = LocalDir::new(store.path().to_path_buf(), Some(PUBLIC_URL.to_string())).unwrap();
+
+        let uri = package(&skin, &storage, "Cat", "CAT", DEFAULT_WEAPON).unwrap();
+
+        assert!(uri.starts_with(PUBLIC_URL) && uri.ends_with(".json"), "{}", uri);
+        let metadata = SkinMetadata::parse(&read(&store, &uri)).unwrap();
+        assert_eq!((metadata.name.as_str(), metadata.symbol.as_str()), ("Cat", "CAT"));
+        assert_eq!(metadata.schema_version, SCHEMA_VERSION);
+        assert_eq!(metadata.weapon, None);
+
+        let slots: Vec<&str> = metadata.textures.slots().iter().map(|(slot, _)| *slot).collect();
+        assert_eq!(slots, ["a", "r"]);
+        let sha256 = metadata.sha256.as_ref().unwrap();
+        for texture in &skin.textures {
+            let uri = metadata.textures.get(&texture.slot).unwrap();
+            assert_eq!(read(&store, uri), texture.bytes);
+            assert_eq!(sha256.get(&texture.slot), Some(&sha256_hex(&texture.bytes)));
+        }
+        assert_eq!(read(&store, metadata.image.as_ref().unwrap()), skin.preview.as_ref().unwrap().bytes);
+
+        //Albedo and roughness are the same bytes, so one file, then the preview and the json
+        assert_eq!(stored(&store).len(), 3);
+    }
+
+    #[test]
+    fn packaging_again_uploads_the_same_names() {
+        let (_dir, skin) = skin();
+        let store = TempDir::new().unwrap();
+        let storage = LocalDir::new(store.path().to_path_buf(), Some(PUBLIC_URL.to_string())).unwrap();
+
+        let first = package(&skin, &storage, "Cat", "CAT", DEFAULT_WEAPON).unwrap();
+        let files = stored(&store);
+
+        assert_eq!(package(&skin, &storage, "Cat", "CAT", DEFAULT_WEAPON).unwrap(), first);
+        assert_eq!(stored(&store), files);
+        assert_ne!(package(&skin, &storage, "Dog", "CAT", DEFAULT_WEAPON).unwrap(), first);
+    }
+
+    #[test]
+    fn never_uploads_a_json_the_client_would_skip() {
+        let (_dir, skin) = skin();
+
+        let store = TempDir::new().unwrap();
+        let storage = LocalDir::new(store.path().to_path_buf(), Some(PUBLIC_URL.to_string())).unwrap();
+        let error = package(&skin, &storage, "", "CAT", DEFAULT_WEAPON).unwrap_err();
+        assert!(error.contains("name is empty"), "{}", error);
+        assert!(stored(&store).iter().all(|name| !name.ends_with(".json")));
+
+        //file:// is only good to look at, the client can't fetch it
+        let store = TempDir::new().unwrap();
+        let storage = LocalDir::new(store.path().to_path_buf(), None).unwrap();
+        let error = package(&skin, &storage, "Cat", "CAT", DEFAULT_WEAPON).unwrap_err();
+        assert!(error.contains("is not an http(s), ipfs, ar or data uri"), "{}", error);
+        assert!(stored(&store).iter().all(|name| !name.ends_with(".json")));
+    }
+
+    #[test]
+    fn publishes_the_uri_it_packaged() {
+        let (_dir, skin) = skin();
+        let store = TempDir::new().unwrap();
+        let storage = storage::from_arg(store.path().to_str().unwrap(), Some(PUBLIC_URL.to_string())).unwrap();
+        let uri = package(&skin, storage.as_ref(), "Cat", "CAT", DEFAULT_WEAPON).unwrap();
+
+        let (creator, mint, collection) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction =
+            create_nft::create_instruction(&creator, &mint, &collection, "Cat", "CAT", &uri, 10, (0, vec![]), (5, None));
+
+        //Listed at its price in the same instruction, it is never free in between
+        let create_nft = nft_program::CreateNft {
+            creator,
+            mint,
+            collection_mint: collection,
+            name: "Cat".to_string(),
+            symbol: "CAT".to_string(),
+            metadata_uri: uri,
+            supply: 10,
+            seller_fee_basis_points: 0,
+            creators: vec![],
+            price: 5,
+            payment_mint: None,
+        };
+        assert_eq!(instruction, create_nft.instruction());
+    }
+}
@@ -0,0 +1,272 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::skin_schema::SLOTS;
+use crate::texture_validation::{inspect, sanitize, TextureFormat, TextureInfo};
+
+const EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "webp"];
+
+pub struct SkinTexture {
+    pub slot: String,
+    pub path: PathBuf,
+    pub bytes: Vec<u8>,
+    pub info: TextureInfo,
+}
+
+impl SkinTexture {
+    pub fn content_type(&self) -> &'static str {
+        content_type(self.info.format)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self.info.format {
+            TextureFormat::Png => "png",
+            TextureFormat::Jpeg => "jpg",
+            TextureFormat::WebP => "webp",
+            TextureFormat::Ktx2 => "ktx2",
+        }
+    }
+}
+
+/**
+ * A skin folder like skins/cat_ak, one <weapon>_<slot>.png per slot and <weapon>_p.png for the preview
+ * Every texture goes through the same checks the client does
+ */
+pub struct SkinDir {
+    pub textures: Vec<SkinTexture>,
+    pub preview: Option<SkinTexture>,
+    //Things the client copes with, but probably not what the creator wanted
+    pub warnings: Vec<String>,
+}
+
+impl SkinDir {
+    pub fn load(dir: &Path, weapon: &str) -> Result<SkinDir, Vec<String>> {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let mut textures = Vec::new();
+
+        if !dir.is_dir() {
+            return Err(vec![format!("{} is not a directory", dir.display())]);
+        }
+
+        for slot in SLOTS {
+            match SkinDir::load_slot(dir, weapon, slot) {
+                Ok(Some(texture)) => textures.push(texture),
+                Ok(None) => {}
+                Err(e) => errors.push(e),
+            }
+        }
+
+        let preview = match SkinDir::load_slot(dir, weapon, "p") {
+            Ok(preview) => preview,
+            Err(e) => {
+                errors.push(e);
+                None
+            }
+        };
+        if preview.is_none() {
+            warnings.push(format!("No {}_p preview, wallets will show no image", weapon));
+        }
+
+        if textures.is_empty() && errors.is_empty() {
+            errors.push(format!(
+                "No texture found, expected {}_<slot>.png with slot one of {}",
+                weapon,
+                SLOTS.join(", ")
+            ));
+        }
+        if textures.iter().any(|t| t.slot == "orm") {
+            let packed: Vec<&str> = textures
+                .iter()
+                .map(|t| t.slot.as_str())
+                .filter(|slot| ["ao", "r", "m"].contains(slot))
+                .collect();
+            if !packed.is_empty() {
+                errors.push(format!("orm already packs {}, remove one or the other", packed.join(", ")));
+            }
+        }
+
+        for texture in &textures {
+            let (width, height) = (texture.info.width, texture.info.height);
+            if !width.is_power_of_two() || !height.is_power_of_two() {
+                warnings.push(format!(
+                    "{} is {}x{}, the client scales it down to powers of two",
+                    texture.path.display(),
+                    width,
+                    height
+                ));
+            }
+        }
+        if let Some(first) = textures.first() {
+            for texture in &textures[1..] {
+                if (texture.info.width, texture.info.height) != (first.info.width, first.info.height) {
+                    warnings.push(format!(
+                        "{} is {}x{} but {} is {}x{}",
+                        texture.path.display(),
+                        texture.info.width,
+                        texture.info.height,
+                        first.path.display(),
+                        first.info.width,
+                        first.info.height
+                    ));
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(SkinDir { textures, preview, warnings })
+    }
+
+    //None when the slot has no file
+    fn load_slot(dir: &Path, weapon: &str, slot: &str) -> Result<Option<SkinTexture>, String> {
+        let Some(path) = EXTENSIONS
+            .iter()
+            .map(|ext| dir.join(format!("{}_{}.{}", weapon, slot, ext)))
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+
+        let bytes = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let info = inspect(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        if info.format == TextureFormat::Ktx2 {
+            return Err(format!("{}: KTX2 is not supported by the client yet", path.display()));
+        }
+        //The header can be fine and the data not
+        sanitize(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        Ok(Some(SkinTexture {
+            slot: slot.to_string(),
+            path,
+            bytes,
+            info,
+        }))
+    }
+}
+
+pub fn content_type(format: TextureFormat) -> &'static str {
+    match format {
+        TextureFormat::Png => "image/png",
+        TextureFormat::Jpeg => "image/jpeg",
+        TextureFormat::WebP => "image/webp",
+        TextureFormat::Ktx2 => "image/ktx2",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageFormat, RgbImage};
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn encode(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, image::Rgb([10, 20, 30])));
+        let mut bytes = Cursor::new(Vec::new());
+        img.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        encode(width, height, ImageFormat::Png)
+    }
+
+    //A skin folder holding these files
+    fn folder(files: &[(&str, Vec<u8>)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for (name, bytes) in files {
+            fs::write(dir.path().join(name), bytes).unwrap();
+        }
+        dir
+    }
+
+    fn errors(files: &[(&str, Vec<u8>)]) -> Vec<String> {
+        SkinDir::load(folder(files).path(), "ak").err().expect("the folder loaded")
+    }
+
+    #[test]
+    fn loads_the_slots_of_the_weapon_and_its_preview() {
+        let dir = folder(&[
+            ("ak_n.jpg", encode(64, 64, ImageFormat::Jpeg)),
+            ("ak_a.png", png(64, 64)),
+            ("ak_p.png", png(40, 30)),
+            ("m4_r.png", png(64, 64)),
+            ("notes.txt", b"not a texture".to_vec()),
+        ]);
+
+        let skin = SkinDir::load(dir.path(), "ak").unwrap();
+
+        let slots: Vec<&str> = skin.textures.iter().map(|t| t.slot.as_str()).collect();
+        assert_eq!(slots, ["a", "n"]);
+        assert_eq!(skin.textures[1].info.format, TextureFormat::Jpeg);
+        assert_eq!((skin.textures[1].content_type(), skin.textures[1].extension()), ("image/jpeg", "jpg"));
+        assert_eq!(skin.preview.map(|p| (p.info.width, p.info.height)), Some((40, 30)));
+        assert!(skin.warnings.is_empty(), "{:?}", skin.warnings);
+    }
+
+    #[test]
+    fn warns_about_what_the_client_copes_with() {
+        let dir = folder(&[("ak_a.png", png(48, 48)), ("ak_n.png", png(64, 64))]);
+
+        let warnings = SkinDir::load(dir.path(), "ak").unwrap().warnings;
+
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings[0].contains("No ak_p preview"));
+        assert!(warnings[1].contains("ak_a.png is 48x48, the client scales it down"));
+        assert!(warnings[2].contains("ak_n.png is 64x64 but"));
+    }
+
+    #[test]
+    fn refuses_what_the_client_would_not_load() {
+        let ktx2 = {
+            let mut bytes = b"\xABKTX 20\xBB\r\n\x1A\n".to_vec();
+            bytes.extend_from_slice(&[0; 8]);
+            bytes.extend_from_slice(&64u32.to_le_bytes());
+            bytes.extend_from_slice(&64u32.to_le_bytes());
+            bytes
+        };
+        //A fine header and the data cut short
+        let mut truncated = png(64, 64);
+        truncated.truncate(40);
+
+        let cases = [
+            (vec![], "No texture found"),
+            (vec![("ak_p.png", png(40, 30))], "No texture found"),
+            (vec![("ak_a.png", b"not a texture".to_vec())], "ak_a.png: Unknown texture format"),
+            (vec![("ak_a.png", vec![])], "ak_a.png: Unknown texture format"),
+            (vec![("ak_a.png", truncated)], "ak_a.png"),
+            (vec![("ak_a.png", ktx2)], "ak_a.png: KTX2 is not supported"),
+            (vec![("ak_orm.png", png(64, 64)), ("ak_ao.png", png(64, 64))], "orm already packs ao"),
+            (vec![("ak_a.png", png(64, 64)), ("ak_p.png", b"not a texture".to_vec())], "ak_p.png"),
+        ];
+
+        for (files, expected) in cases {
+            let names: Vec<&str> = files.iter().map(|(name, _)| *name).collect();
+            let errors = errors(&files);
+            assert!(errors.iter().any(|e| e.contains(expected)), "{:?}: {:?}", names, errors);
+        }
+    }
+
+    #[test]
+    fn reports_every_broken_texture_at_once() {
+        let errors = errors(&[("ak_a.png", vec![]), ("ak_n.png", b"junk".to_vec()), ("ak_r.png", png(64, 64))]);
+
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+    }
+
+    #[test]
+    fn a_missing_folder_is_not_a_skin() {
+        let dir = TempDir::new().unwrap();
+
+        let errors = SkinDir::load(&dir.path().join("missing"), "ak").err().unwrap();
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].ends_with("is not a directory"));
+    }
+}
@@ -0,0 +1,185 @@
+use std::fs;
+use std::path::PathBuf;
+
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+
+/**
+ * Where the textures and the json go, returns the uri the client will fetch
+ * Names are content addressed, uploading the same skin twice is harmless
+ */
+pub trait Storage {
+    fn put(&self, name: &str, bytes: &[u8], content_type: &str) -> Result<String, String>;
+}
+
+//Copies into a directory, served by anything at base_url
+pub struct LocalDir {
+    dir: PathBuf,
+    base_url: String,
+}
+
+impl LocalDir {
+    pub fn new(dir: PathBuf, base_url: Option<String>) -> Result<Self, String> {
+        fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+
+        //file:// uris are only good to look at, the client wants something it can fetch
+        let base_url = match base_url {
+            Some(url) => url,
+            None => format!(
+                "file://{}",
+                dir.canonicalize().map_err(|e| e.to_string())?.display()
+            ),
+        };
+
+        Ok(Self {
+            dir,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+impl Storage for LocalDir {
+    fn put(&self, name: &str, bytes: &[u8], _content_type: &str) -> Result<String, String> {
+        let path = self.dir.join(name);
+        fs::write(&path, bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        Ok(format!("{}/{}", self.base_url, name))
+    }
+}
+
+//PUT to <url>/<name>, a plain http server or a stand-in for a pinning service
+pub struct HttpStorage {
+    url: String,
+    http: Client,
+}
+
+impl HttpStorage {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            http: Client::new(),
+        }
+    }
+}
+
+impl Storage for HttpStorage {
+    fn put(&self, name: &str, bytes: &[u8], content_type: &str) -> Result<String, String> {
+        let url = format!("{}/{}", self.url, name);
+
+        let response = self
+            .http
+            .put(&url)
+            .header(CONTENT_TYPE, content_type)
+            .body(bytes.to_vec())
+            .send()
+            .map_err(|e| e.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!("{}: {}", url, response.status()));
+        }
+
+        Ok(url)
+    }
+}
+
+//An http(s) url is an HttpStorage, anything else a directory
+pub fn from_arg(spec: &str, public_url: Option<String>) -> Result<Box<dyn Storage>, String> {
+    if spec.starts_with("http://") || spec.starts_with("https://") {
+        return Ok(Box::new(HttpStorage::new(spec)));
+    }
+
+    Ok(Box::new(LocalDir::new(PathBuf::from(spec), public_url)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    //Answers one request with status, returns its request line, content type and body
+    fn serve_once(status: &str) -> (String, thread::JoinHandle<(String, String, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/skins/", listener.local_addr().unwrap());
+        let status = status.to_string();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let (mut content_type, mut length) = (String::new(), 0);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(": ").unwrap();
+                match name.to_ascii_lowercase().as_str() {
+                    "content-type" => content_type = value.to_string(),
+                    "content-length" => length = value.parse().unwrap(),
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+            (request.trim_end().to_string(), content_type, body)
+        });
+
+        (url, server)
+    }
+
+    #[test]
+    fn a_local_dir_is_served_at_its_public_url() {
+        let store = TempDir::new().unwrap();
+        let dir = store.path().join("skins");
+        let storage = LocalDir::new(dir.clone(), Some("https://cdn.test/skins/".to_string())).unwrap();
+
+        assert_eq!(storage.put("ab.png", b"png", "image/png").unwrap(), "https://cdn.test/skins/ab.png");
+        assert_eq!(fs::read(dir.join("ab.png")).unwrap(), b"png");
+    }
+
+    #[test]
+    fn a_local_dir_without_public_url_gives_file_uris() {
+        let store = TempDir::new().unwrap();
+        let storage = LocalDir::new(store.path().to_path_buf(), None).unwrap();
+
+        let uri = storage.put("ab.json", b"{}", "application/json").unwrap();
+
+        assert_eq!(uri, format!("file://{}/ab.json", store.path().canonicalize().unwrap().display()));
+    }
+
+    #[test]
+    fn puts_to_the_http_storage() {
+        let (url, server) = serve_once("201 Created");
+        let storage = from_arg(&url, None).unwrap();
+
+        let uri = storage.put("ab.png", b"png", "image/png").unwrap();
+
+        assert_eq!(uri, format!("{}ab.png", url));
+        let (request, content_type, body) = server.join().unwrap();
+        assert_eq!(request, "PUT /skins/ab.png HTTP/1.1");
+        assert_eq!(content_type, "image/png");
+        assert_eq!(body, b"png");
+    }
+
+    #[test]
+    fn a_refused_upload_is_an_error() {
+        let (url, server) = serve_once("403 Forbidden");
+        let storage = HttpStorage::new(&url);
+
+        let error = storage.put("ab.png", b"png", "image/png").unwrap_err();
+
+        assert!(error.contains("403"), "{}", error);
+        server.join().unwrap();
+    }
+}
@@ -7,10 +7,7 @@ import { getExplorerLink, getKeypairFromEnvironment } from "@solana-developers/h
 import idl from "./idl/nft.json";
 import { createUmi } from "@metaplex-foundation/umi-bundle-defaults";
 import Wallet from "@coral-xyz/anchor/dist/esm/nodewallet.js"
-import {
-    fetchMasterEditionFromSeeds,
-    findEditionMarkerPda,
-} from "@metaplex-foundation/mpl-token-metadata";
+import { findEditionMarkerPda } from "@metaplex-foundation/mpl-token-metadata";
 import { fromWeb3JsPublicKey } from "@metaplex-foundation/umi-web3js-adapters";
 
 const keypair = getKeypairFromEnvironment("WS_1");
@@ -33,12 +30,18 @@ async function buy_nft(mint: PublicKey) {
     const editionMintKeypair = Keypair.generate();
     const editionMint = editionMintKeypair.publicKey;
 
-    const masterEdition = await fetchMasterEditionFromSeeds(
-        umi,
-        { mint: fromWeb3JsPublicKey(mint) },
+    // Pays the treasury of the skin's listing, spl prices are left to the game client
+    const [listingPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("listing"), mint.toBuffer()],
+        program.programId
     );
+    const listing = await program.account.skinListing.fetch(listingPda);
+    if (listing.paymentMint) {
+        throw new Error("This skin is paid in an spl token");
+    }
 
-    const editionMarkerIx = Math.floor(Number(masterEdition.supply) / 248);
+    // The next edition follows the ones printed, not the supply that burns lower
+    const editionMarkerIx = Math.floor((Number(listing.editionsPrinted) + 1) / 248);
     const editionMarker = await findEditionMarkerPda(
         umi,
         {
@@ -47,12 +50,16 @@ async function buy_nft(mint: PublicKey) {
         },
     );
 
-    const tx = await program.methods.buyNft()
+    const tx = await program.methods.buyNft([], listing.price, listing.paymentMint)
     .accounts({
         buyer: keypair.publicKey,
         masterMint: mint,
         newMint: editionMint,
         editionMarker: editionMarker[0],
+        treasury: listing.treasury,
+        paymentMint: null,
+        buyerPaymentAccount: null,
+        treasuryPaymentAccount: null,
     })
     .signers([editionMintKeypair, keypair])
     .rpc({ skipPreflight: true });
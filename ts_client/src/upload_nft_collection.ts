@@ -70,6 +70,12 @@ async function upload_nft_to_collection(creator: PublicKey, collectionMint: Publ
         symbol,
         uri,
         new BN(5),
+        // No royalties, empty creators gives the whole share to the signer
+        0,
+        [],
+        // 0.01 SOL a print, paid to the creator
+        new BN(10_000_000),
+        null,
     )
     .accounts({
         creator: creator,
@@ -122,6 +128,7 @@ async function upload_metadata(name: String, symbol: String)
         properties: {
             files: [{type: "image/png", uri: IMG_URI}]
         },
+        schema_version : 2,
         textures : attributes,
         identifier : "fps+bq", //"magic field to say the nft is compatible with the game"
     };